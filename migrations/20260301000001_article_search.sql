@@ -0,0 +1,10 @@
+-- Full-text search over articles
+
+-- `simple` keeps the tokenizer language-neutral, the blog mixes Chinese and English
+ALTER TABLE articles ADD COLUMN search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(raw_content, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_articles_search_vector ON articles USING GIN (search_vector);
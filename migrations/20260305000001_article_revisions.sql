@@ -0,0 +1,17 @@
+-- Article revision history
+
+CREATE TABLE article_revisions (
+    id uuid primary key default gen_random_uuid(),
+    article_id uuid not null references articles (id) ON DELETE CASCADE,
+    -- null for revisions imported from articles that existed before history was kept
+    author_id uuid references users (id) ON DELETE SET NULL,
+    title varchar not null,
+    raw_content text not null,
+    create_time timestamp not null default current_timestamp
+);
+
+CREATE INDEX idx_article_revisions_article ON article_revisions (article_id, create_time);
+
+-- Keep the current text of every existing article as its first revision
+INSERT INTO article_revisions (article_id, title, raw_content, create_time)
+SELECT id, title, raw_content, modify_time FROM articles;
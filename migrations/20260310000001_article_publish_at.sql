@@ -0,0 +1,12 @@
+-- Scheduled publishing
+
+ALTER TABLE articles ADD COLUMN publish_at timestamp;
+
+CREATE INDEX idx_articles_publish_at ON articles (publish_at) WHERE published = false;
+
+Create or replace view article_with_tag as
+select a.id, a.title, a.raw_content, a.content, a.published, array_agg(c.id) as tags_id, array_agg(c.tag) as tags, a.create_time, a.modify_time, a.publish_at
+from articles a
+         left join article_tag_relation b on a.id=b.article_id
+         left join tags c on b.tag_id=c.id
+group by a.id, a.title, a.content, a.published, a.create_time, a.modify_time;
@@ -0,0 +1,32 @@
+-- Human-readable article urls
+
+-- Existing articles get their slug generated from the title on server start
+ALTER TABLE articles ADD COLUMN slug varchar UNIQUE;
+
+-- A new slug alone is not a modification, the backfill and the renames keep the modify time
+CREATE OR REPLACE FUNCTION update_modified_column()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF (NEW.title, NEW.raw_content, NEW.content, NEW.published, NEW.publish_at)
+        IS DISTINCT FROM (OLD.title, OLD.raw_content, OLD.content, OLD.published, OLD.publish_at) THEN
+        NEW.modify_time = now();
+    END IF;
+RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+-- Old slugs of renamed articles, kept so that published links keep working
+CREATE TABLE article_slug_history (
+    slug varchar primary key,
+    article_id uuid not null references articles (id) ON DELETE CASCADE,
+    create_time timestamp not null default current_timestamp
+);
+
+CREATE INDEX idx_article_slug_history_article ON article_slug_history (article_id);
+
+Create or replace view article_with_tag as
+select a.id, a.title, a.raw_content, a.content, a.published, array_agg(c.id) as tags_id, array_agg(c.tag) as tags, a.create_time, a.modify_time, a.publish_at, a.slug
+from articles a
+         left join article_tag_relation b on a.id=b.article_id
+         left join tags c on b.tag_id=c.id
+group by a.id, a.title, a.content, a.published, a.create_time, a.modify_time;
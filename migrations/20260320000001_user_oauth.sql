@@ -0,0 +1,22 @@
+-- Accounts on oauth providers linked to a user, replaces users.github
+
+CREATE TABLE user_oauth (
+    provider varchar not null,
+    -- Stable id of the account on the provider
+    subject varchar not null,
+    user_id uuid not null references users (id) ON DELETE CASCADE,
+    profile_url varchar,
+    create_time timestamp not null default current_timestamp,
+    primary key (provider, subject),
+    -- One account of each provider per user
+    unique (provider, user_id)
+);
+
+CREATE INDEX idx_user_oauth_user ON user_oauth (user_id);
+
+-- Github accounts used to be stored by profile url, the subject is changed
+-- to the account id on the next login
+INSERT INTO user_oauth (provider, subject, user_id, profile_url)
+SELECT 'github', github, id, github FROM users WHERE github IS NOT NULL;
+
+ALTER TABLE users DROP COLUMN github;
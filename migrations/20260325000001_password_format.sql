@@ -0,0 +1,3 @@
+-- Mark the format of the stored password hashes, new ones are `$argon2id$...` PHC strings
+-- and the legacy sha3 ones are replaced on the next successful login
+UPDATE users SET password = '$sha3-256$' || password WHERE password NOT LIKE '$%';
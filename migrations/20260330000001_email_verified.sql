@@ -0,0 +1,6 @@
+-- Users must verify their email before commenting
+
+ALTER TABLE users ADD COLUMN email_verified boolean NOT NULL DEFAULT false;
+
+-- Accounts created before the verification existed are trusted
+UPDATE users SET email_verified = true;
@@ -0,0 +1,19 @@
+-- Second factor of the login, RFC 6238 one-time passwords
+
+CREATE TABLE user_totp (
+    user_id uuid primary key references users (id) ON DELETE CASCADE,
+    -- Base32, authenticator apps need the plain secret
+    secret varchar not null,
+    -- False until the first code is checked
+    enabled boolean not null default false,
+    -- Last accepted step, a code can't be used twice
+    last_step bigint not null default 0,
+    create_time timestamp not null default current_timestamp
+);
+
+-- Single use codes for a lost authenticator, removed once used
+CREATE TABLE user_recovery_code (
+    user_id uuid not null references users (id) ON DELETE CASCADE,
+    code_hash varchar not null,
+    primary key (user_id, code_hash)
+);
@@ -0,0 +1,17 @@
+-- Append only record of sensitive actions
+
+CREATE TABLE audit_log (
+    id bigserial primary key,
+    -- null for actions of the server itself, e.g. a login lockout
+    actor_id uuid references users (id) ON DELETE SET NULL,
+    -- account at the time, kept when the user is deleted
+    actor varchar not null,
+    action varchar not null,
+    target varchar not null,
+    before jsonb,
+    after jsonb,
+    ip varchar,
+    create_time timestamp not null default current_timestamp
+);
+
+CREATE INDEX idx_audit_log_time ON audit_log (create_time);
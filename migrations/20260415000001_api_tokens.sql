@@ -0,0 +1,17 @@
+-- Personal tokens for scripts, sent as `Authorization: Bearer {token}`
+
+CREATE TABLE api_tokens (
+    id uuid primary key default gen_random_uuid(),
+    user_id uuid not null references users (id) ON DELETE CASCADE,
+    name varchar not null,
+    -- sha3 of the token, the token itself is only shown once
+    token_hash varchar not null unique,
+    -- start of the token, to tell them apart
+    token_prefix varchar not null,
+    scopes varchar[] not null,
+    expire_time timestamp,
+    last_used_time timestamp,
+    last_used_ip varchar,
+    create_time timestamp not null default current_timestamp,
+    unique (user_id, name)
+);
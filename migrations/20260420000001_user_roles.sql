@@ -0,0 +1,7 @@
+-- Named roles replace the `groups` smallint, their permissions are defined in `models::role`
+
+CREATE TYPE user_role AS ENUM ('admin', 'editor', 'moderator', 'reader');
+
+ALTER TABLE users ADD COLUMN role user_role not null default 'reader';
+UPDATE users SET role = 'admin' WHERE groups = 0;
+ALTER TABLE users DROP COLUMN groups;
@@ -0,0 +1,10 @@
+-- Replies keep the comment they answer, `depth` is 0 for the comments on the article itself
+
+ALTER TABLE comments
+    ADD COLUMN parent_id uuid references comments (id),
+    ADD COLUMN depth smallint not null default 0,
+    -- a deleted comment with replies stays as an empty placeholder
+    ADD COLUMN deleted boolean not null default false;
+
+CREATE INDEX idx_comments_parent_id ON comments (parent_id);
+CREATE INDEX idx_comments_article_id ON comments (article_id, create_time);
@@ -0,0 +1,10 @@
+-- Comments held for moderation are only shown to their author and the moderators
+
+CREATE TYPE comment_status AS ENUM ('pending', 'approved', 'rejected');
+
+ALTER TABLE comments
+    ADD COLUMN status comment_status not null default 'approved',
+    ADD COLUMN moderator_id uuid references users (id) ON DELETE SET NULL,
+    ADD COLUMN moderate_time timestamp;
+
+CREATE INDEX idx_comments_pending ON comments (create_time) WHERE status = 'pending';
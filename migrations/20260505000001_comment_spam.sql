@@ -0,0 +1,26 @@
+-- Rules of the admins run on every new comment, `pattern` is a plain word unless `is_regex`
+
+CREATE TYPE spam_action AS ENUM ('reject', 'moderate');
+
+CREATE TABLE spam_rules (
+    id uuid primary key default gen_random_uuid(),
+    pattern varchar not null unique,
+    is_regex boolean not null default false,
+    action spam_action not null default 'moderate',
+    create_time timestamp not null default current_timestamp
+);
+
+-- Naive Bayes classifier, learned from the decisions of the moderators:
+-- the number of spam and ham comments each token was seen in
+CREATE TABLE spam_tokens (
+    token varchar primary key,
+    spam bigint not null default 0,
+    ham bigint not null default 0
+);
+
+-- Comments already learned, a comment is only learned once
+CREATE TABLE spam_training (
+    comment_id uuid primary key,
+    spam boolean not null,
+    create_time timestamp not null default current_timestamp
+);
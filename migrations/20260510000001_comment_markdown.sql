@@ -0,0 +1,4 @@
+-- `comment` now holds the markdown written by the user and `comment_html` its sanitized rendering.
+-- The comments written before are html and have no rendering, they are sanitized when shown
+
+ALTER TABLE comments ADD COLUMN comment_html text;
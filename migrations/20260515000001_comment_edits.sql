@@ -0,0 +1,15 @@
+-- Comments can be edited, the text before each edit is kept
+
+ALTER TABLE comments ADD COLUMN edit_time timestamp;
+
+CREATE TABLE comment_revisions (
+    id uuid primary key default gen_random_uuid(),
+    comment_id uuid not null references comments (id) ON DELETE CASCADE,
+    -- who made the edit that replaced this text
+    editor_id uuid references users (id) ON DELETE SET NULL,
+    comment text not null,
+    comment_html text,
+    create_time timestamp not null default current_timestamp
+);
+
+CREATE INDEX idx_comment_revisions_comment ON comment_revisions (comment_id, create_time);
@@ -0,0 +1,8 @@
+-- Visitors without an account can comment when `comment.guest` is on, they give a name and an
+-- email of which only the sha256 is kept, for the Gravatar identicons
+
+ALTER TABLE comments ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE comments ADD COLUMN guest_name varchar;
+ALTER TABLE comments ADD COLUMN guest_email_hash varchar;
+ALTER TABLE comments ADD CONSTRAINT comments_author_check
+    CHECK ((user_id IS NULL) <> (guest_name IS NULL));
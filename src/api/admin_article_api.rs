@@ -1,33 +1,64 @@
 use salvo::{
-    Request, Response, Router,
+    Depot, Request, Response, Router,
     http::{StatusCode, StatusError},
     prelude::handler,
 };
 
 use crate::{
-    Routers,
-    api::{JsonErrResponse, JsonOkResponse, block_no_admin, size_add, size_reduce},
-    models::articles::{ArticleList, ArticlesWithTag, EditArticle, ModifyPublish, NewArticle},
+    Routers, USER_INFO,
+    api::{JsonErrResponse, JsonOkResponse, RequireAccess, audit, size_add, size_reduce},
+    models::{
+        article_revision::{ArticleRevision, RestoreRevision},
+        articles::{
+            ArticleList, ArticleSearch, ArticlesWithTag, EditArticle, ModifyPublish, NewArticle,
+        },
+        user::UserInfo,
+    },
     utils::{from_code, parse_json_body, parse_last_path, parse_query, set_json_response},
 };
 
 #[handler]
-async fn create_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn create_article(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<NewArticle>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let author_id = depot.get::<UserInfo>(USER_INFO).ok().unwrap().id;
 
     tokio::spawn(async { size_add().await });
-    set_json_response(res, 32, JsonOkResponse::status(body.insert().await));
+    match body.insert(author_id).await {
+        Some(id) => {
+            audit(req, depot, "article.create", format!("article:{}", id))
+                .after(ArticlesWithTag::query_without_article(id, true).await.ok())
+                .insert()
+                .await;
+            set_json_response(res, 32, JsonOkResponse::status(true))
+        }
+        None => set_json_response(res, 32, JsonOkResponse::status(false)),
+    }
     Ok(())
 }
 
 #[handler]
-async fn delete_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn delete_article(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let id = parse_last_path::<uuid::Uuid>(req)?;
+    let before = ArticlesWithTag::query_without_article(id, true).await.ok();
 
     match ArticlesWithTag::delete_with_id(id).await {
         Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "article.delete", format!("article:{}", id))
+                    .before(before)
+                    .insert()
+                    .await;
+            }
             tokio::spawn(async { size_reduce().await });
             set_json_response(res, 32, JsonOkResponse::ok(data))
         }
@@ -85,6 +116,19 @@ async fn admin_list_all_article(req: &mut Request, res: &mut Response) -> Result
     Ok(())
 }
 
+#[handler]
+async fn admin_search_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let keyword = parse_query::<String>(req, "q")?;
+    let limit = parse_query::<i64>(req, "limit")?;
+    let offset = parse_query::<i64>(req, "offset")?;
+
+    match ArticleSearch::search(&keyword, limit, offset, true).await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
 #[handler]
 async fn admin_list_all_unpublished(
     req: &mut Request,
@@ -101,33 +145,135 @@ async fn admin_list_all_unpublished(
 }
 
 #[handler]
-async fn edit_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn edit_article(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<EditArticle>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let author_id = depot.get::<UserInfo>(USER_INFO).ok().unwrap().id;
+    let id = body.id();
+    let before = ArticlesWithTag::query_without_article(id, true).await.ok();
 
-    match body.edit_article().await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+    match body.edit_article(author_id).await {
+        Ok(data) => {
+            // The content itself is kept in the revisions
+            audit(req, depot, "article.edit", format!("article:{}", id))
+                .before(before)
+                .after(ArticlesWithTag::query_without_article(id, true).await.ok())
+                .insert()
+                .await;
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn update_publish(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn list_revisions(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let id = parse_query::<uuid::Uuid>(req, "id")?;
+
+    match ArticleRevision::list(id).await {
+        Ok(data) => set_json_response(res, 128, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn view_revision(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let id = parse_query::<uuid::Uuid>(req, "id")?;
+
+    match ArticleRevision::view(id).await {
+        Ok(data) => set_json_response(res, 128, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn diff_revision(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let from = parse_query::<uuid::Uuid>(req, "from")?;
+    let to = parse_query::<uuid::Uuid>(req, "to")?;
+
+    match ArticleRevision::diff(from, to).await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn restore_revision(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<RestoreRevision>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let author_id = depot.get::<UserInfo>(USER_INFO).ok().unwrap().id;
+    let article_id = ArticleRevision::view(body.id)
+        .await
+        .ok()
+        .map(|r| r.article_id);
+    let before = match article_id {
+        Some(id) => ArticlesWithTag::query_without_article(id, true).await.ok(),
+        None => None,
+    };
+
+    match ArticleRevision::restore(body.id, author_id).await {
+        Ok(data) => {
+            if let Some(id) = article_id.filter(|_| data > 0) {
+                audit(req, depot, "article.restore", format!("article:{}", id))
+                    .before(before)
+                    .after(serde_json::json!({
+                        "revision": body.id,
+                        "article": ArticlesWithTag::query_without_article(id, true).await.ok(),
+                    }))
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn update_publish(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<ModifyPublish>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let id = body.id();
+    let before = ArticlesWithTag::query_without_article(id, true).await.ok();
 
     match ArticlesWithTag::publish_article(body).await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "article.publish", format!("article:{}", id))
+                    .before(before)
+                    .after(ArticlesWithTag::query_without_article(id, true).await.ok())
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn upload(req: &mut Request, res: &mut Response) {
+async fn upload(req: &mut Request, depot: &mut Depot, res: &mut Response) {
     // Set max size to 50MB
     req.set_secure_max_size(50 * 1024 * 1024);
     match req.try_files("files").await {
@@ -148,6 +294,10 @@ async fn upload(req: &mut Request, res: &mut Response) {
                     }
                 }
             }
+            audit(req, depot, "article.upload", msgs.join(","))
+                .after(&msgs)
+                .insert()
+                .await;
             set_json_response(res, 32, JsonOkResponse::ok(msgs))
         }
         Ok(None) => {
@@ -169,7 +319,7 @@ impl Routers for AdminArticle {
         vec![
             Router::new()
                 .path(PREFIX.to_owned() + "article")
-                .hoop(block_no_admin)
+                .hoop(RequireAccess("article"))
                 // http get /article/admin/view?id==4
                 .push(Router::new().path("admin/view").get(admin_view_article))
                 // http get /article/admin/navigation?id==4
@@ -190,6 +340,8 @@ impl Routers for AdminArticle {
                         .path("admin/view_all")
                         .get(admin_list_all_article),
                 )
+                // http get /article/admin/search q==keyword limit==5 offset==0
+                .push(Router::new().path("admin/search").get(admin_search_article))
                 // http get /article/admin/view_unpublished limit==5 offset==0
                 .push(
                     Router::new()
@@ -207,11 +359,23 @@ impl Routers for AdminArticle {
                 // http post /article/edit id:=1 title=something raw_content=something
                 .push(Router::new().path("edit").post(edit_article))
                 // http post /article/publish id:=5 published:=true
-                .push(Router::new().path("publish").post(update_publish)),
+                .push(Router::new().path("publish").post(update_publish))
+                // http get /article/admin/revisions?id==<article_id>
+                .push(Router::new().path("admin/revisions").get(list_revisions))
+                // http get /article/admin/revision?id==<revision_id>
+                .push(Router::new().path("admin/revision").get(view_revision))
+                // http get /article/admin/revision/diff?from==<revision_id>&to==<revision_id>
+                .push(Router::new().path("admin/revision/diff").get(diff_revision))
+                // http post /article/revision/restore id:=<revision_id>
+                .push(
+                    Router::new()
+                        .path("revision/restore")
+                        .post(restore_revision),
+                ),
             // http post /upload
             Router::new()
                 .path(PREFIX.to_owned() + "upload")
-                .hoop(block_no_admin)
+                .hoop(RequireAccess("article"))
                 .post(upload),
         ]
     }
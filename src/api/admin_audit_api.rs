@@ -0,0 +1,46 @@
+use salvo::{
+    Request, Response, Router,
+    http::{StatusCode, StatusError},
+    prelude::handler,
+};
+
+use crate::{
+    Routers,
+    api::{JsonErrResponse, JsonOkResponse, RequirePermission},
+    models::audit_log::{AuditFilter, AuditLog},
+    utils::{from_code, parse_query, set_json_response},
+};
+
+/// Entries returned by one page at most
+const MAX_LIMIT: i64 = 100;
+
+#[handler]
+async fn view_audit_log(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let limit = parse_query::<i64>(req, "limit")?.clamp(1, MAX_LIMIT);
+    let offset = parse_query::<i64>(req, "offset")?.max(0);
+    let filter = req
+        .parse_queries::<AuditFilter>()
+        .map_err(|_| from_code(StatusCode::BAD_REQUEST, "Query Param Is Incorrect"))?;
+
+    match AuditLog::query(&filter, limit, offset).await {
+        Ok(data) => set_json_response(res, 512, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+pub struct AdminAudit;
+
+impl Routers for AdminAudit {
+    fn build(self) -> Vec<Router> {
+        use crate::api::PREFIX;
+        vec![
+            // http {ip}/audit/view limit==20 offset==0 action==article target==article:uuid
+            // actor==admin from==2026-01-01 to==2026-01-31
+            Router::new()
+                .path(PREFIX.to_owned() + "audit/view")
+                .hoop(RequirePermission("audit:read"))
+                .get(view_audit_log),
+        ]
+    }
+}
@@ -2,7 +2,7 @@ use salvo::{Request, Response, Router, http::StatusError, prelude::handler};
 
 use crate::{
     Routers,
-    api::{JsonErrResponse, JsonOkResponse, block_no_admin},
+    api::{JsonErrResponse, JsonOkResponse, RequirePermission},
     db_wrapper::get_redis,
     models::articles::PublishedStatistics,
     utils::{parse_query, set_json_response},
@@ -38,12 +38,12 @@ impl Routers for ChartData {
             // http {ip}/article/month
             Router::new()
                 .path(PREFIX.to_owned() + "article/month")
-                .hoop(block_no_admin)
+                .hoop(RequirePermission("stats:read"))
                 .get(publish_by_month),
             // http {ip}/ip/view limit==5 offset==0
             Router::new()
                 .path(PREFIX.to_owned() + "ip/view")
-                .hoop(block_no_admin)
+                .hoop(RequirePermission("stats:read"))
                 .get(get_ip_chart),
         ]
     }
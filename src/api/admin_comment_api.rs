@@ -0,0 +1,212 @@
+use salvo::{
+    Depot, Request, Response, Router,
+    http::{StatusCode, StatusError},
+    prelude::handler,
+};
+
+use crate::{
+    Routers, USER_INFO,
+    api::{JsonErrResponse, JsonOkResponse, RequirePermission, audit},
+    models::{
+        comment::{CommentRevision, CommentStatus, ModerateComments, ModerationComment},
+        notify::UserNotify,
+        spam::{NewSpamRule, SpamBayes, SpamRule},
+        user::UserInfo,
+    },
+    utils::{from_code, parse_json_body, parse_last_path, parse_query, set_json_response},
+};
+
+#[handler]
+async fn view_queue(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let limit = parse_query::<i64>(req, "limit")?;
+    let offset = parse_query::<i64>(req, "offset")?;
+    let status = match req.query::<String>("status").as_deref() {
+        None | Some("pending") => CommentStatus::Pending,
+        Some("approved") => CommentStatus::Approved,
+        Some("rejected") => CommentStatus::Rejected,
+        Some(_) => return Err(from_code(StatusCode::BAD_REQUEST, "Unknown status")),
+    };
+
+    match ModerationComment::list(status, limit, offset).await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn view_revisions(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let id = parse_last_path::<uuid::Uuid>(req)?;
+
+    match CommentRevision::list(id).await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+/// Apply the decision, learn it, then tell the authors and, for the approved replies,
+/// whom they answer
+async fn moderate(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    status: CommentStatus,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<ModerateComments>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let moderator = depot.get::<UserInfo>(USER_INFO).ok().unwrap().clone();
+    let (action, notify_type) = match status {
+        CommentStatus::Approved => ("comment.approve", "approved"),
+        _ => ("comment.reject", "rejected"),
+    };
+
+    match body.decide(moderator.id, status).await {
+        Ok(decided) => {
+            for comment in &decided {
+                audit(req, depot, action, format!("comment:{}", comment.id))
+                    .after(serde_json::json!({ "status": status }))
+                    .insert()
+                    .await;
+                if let Err(e) = SpamBayes::train(
+                    comment.id,
+                    &comment.comment,
+                    status == CommentStatus::Rejected,
+                )
+                .await
+                {
+                    tracing::info!("can't train spam classifier with {}", e);
+                }
+                if let Some(user_id) = comment.user_id {
+                    let decision_notify = UserNotify {
+                        user_id,
+                        send_user_name: moderator.nickname.clone(),
+                        article_id: comment.article_id,
+                        article_title: comment.article_title.clone(),
+                        notify_type: notify_type.into(),
+                    };
+                    decision_notify.cache().await;
+                }
+                if let Some(reply_user_id) = comment.reply_user_id
+                    && status == CommentStatus::Approved
+                    && Some(reply_user_id) != comment.user_id
+                {
+                    let reply_notify = UserNotify {
+                        user_id: reply_user_id,
+                        send_user_name: comment.nickname.clone(),
+                        article_id: comment.article_id,
+                        article_title: comment.article_title.clone(),
+                        notify_type: "reply".into(),
+                    };
+                    reply_notify.cache().await;
+                }
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(decided.len()))
+        }
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn approve(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    moderate(req, depot, res, CommentStatus::Approved).await
+}
+
+#[handler]
+async fn reject(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    moderate(req, depot, res, CommentStatus::Rejected).await
+}
+
+#[handler]
+async fn view_rules(res: &mut Response) {
+    match SpamRule::list().await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+}
+
+#[handler]
+async fn new_rule(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<NewSpamRule>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    match body.insert().await {
+        Ok(id) => {
+            audit(req, depot, "spam.rule.create", format!("spam_rule:{}", id))
+                .after(&body)
+                .insert()
+                .await;
+            set_json_response(res, 32, JsonOkResponse::ok(id))
+        }
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn delete_rule(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let id = parse_last_path::<uuid::Uuid>(req)?;
+
+    match SpamRule::delete(id).await {
+        Ok(num) => {
+            if num > 0 {
+                audit(req, depot, "spam.rule.delete", format!("spam_rule:{}", id))
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::status(num > 0))
+        }
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+pub struct AdminComment;
+
+impl Routers for AdminComment {
+    fn build(self) -> Vec<Router> {
+        use crate::api::PREFIX;
+        vec![
+            Router::new()
+                .path(PREFIX.to_owned() + "moderation")
+                .hoop(RequirePermission("comment:moderate"))
+                // http get {ip}/moderation/view limit==10 offset==0 status==pending
+                .push(Router::new().path("view").get(view_queue))
+                // http post {ip}/moderation/approve ids:='["uuid"]'
+                .push(Router::new().path("approve").post(approve))
+                // http post {ip}/moderation/reject ids:='["uuid"]'
+                .push(Router::new().path("reject").post(reject))
+                // http get {ip}/moderation/revisions/{uuid}
+                .push(Router::new().path("revisions/{id}").get(view_revisions))
+                .push(
+                    Router::new()
+                        .path("rules")
+                        // http get {ip}/moderation/rules/view
+                        .push(Router::new().path("view").get(view_rules))
+                        // http post {ip}/moderation/rules/new pattern=casino is_regex:=false action=reject
+                        .push(Router::new().path("new").post(new_rule))
+                        // http post {ip}/moderation/rules/delete/{uuid}
+                        .push(Router::new().path("delete/{id}").post(delete_rule)),
+                ),
+        ]
+    }
+}
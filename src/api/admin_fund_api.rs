@@ -1,13 +1,13 @@
 use http_body_util::BodyExt;
 use salvo::{
-    Request, Response, Router,
+    Depot, Request, Response, Router,
     http::{StatusCode, StatusError},
     prelude::handler,
 };
 
 use crate::{
     Routers,
-    api::{JsonErrResponse, JsonOkResponse, block_no_admin},
+    api::{JsonErrResponse, JsonOkResponse, RequireAccess, audit},
     models::fund::{
         BatchUpdateEntry, FundEntry, FundPortfolio, NewFundEntry, NewPortfolio, PortfolioSimple,
         PortfolioWithEntriesSimple, UpdateFundEntry, UpdatePortfolio,
@@ -41,37 +41,86 @@ async fn get_portfolio(req: &mut Request, res: &mut Response) -> Result<(), Stat
 }
 
 #[handler]
-async fn create_portfolio(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn create_portfolio(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<NewPortfolio>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
 
     match FundPortfolio::create(body).await {
-        Ok(id) => set_json_response(res, 64, JsonOkResponse::ok(id)),
+        Ok(id) => {
+            audit(
+                req,
+                depot,
+                "fund.portfolio.create",
+                format!("fund_portfolio:{}", id),
+            )
+            .after(FundPortfolio::get_by_id(id).await.ok())
+            .insert()
+            .await;
+            set_json_response(res, 64, JsonOkResponse::ok(id))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn update_portfolio(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn update_portfolio(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<UpdatePortfolio>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let id = body.id;
+    let before = FundPortfolio::get_by_id(id).await.ok();
 
     match FundPortfolio::update(body).await {
-        Ok(_) => set_json_response(res, 32, JsonOkResponse::status(true)),
+        Ok(_) => {
+            audit(
+                req,
+                depot,
+                "fund.portfolio.update",
+                format!("fund_portfolio:{}", id),
+            )
+            .before(before)
+            .after(FundPortfolio::get_by_id(id).await.ok())
+            .insert()
+            .await;
+            set_json_response(res, 32, JsonOkResponse::status(true))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn delete_portfolio(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn delete_portfolio(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let id = parse_last_path::<i32>(req)?;
+    let before = PortfolioWithEntriesSimple::get(id).await.ok();
 
     match FundPortfolio::delete(id).await {
-        Ok(_) => set_json_response(res, 32, JsonOkResponse::status(true)),
+        Ok(_) => {
+            audit(
+                req,
+                depot,
+                "fund.portfolio.delete",
+                format!("fund_portfolio:{}", id),
+            )
+            .before(before)
+            .insert()
+            .await;
+            set_json_response(res, 32, JsonOkResponse::status(true))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
@@ -91,7 +140,11 @@ async fn list_entries(req: &mut Request, res: &mut Response) -> Result<(), Statu
 }
 
 #[handler]
-async fn create_entry(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn create_entry(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     // Read body once, then try to deserialize as array or single object
     let body_bytes = {
         let body = req.take_body();
@@ -107,7 +160,10 @@ async fn create_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
         let mut ids: Vec<i32> = Vec::with_capacity(list.len());
         for item in list {
             match FundEntry::create(item).await {
-                Ok(id) => ids.push(id),
+                Ok(id) => {
+                    audit_entry(req, depot, "fund.entry.create", id, None).await;
+                    ids.push(id)
+                }
                 Err(e) => {
                     return {
                         let _: () = set_json_response(res, 64, JsonErrResponse::err(e));
@@ -123,7 +179,10 @@ async fn create_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
     // Try single object
     if let Ok(item) = serde_json::from_slice::<NewFundEntry>(&body_bytes) {
         match FundEntry::create(item).await {
-            Ok(id) => set_json_response(res, 64, JsonOkResponse::ok(id)),
+            Ok(id) => {
+                audit_entry(req, depot, "fund.entry.create", id, None).await;
+                set_json_response(res, 64, JsonOkResponse::ok(id))
+            }
             Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
         }
         return Ok(());
@@ -134,7 +193,11 @@ async fn create_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
 }
 
 #[handler]
-async fn update_entry(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn update_entry(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     // Read body once and attempt to deserialize as array or single object
     let body_bytes = {
         let body = req.take_body();
@@ -148,8 +211,13 @@ async fn update_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
     if let Ok(list) = serde_json::from_slice::<Vec<UpdateFundEntry>>(&body_bytes) {
         let mut portfolio_id: Option<i32> = None;
         for item in list {
+            let id = item.id;
+            let before = FundEntry::get_by_id(id).await.ok();
             match FundEntry::update(item).await {
-                Ok(pid) => portfolio_id = Some(pid),
+                Ok(pid) => {
+                    audit_entry(req, depot, "fund.entry.update", id, before).await;
+                    portfolio_id = Some(pid)
+                }
                 Err(e) => {
                     set_json_response(res, 64, JsonErrResponse::err(e));
                     return Ok(());
@@ -163,8 +231,13 @@ async fn update_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
     }
 
     if let Ok(item) = serde_json::from_slice::<UpdateFundEntry>(&body_bytes) {
+        let id = item.id;
+        let before = FundEntry::get_by_id(id).await.ok();
         match FundEntry::update(item).await {
-            Ok(portfolio_id) => set_json_response(res, 64, JsonOkResponse::ok(portfolio_id)),
+            Ok(portfolio_id) => {
+                audit_entry(req, depot, "fund.entry.update", id, before).await;
+                set_json_response(res, 64, JsonOkResponse::ok(portfolio_id))
+            }
             Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
         }
         return Ok(());
@@ -174,17 +247,48 @@ async fn update_entry(req: &mut Request, res: &mut Response) -> Result<(), Statu
 }
 
 #[handler]
-async fn delete_entry(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn delete_entry(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     // Single delete via path parameter
     let id = parse_last_path::<i32>(req)?;
+    let before = FundEntry::get_by_id(id).await.ok();
 
     match FundEntry::delete(id).await {
-        Ok(portfolio_id) => set_json_response(res, 64, JsonOkResponse::ok(portfolio_id)),
+        Ok(portfolio_id) => {
+            audit(
+                req,
+                depot,
+                "fund.entry.delete",
+                format!("fund_entry:{}", id),
+            )
+            .before(before)
+            .insert()
+            .await;
+            set_json_response(res, 64, JsonOkResponse::ok(portfolio_id))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
+/// The entry is read again to record its state after the action
+async fn audit_entry(
+    req: &mut Request,
+    depot: &mut Depot,
+    action: &'static str,
+    id: i32,
+    before: Option<FundEntry>,
+) {
+    audit(req, depot, action, format!("fund_entry:{}", id))
+        .before(before)
+        .after(FundEntry::get_by_id(id).await.ok())
+        .insert()
+        .await;
+}
+
 #[derive(serde::Deserialize)]
 struct BatchUpdateRequest {
     portfolio_id: i32,
@@ -198,26 +302,62 @@ struct BatchUpdateOrderRequest {
 }
 
 #[handler]
-async fn batch_update_amounts(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn batch_update_amounts(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<BatchUpdateRequest>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
-
-    match FundEntry::batch_update_amounts(body.portfolio_id, body.updates).await {
-        Ok(_) => set_json_response(res, 32, JsonOkResponse::status(true)),
+    let portfolio_id = body.portfolio_id;
+    let before = FundEntry::list_by_portfolio(portfolio_id).await.ok();
+
+    match FundEntry::batch_update_amounts(portfolio_id, body.updates).await {
+        Ok(_) => {
+            audit(
+                req,
+                depot,
+                "fund.entry.batch_amounts",
+                format!("fund_portfolio:{}", portfolio_id),
+            )
+            .before(before)
+            .after(FundEntry::list_by_portfolio(portfolio_id).await.ok())
+            .insert()
+            .await;
+            set_json_response(res, 32, JsonOkResponse::status(true))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn batch_update_order(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn batch_update_order(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<BatchUpdateOrderRequest>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
-
-    match FundEntry::batch_update_order(body.portfolio_id, body.updates).await {
-        Ok(_) => set_json_response(res, 32, JsonOkResponse::status(true)),
+    let portfolio_id = body.portfolio_id;
+    let before = FundEntry::list_by_portfolio(portfolio_id).await.ok();
+
+    match FundEntry::batch_update_order(portfolio_id, body.updates).await {
+        Ok(_) => {
+            audit(
+                req,
+                depot,
+                "fund.entry.batch_order",
+                format!("fund_portfolio:{}", portfolio_id),
+            )
+            .before(before)
+            .after(FundEntry::list_by_portfolio(portfolio_id).await.ok())
+            .insert()
+            .await;
+            set_json_response(res, 32, JsonOkResponse::status(true))
+        }
         Err(e) => set_json_response(res, 64, JsonErrResponse::err(e)),
     }
     Ok(())
@@ -233,7 +373,7 @@ impl Routers for AdminFund {
         vec![
             Router::new()
                 .path(PREFIX.to_owned() + "fund")
-                .hoop(block_no_admin)
+                .hoop(RequireAccess("fund"))
                 // Portfolio routes
                 // GET /api/v1/fund/portfolios - list all portfolios
                 .push(Router::new().path("portfolios").get(list_portfolios))
@@ -1,19 +1,23 @@
 use salvo::{
-    Request, Response, Router,
+    Depot, Request, Response, Router,
     http::{StatusCode, StatusError},
     prelude::handler,
 };
 
 use crate::{
     Routers,
-    api::block_no_admin,
-    api::{JsonErrResponse, JsonOkResponse},
+    api::RequireAccess,
+    api::{JsonErrResponse, JsonOkResponse, audit},
     models::tag::{TagCount, Tags},
     utils::{from_code, parse_json_body, parse_last_path, parse_query, set_json_response},
 };
 
 #[handler]
-async fn create_tag(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn create_tag(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     #[derive(serde::Deserialize, serde::Serialize)]
     pub struct NewTag {
         tag: String,
@@ -23,18 +27,37 @@ async fn create_tag(req: &mut Request, res: &mut Response) -> Result<(), StatusE
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
 
     match Tags::insert(&body.tag).await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+        Ok(id) => {
+            audit(req, depot, "tag.create", format!("tag:{}", id))
+                .after(&body)
+                .insert()
+                .await;
+            set_json_response(res, 64, JsonOkResponse::ok(id))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn delete_tag(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn delete_tag(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let id = parse_last_path::<uuid::Uuid>(req)?;
+    let before = Tags::query_name(id).await.ok();
 
     match Tags::delete_tag(id).await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "tag.delete", format!("tag:{}", id))
+                    .before(serde_json::json!({ "tag": before }))
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
@@ -53,13 +76,27 @@ async fn view_tag(req: &mut Request, res: &mut Response) -> Result<(), StatusErr
 }
 
 #[handler]
-async fn edit_tag(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn edit_tag(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<Tags>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let before = Tags::query_name(body.id()).await.ok();
 
     match body.edit_tag().await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "tag.edit", format!("tag:{}", body.id()))
+                    .before(serde_json::json!({ "tag": before }))
+                    .after(&body)
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
@@ -73,7 +110,7 @@ impl Routers for Tag {
         vec![
             Router::new()
                 .path(PREFIX.to_owned() + "tag")
-                .hoop(block_no_admin)
+                .hoop(RequireAccess("tag"))
                 // http get {ip}/tag/view limit==5 offset==0
                 .push(Router::new().path("view").get(view_tag))
                 // http post {ip}/tag/new tag="Rust"
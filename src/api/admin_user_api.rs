@@ -1,22 +1,40 @@
 use salvo::{
-    Request, Response, Router,
+    Depot, Request, Response, Router,
     http::{StatusCode, StatusError},
     prelude::handler,
 };
 
 use crate::{
     Routers,
-    api::{JsonErrResponse, JsonOkResponse, block_no_admin},
-    models::user::{ChangePermission, DisabledUser, UserInfo},
+    api::{JsonErrResponse, JsonOkResponse, RequireAccess, audit},
+    models::{
+        login_throttle::{ClearLockout, LoginThrottle},
+        role::RoleView,
+        session::{RevokeUserSessions, Session},
+        user::{ChangeRole, DisabledUser, UserInfo, VerifiedUser},
+    },
     utils::{from_code, parse_json_body, parse_last_path, parse_query, set_json_response},
 };
 
 #[handler]
-async fn delete_user(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn delete_user(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let id = parse_last_path::<uuid::Uuid>(req)?;
+    let before = UserInfo::view_user(id).await.ok();
 
     match UserInfo::delete(id).await {
-        Ok(num) => set_json_response(res, 32, JsonOkResponse::ok(num)),
+        Ok(num) => {
+            if num > 0 {
+                audit(req, depot, "user.delete", format!("user:{}", id))
+                    .before(before)
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(num))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
 
@@ -37,31 +55,148 @@ async fn view_user_list(req: &mut Request, res: &mut Response) -> Result<(), Sta
 }
 
 #[handler]
-async fn change_permission(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
-    let body = parse_json_body::<ChangePermission>(req)
+async fn view_roles(res: &mut Response) {
+    set_json_response(res, 64, JsonOkResponse::ok(RoleView::all()))
+}
+
+#[handler]
+async fn change_role(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<ChangeRole>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let id = body.id;
+    let before = UserInfo::view_user(id).await.ok();
 
-    match UserInfo::change_permission(body).await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+    match UserInfo::change_role(body).await {
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "user.role", format!("user:{}", id))
+                    .before(before)
+                    .after(UserInfo::view_user(id).await.ok())
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
 #[handler]
-async fn change_disabled(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+async fn change_disabled(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
     let body = parse_json_body::<DisabledUser>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let id = body.id;
+    let before = UserInfo::view_user(id).await.ok();
 
     match UserInfo::disabled_user(body).await {
-        Ok(data) => set_json_response(res, 32, JsonOkResponse::ok(data)),
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "user.disable", format!("user:{}", id))
+                    .before(before)
+                    .after(UserInfo::view_user(id).await.ok())
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn change_verified(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<VerifiedUser>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let id = body.id;
+    let before = UserInfo::view_user(id).await.ok();
+
+    match UserInfo::verify_email(body).await {
+        Ok(data) => {
+            if data > 0 {
+                audit(req, depot, "user.verify", format!("user:{}", id))
+                    .before(before)
+                    .after(UserInfo::view_user(id).await.ok())
+                    .insert()
+                    .await;
+            }
+            set_json_response(res, 32, JsonOkResponse::ok(data))
+        }
         Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
     }
     Ok(())
 }
 
+#[handler]
+async fn revoke_sessions(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<RevokeUserSessions>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    let revoked = Session::revoke_all(body.id, None).await;
+    audit(
+        req,
+        depot,
+        "user.revoke_sessions",
+        format!("user:{}", body.id),
+    )
+    .after(serde_json::json!({ "revoked": revoked }))
+    .insert()
+    .await;
+    set_json_response(res, 32, JsonOkResponse::ok(revoked));
+    Ok(())
+}
+
+#[handler]
+async fn view_lockouts(res: &mut Response) {
+    set_json_response(res, 256, JsonOkResponse::ok(LoginThrottle::list().await));
+}
+
+#[handler]
+async fn clear_lockout(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<ClearLockout>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    let cleared = LoginThrottle::clear(&body).await;
+    if cleared {
+        audit(
+            req,
+            depot,
+            "user.clear_lockout",
+            format!("{}:{}", body.kind, body.key),
+        )
+        .insert()
+        .await;
+    }
+    set_json_response(res, 32, JsonOkResponse::status(cleared));
+    Ok(())
+}
+
 pub struct AdminUser;
 impl Routers for AdminUser {
     fn build(self) -> Vec<Router> {
@@ -69,7 +204,7 @@ impl Routers for AdminUser {
         vec![
             Router::new()
                 .path(PREFIX.to_owned() + "user")
-                .hoop(block_no_admin)
+                .hoop(RequireAccess("user"))
                 // http get {ip}/user/view_all limit==5 offset==0
                 .push(Router::new().path("view_all").get(view_user_list))
                 // http post {ip}/user/delete/uuid
@@ -78,10 +213,28 @@ impl Routers for AdminUser {
                         .path(r"delete/{id|[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}}")
                         .post(delete_user),
                 )
-                // http post {ip}/user/permission id:=uuid permission:=0
-                .push(Router::new().path("permission").post(change_permission))
+                // http get {ip}/user/roles
+                .push(Router::new().path("roles").get(view_roles))
+                // http post {ip}/user/role id:=uuid role=editor
+                .push(Router::new().path("role").post(change_role))
                 // http post {ip}/user/permission id:=uuid disabled:=1
-                .push(Router::new().path("delete/disable").post(change_disabled)),
+                .push(Router::new().path("delete/disable").post(change_disabled))
+                // http post {ip}/user/verify id:=uuid verified:=true
+                .push(Router::new().path("verify").post(change_verified))
+                // http post {ip}/user/sessions/revoke_all id:=uuid
+                .push(
+                    Router::new()
+                        .path("sessions/revoke_all")
+                        .post(revoke_sessions),
+                )
+                // http get {ip}/user/lockouts
+                .push(
+                    Router::new()
+                        .path("lockouts")
+                        .get(view_lockouts)
+                        // http post {ip}/user/lockouts/clear kind=account key=xxx
+                        .push(Router::new().path("clear").post(clear_lockout)),
+                ),
         ]
     }
 }
@@ -0,0 +1,324 @@
+use atom_syndication::{
+    CategoryBuilder, ContentBuilder, EntryBuilder, FeedBuilder, FixedDateTime, LinkBuilder,
+};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use rss::{CategoryBuilder as RssCategoryBuilder, ChannelBuilder, GuidBuilder, ItemBuilder};
+use salvo::{
+    Request, Response, Router,
+    http::{
+        ResBody, StatusCode, StatusError,
+        header::{self, HeaderValue},
+    },
+    prelude::handler,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    Routers,
+    api::JsonErrResponse,
+    config::get_config,
+    models::{articles::ArticlesWithTag, tag::Tags},
+    utils::{from_code, set_json_response, site_url},
+    web::Cache,
+};
+
+/// What a feed is about, shared by all the formats
+struct FeedMeta {
+    title: String,
+    description: String,
+    /// Path of the page the feed belongs to
+    home_path: String,
+    /// Paths of the feed itself in atom and json format
+    atom_path: String,
+    json_path: String,
+}
+
+impl FeedMeta {
+    fn tag(id: Uuid, name: &str) -> Self {
+        let site = &get_config().site;
+        FeedMeta {
+            title: format!("{} - {}", site.title, name),
+            description: format!("{} Articles tagged {}.", site.description, name),
+            home_path: format!("/tag/{}", id),
+            atom_path: format!("/tag/{}/atom.xml", id),
+            json_path: format!("/tag/{}/feed.json", id),
+        }
+    }
+
+    fn site() -> Self {
+        let site = &get_config().site;
+        FeedMeta {
+            title: site.title.clone(),
+            description: site.description.clone(),
+            home_path: "/".to_string(),
+            atom_path: "/atom.xml".to_string(),
+            json_path: "/feed.json".to_string(),
+        }
+    }
+}
+
+fn absolute_url(path: &str) -> String {
+    format!("{}{}", site_url(), path)
+}
+
+fn article_url(article: &ArticlesWithTag) -> String {
+    match article.slug {
+        Some(ref slug) => absolute_url(&format!("/article/{}", slug)),
+        None => absolute_url(&format!("/article/{}", article.id)),
+    }
+}
+
+/// Times are stored as utc without timezone
+fn utc(time: &NaiveDateTime) -> FixedDateTime {
+    Utc.from_utc_datetime(time).fixed_offset()
+}
+
+/// The newest modify time of the articles, or now if there are none
+fn last_updated(articles: &[ArticlesWithTag]) -> FixedDateTime {
+    articles
+        .iter()
+        .map(|article| article.modify_time)
+        .max()
+        .map(|time| utc(&time))
+        .unwrap_or_else(|| Utc::now().fixed_offset())
+}
+
+fn set_feed_response(res: &mut Response, body: Cache, content_type: &'static str) {
+    res.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    res.body(ResBody::Once(body.into_inner()));
+    res.status_code(StatusCode::OK);
+}
+
+fn render_rss(res: &mut Response, meta: &FeedMeta, articles: Vec<ArticlesWithTag>) {
+    let updated = last_updated(&articles).to_rfc2822();
+    let items = articles
+        .into_iter()
+        .map(|article| {
+            let link = article_url(&article);
+            ItemBuilder::default()
+                .title(article.title)
+                .link(link.clone())
+                .guid(GuidBuilder::default().value(link).permalink(true).build())
+                .description(article.content)
+                .categories(
+                    article
+                        .tags
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|tag| RssCategoryBuilder::default().name(tag).build())
+                        .collect::<Vec<_>>(),
+                )
+                .pub_date(utc(&article.create_time).to_rfc2822())
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    let channel = ChannelBuilder::default()
+        .title(meta.title.clone())
+        .link(absolute_url(&meta.home_path))
+        .description(meta.description.clone())
+        .last_build_date(updated)
+        .items(items)
+        .build();
+
+    let mut bytes = Cache::new();
+    channel.write_to(&mut bytes).unwrap();
+    set_feed_response(res, bytes, "application/rss+xml; charset=utf-8");
+}
+
+fn render_atom(res: &mut Response, meta: &FeedMeta, articles: Vec<ArticlesWithTag>) {
+    let updated = last_updated(&articles);
+    let entries = articles
+        .into_iter()
+        .map(|article| {
+            let link = article_url(&article);
+            EntryBuilder::default()
+                .title(article.title)
+                .id(format!("urn:uuid:{}", article.id))
+                .link(
+                    LinkBuilder::default()
+                        .href(link)
+                        .rel("alternate")
+                        .mime_type(Some("text/html".to_string()))
+                        .build(),
+                )
+                .published(Some(utc(&article.create_time)))
+                .updated(utc(&article.modify_time))
+                .categories(
+                    article
+                        .tags
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|tag| CategoryBuilder::default().term(tag).build())
+                        .collect::<Vec<_>>(),
+                )
+                .content(Some(
+                    ContentBuilder::default()
+                        .value(Some(article.content))
+                        .content_type(Some("html".to_string()))
+                        .build(),
+                ))
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    let feed = FeedBuilder::default()
+        .title(meta.title.clone())
+        .subtitle(Some(meta.description.clone().into()))
+        .id(absolute_url(&meta.atom_path))
+        .updated(updated)
+        .links(vec![
+            LinkBuilder::default()
+                .href(absolute_url(&meta.atom_path))
+                .rel("self")
+                .mime_type(Some("application/atom+xml".to_string()))
+                .build(),
+            LinkBuilder::default()
+                .href(absolute_url(&meta.home_path))
+                .rel("alternate")
+                .mime_type(Some("text/html".to_string()))
+                .build(),
+        ])
+        .entries(entries)
+        .build();
+
+    let mut bytes = Cache::new();
+    feed.write_to(&mut bytes).unwrap();
+    set_feed_response(res, bytes, "application/atom+xml; charset=utf-8");
+}
+
+/// [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/)
+#[derive(Serialize)]
+struct JsonFeed {
+    version: &'static str,
+    title: String,
+    description: String,
+    home_page_url: String,
+    feed_url: String,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    title: String,
+    content_html: String,
+    date_published: String,
+    date_modified: String,
+    tags: Vec<String>,
+}
+
+fn render_json_feed(res: &mut Response, meta: &FeedMeta, articles: Vec<ArticlesWithTag>) {
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: meta.title.clone(),
+        description: meta.description.clone(),
+        home_page_url: absolute_url(&meta.home_path),
+        feed_url: absolute_url(&meta.json_path),
+        items: articles
+            .into_iter()
+            .map(|article| JsonFeedItem {
+                id: article.id.to_string(),
+                url: article_url(&article),
+                title: article.title,
+                content_html: article.content,
+                date_published: utc(&article.create_time).to_rfc3339(),
+                date_modified: utc(&article.modify_time).to_rfc3339(),
+                tags: article.tags.unwrap_or_default(),
+            })
+            .collect(),
+    };
+    let mut bytes = Cache::with_capacity(1024);
+    serde_json::to_writer(&mut bytes, &feed).unwrap();
+    set_feed_response(res, bytes, "application/feed+json; charset=utf-8");
+}
+
+#[handler]
+async fn rss_path(res: &mut Response) {
+    match ArticlesWithTag::query_feed(get_config().site.feed_size, None).await {
+        Ok(articles) => render_rss(res, &FeedMeta::site(), articles),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+}
+
+#[handler]
+async fn atom_path(res: &mut Response) {
+    match ArticlesWithTag::query_feed(get_config().site.feed_size, None).await {
+        Ok(articles) => render_atom(res, &FeedMeta::site(), articles),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+}
+
+#[handler]
+async fn json_feed_path(res: &mut Response) {
+    match ArticlesWithTag::query_feed(get_config().site.feed_size, None).await {
+        Ok(articles) => render_json_feed(res, &FeedMeta::site(), articles),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+}
+
+/// Feed of a single tag, a missing tag is a 404
+async fn tag_feed(req: &Request) -> Result<(FeedMeta, Vec<ArticlesWithTag>), StatusError> {
+    let tag_id = req
+        .param::<Uuid>("tag_id")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+    let name = Tags::query_name(tag_id)
+        .await
+        .map_err(|e| from_code(StatusCode::NOT_FOUND, e))?;
+    let articles = ArticlesWithTag::query_feed(get_config().site.feed_size, Some(tag_id))
+        .await
+        .map_err(|e| from_code(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok((FeedMeta::tag(tag_id, &name), articles))
+}
+
+#[handler]
+async fn tag_rss_path(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let (meta, articles) = tag_feed(req).await?;
+    render_rss(res, &meta, articles);
+    Ok(())
+}
+
+#[handler]
+async fn tag_atom_path(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let (meta, articles) = tag_feed(req).await?;
+    render_atom(res, &meta, articles);
+    Ok(())
+}
+
+#[handler]
+async fn tag_json_feed_path(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let (meta, articles) = tag_feed(req).await?;
+    render_json_feed(res, &meta, articles);
+    Ok(())
+}
+
+pub struct Feed;
+
+impl Routers for Feed {
+    fn build(self) -> Vec<Router> {
+        const TAG_ID: &str = r"{tag_id|[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}}";
+        vec![
+            // http {ip}/rss
+            Router::new().path("rss").get(rss_path),
+            // http {ip}/atom.xml
+            Router::new().path("atom.xml").get(atom_path),
+            // http {ip}/feed.json
+            Router::new().path("feed.json").get(json_feed_path),
+            // http {ip}/tag/{tag_id}/rss
+            Router::new()
+                .path(format!("tag/{}/rss", TAG_ID))
+                .get(tag_rss_path),
+            // http {ip}/tag/{tag_id}/atom.xml
+            Router::new()
+                .path(format!("tag/{}/atom.xml", TAG_ID))
+                .get(tag_atom_path),
+            // http {ip}/tag/{tag_id}/feed.json
+            Router::new()
+                .path(format!("tag/{}/feed.json", TAG_ID))
+                .get(tag_json_feed_path),
+        ]
+    }
+}
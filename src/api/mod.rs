@@ -1,20 +1,36 @@
 mod admin_article_api;
+mod admin_audit_api;
 mod admin_chart_data_api;
+mod admin_comment_api;
 mod admin_fund_api;
 mod admin_tag_api;
 mod admin_user_api;
+mod feed_api;
+mod oauth_api;
+mod sitemap_api;
 mod user_api;
 mod visitor_api;
 
 pub use admin_article_api::AdminArticle;
+pub use admin_audit_api::AdminAudit;
 pub use admin_chart_data_api::ChartData;
+pub use admin_comment_api::AdminComment;
 pub use admin_fund_api::AdminFund;
 pub use admin_tag_api::Tag;
 pub use admin_user_api::AdminUser;
+pub use feed_api::Feed;
+pub use oauth_api::OAuth;
+pub use sitemap_api::Sitemap;
 pub use user_api::User;
 pub use visitor_api::Visitor;
 
-use salvo::{Depot, Request, Response, prelude::handler, routing::FlowCtrl};
+use crate::models::{api_token::TokenScopes, audit_log::NewAuditLog, role::Role, user::UserInfo};
+use salvo::{
+    Depot, Request, Response,
+    http::{Method, StatusCode, StatusError},
+    prelude::handler,
+    routing::FlowCtrl,
+};
 use tokio::sync::RwLock;
 
 // todo: remove on delete all template
@@ -22,6 +38,53 @@ const PREFIX: &str = "api/v1/";
 
 static PAGE_MAX: RwLock<PageMeta> = RwLock::const_new(PageMeta::new());
 
+/// Requests authenticated with an api token
+fn token_scopes(depot: &Depot) -> Option<&TokenScopes> {
+    depot.get::<TokenScopes>(crate::API_TOKEN).ok()
+}
+
+fn role(depot: &Depot) -> Option<Role> {
+    depot
+        .get::<Option<Role>>(crate::ROLE)
+        .ok()
+        .copied()
+        .flatten()
+}
+
+/// The role must grant the permission, and the scopes too for a request with a token
+fn check_permission(depot: &Depot, permission: &str) -> Result<(), StatusError> {
+    if !role(depot).is_some_and(|role| role.can(permission)) {
+        return Err(crate::utils::from_code(
+            StatusCode::FORBIDDEN,
+            "No permission",
+        ));
+    }
+    if let Some(scopes) = token_scopes(depot)
+        && !scopes.allows(permission)
+    {
+        return Err(crate::utils::from_code(
+            StatusCode::FORBIDDEN,
+            format!("The token lacks the scope {}", permission),
+        ));
+    }
+    Ok(())
+}
+
+/// Audit entry of a mutation by the user of the request, written once it succeeded
+fn audit(req: &Request, depot: &Depot, action: &'static str, target: String) -> NewAuditLog {
+    let info = depot.get::<UserInfo>(crate::USER_INFO).ok();
+    NewAuditLog {
+        actor_id: info.map(|info| info.id),
+        actor: info.map(|info| info.account.clone()).unwrap_or_default(),
+        action,
+        target,
+        before: None,
+        after: None,
+        ip: crate::utils::client_ip(req),
+    }
+}
+
+/// Session only, api tokens are refused
 #[handler]
 async fn block_unlogin(
     req: &mut Request,
@@ -29,14 +92,7 @@ async fn block_unlogin(
     res: &mut Response,
     ctrl: &mut FlowCtrl,
 ) -> Result<(), salvo::http::StatusError> {
-    let permission = {
-        depot
-            .get::<Option<i16>>(crate::PERMISSION)
-            .ok()
-            .map(|a| a.is_some())
-            .unwrap_or_default()
-    };
-    if permission {
+    if role(depot).is_some() && token_scopes(depot).is_none() {
         ctrl.call_next(req, depot, res).await;
         Ok(())
     } else {
@@ -47,28 +103,43 @@ async fn block_unlogin(
     }
 }
 
+/// Every route under the hoop needs the permission
+pub(crate) struct RequirePermission(pub &'static str);
+
 #[handler]
-pub(crate) async fn block_no_admin(
-    req: &mut Request,
-    depot: &mut Depot,
-    res: &mut Response,
-    ctrl: &mut FlowCtrl,
-) -> Result<(), salvo::http::StatusError> {
-    let permission = {
-        depot
-            .get::<Option<i16>>(crate::PERMISSION)
-            .ok()
-            .and_then(|a| a.map(|b| b == 0))
-            .unwrap_or_default()
-    };
-    if permission {
+impl RequirePermission {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        depot: &mut Depot,
+        res: &mut Response,
+        ctrl: &mut FlowCtrl,
+    ) -> Result<(), StatusError> {
+        check_permission(depot, self.0)?;
+        ctrl.call_next(req, depot, res).await;
+        Ok(())
+    }
+}
+
+/// `{resource}:read` to get and `{resource}:write` for the other methods
+struct RequireAccess(&'static str);
+
+#[handler]
+impl RequireAccess {
+    async fn handle(
+        &self,
+        req: &mut Request,
+        depot: &mut Depot,
+        res: &mut Response,
+        ctrl: &mut FlowCtrl,
+    ) -> Result<(), StatusError> {
+        let access = match *req.method() {
+            Method::GET | Method::HEAD => "read",
+            _ => "write",
+        };
+        check_permission(depot, &format!("{}:{}", self.0, access))?;
         ctrl.call_next(req, depot, res).await;
         Ok(())
-    } else {
-        Err(crate::utils::from_code(
-            salvo::http::StatusCode::FORBIDDEN,
-            "No permission",
-        ))
     }
 }
 
@@ -144,6 +215,20 @@ pub async fn init_page_size() {
     }
 }
 
+/// Publish the articles whose scheduled time has come and refresh the page counters
+pub async fn publish_scheduled() {
+    use crate::models::articles::ArticlesWithTag;
+
+    match ArticlesWithTag::publish_scheduled().await {
+        Ok(ids) if !ids.is_empty() => {
+            tracing::info!("scheduled publish: {:?}", ids);
+            init_page_size().await;
+        }
+        Ok(_) => (),
+        Err(e) => tracing::error!("scheduled publish failed: {}", e),
+    }
+}
+
 pub(crate) async fn size_add() {
     let mut page = PAGE_MAX.write().await;
     page.add()
@@ -0,0 +1,261 @@
+use bytes::BytesMut;
+use salvo::{
+    Depot, Request, Response, Router,
+    http::{
+        StatusCode, StatusError,
+        cookie::{Cookie, time},
+        header,
+    },
+    prelude::handler,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    Routers, USER_INFO,
+    api::{JsonErrResponse, JsonOkResponse, block_unlogin},
+    db_wrapper::get_redis,
+    models::{
+        user::{LoginStep, LoginUser, UserInfo},
+        user_oauth::{UnlinkOAuth, UserOAuth},
+    },
+    utils::{
+        from_code,
+        oauth::{ProviderInfo, find_provider, provider_infos},
+        parse_json_body, parse_query, random_string, set_cookie, set_json_response,
+        set_plain_text_response, sha3_256_encode,
+    },
+};
+
+/// Seconds the user has to finish the authorization on the provider
+const STATE_TTL: i64 = 600;
+
+/// Holds the state in the browser that started the authorization
+const STATE_COOKIE: &str = "oauth_state";
+
+/// What the authorization was started for, stored on redis
+/// key is `oauth_state_{state}`
+#[derive(Serialize, Deserialize, Debug)]
+struct OAuthState {
+    provider: String,
+    /// Set when a logged in user links an account
+    user_id: Option<Uuid>,
+}
+
+#[handler]
+async fn authorize(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let name = req
+        .param::<String>("provider")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+    let provider =
+        find_provider(&name).ok_or_else(|| from_code(StatusCode::NOT_FOUND, "Unknown provider"))?;
+
+    let state = sha3_256_encode(random_string(16));
+    let url = provider
+        .authorize_url(&state)
+        .await
+        .map_err(|e| from_code(StatusCode::BAD_GATEWAY, e))?;
+
+    let intent = OAuthState {
+        provider: name,
+        user_id: depot.get::<UserInfo>(USER_INFO).ok().map(|info| info.id),
+    };
+    let key = format!("oauth_state_{}", state);
+    let redis_pool = get_redis();
+    redis_pool
+        .set(&key, &serde_json::to_string(&intent).unwrap())
+        .await;
+    redis_pool.expire(&key, STATE_TTL).await;
+
+    let mut cookie = Cookie::new(STATE_COOKIE, state);
+    cookie.set_path("/api/v1/");
+    cookie.set_http_only(true);
+    cookie.set_max_age(time::Duration::seconds(STATE_TTL));
+    res.add_cookie(cookie);
+
+    res.status_code(StatusCode::FOUND);
+    res.headers_mut()
+        .insert(header::LOCATION, url.parse().unwrap());
+    Ok(())
+}
+
+/// The state must be known, unused, issued for this provider and to this browser
+async fn take_state(req: &Request, name: &str) -> Result<OAuthState, StatusError> {
+    let invalid = || from_code(StatusCode::NOT_ACCEPTABLE, "Invalid oauth state");
+    let state = req.query::<String>("state").ok_or_else(invalid)?;
+    match req.cookies().get(STATE_COOKIE) {
+        Some(cookie) if cookie.value() == state => (),
+        _ => return Err(invalid()),
+    }
+
+    let key = format!("oauth_state_{}", state);
+    let redis_pool = get_redis();
+    let value = match redis_pool.get(&key).await {
+        Ok(Some(value)) => value,
+        _ => return Err(invalid()),
+    };
+    // Only the request that removes it may use the state
+    if !redis_pool.del(&key).await {
+        return Err(invalid());
+    }
+
+    match serde_json::from_str::<OAuthState>(&value) {
+        Ok(state) if state.provider == name => Ok(state),
+        _ => Err(invalid()),
+    }
+}
+
+async fn finish(
+    name: &str,
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let code = parse_query::<String>(req, "code")?;
+    let state = take_state(req, name).await?;
+    res.remove_cookie(STATE_COOKIE);
+    let provider =
+        find_provider(name).ok_or_else(|| from_code(StatusCode::NOT_FOUND, "Unknown provider"))?;
+
+    let identity = provider
+        .identity(&code)
+        .await
+        .map_err(|e| from_code(StatusCode::NOT_ACCEPTABLE, e))?;
+
+    match state.user_id {
+        Some(user_id) => {
+            // The session must still belong to the user who started linking
+            if depot.get::<UserInfo>(USER_INFO).ok().map(|info| info.id) != Some(user_id) {
+                return Err(from_code(StatusCode::NOT_ACCEPTABLE, "Invalid oauth state"));
+            }
+            match UserOAuth::link(user_id, name, &identity).await {
+                Ok(_) => redirect_home(res),
+                Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+            }
+        }
+        None => match LoginUser::login_with_oauth(name, identity).await {
+            Ok(LoginStep::Session(cookie)) => {
+                set_cookie(res, cookie, None, Some("/"), None, Some(24));
+                redirect_home(res);
+            }
+            // The login page asks for the one-time password
+            Ok(LoginStep::Totp(challenge)) => redirect(
+                res,
+                &format!(
+                    "/home?totp_token={}&totp_setup={}",
+                    challenge.token, challenge.setup
+                ),
+            ),
+            Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+        },
+    }
+
+    Ok(())
+}
+
+fn redirect_home(res: &mut Response) {
+    redirect(res, "/home")
+}
+
+fn redirect(res: &mut Response, location: &str) {
+    res.status_code(StatusCode::FOUND);
+    res.headers_mut()
+        .insert(header::LOCATION, location.parse().unwrap());
+    set_plain_text_response(res, BytesMut::from(r#"{"status": true}"#));
+}
+
+#[handler]
+async fn callback(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let name = req
+        .param::<String>("provider")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+    finish(&name, req, depot, res).await
+}
+
+/// Callback registered on github oauth apps before other providers were supported
+#[handler]
+async fn login_with_github(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    finish("github", req, depot, res).await
+}
+
+#[handler]
+async fn linked_accounts(depot: &mut Depot, res: &mut Response) {
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    #[derive(Serialize)]
+    struct Linked {
+        linked: Vec<UserOAuth>,
+        providers: Vec<ProviderInfo>,
+    }
+
+    match UserOAuth::view_with_user(info.id).await {
+        Ok(linked) => set_json_response(
+            res,
+            64,
+            JsonOkResponse::ok(Linked {
+                linked,
+                providers: provider_infos(),
+            }),
+        ),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+}
+
+#[handler]
+async fn unlink(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<UnlinkOAuth>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    match UserOAuth::unlink(info.id, &body.provider).await {
+        Ok(num) => set_json_response(res, 32, JsonOkResponse::ok(num)),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+    Ok(())
+}
+
+pub struct OAuth;
+
+impl Routers for OAuth {
+    fn build(self) -> Vec<Router> {
+        use crate::api::PREFIX;
+        vec![
+            // http {ip}/PREFIX/oauth/{provider}/authorize
+            Router::new()
+                .path(PREFIX.to_owned() + "oauth/{provider}/authorize")
+                .get(authorize),
+            // http {ip}/PREFIX/oauth/{provider}/callback?code={code}&&state={state}
+            Router::new()
+                .path(PREFIX.to_owned() + "oauth/{provider}/callback")
+                .get(callback),
+            // http {ip}/PREFIX/login_with_github?code={code}&&state={state}
+            Router::new()
+                .path(PREFIX.to_owned() + "login_with_github")
+                .get(login_with_github),
+            Router::new()
+                .path(PREFIX.to_owned() + "user/oauth")
+                .hoop(block_unlogin)
+                // http {ip}/PREFIX/user/oauth
+                .get(linked_accounts)
+                // http post {ip}/PREFIX/user/oauth/unlink provider=xxx
+                .push(Router::new().path("unlink").post(unlink)),
+        ]
+    }
+}
@@ -0,0 +1,203 @@
+use bytes::BytesMut;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use salvo::{
+    Request, Response, Router,
+    http::{StatusCode, StatusError},
+    prelude::handler,
+};
+use std::fmt::Write;
+
+use crate::{
+    Routers,
+    config::get_config,
+    models::{articles::ArticleList, tag::TagLastModify},
+    utils::{from_code, set_plain_text_response, set_xml_text_response, site_url},
+};
+
+/// Pages that are not generated from the database
+const STATIC_PAGES: [&str; 3] = ["/", "/list", "/about"];
+
+fn lastmod(time: &NaiveDateTime) -> String {
+    Utc.from_utc_datetime(time).to_rfc3339()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+struct UrlSet(String);
+
+impl UrlSet {
+    fn new() -> Self {
+        UrlSet(String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+        ))
+    }
+
+    fn push(&mut self, path: &str, modify_time: Option<&NaiveDateTime>) {
+        let loc = xml_escape(&format!("{}{}", site_url(), path));
+        match modify_time {
+            Some(time) => writeln!(
+                self.0,
+                "<url><loc>{}</loc><lastmod>{}</lastmod></url>",
+                loc,
+                lastmod(time)
+            ),
+            None => writeln!(self.0, "<url><loc>{}</loc></url>", loc),
+        }
+        .unwrap();
+    }
+
+    fn push_static_pages(&mut self) {
+        for page in STATIC_PAGES {
+            self.push(page, None);
+        }
+    }
+
+    fn push_tags(&mut self, tags: &[TagLastModify]) {
+        for tag in tags {
+            self.push(&format!("/tag/{}", tag.id), Some(&tag.modify_time));
+        }
+    }
+
+    fn push_articles(&mut self, articles: &[ArticleList]) {
+        for article in articles {
+            let path = match article.slug {
+                Some(ref slug) => format!("/article/{}", slug),
+                None => format!("/article/{}", article.id),
+            };
+            self.push(&path, Some(&article.modify_time));
+        }
+    }
+
+    fn finish(mut self) -> BytesMut {
+        self.0.push_str("</urlset>\n");
+        BytesMut::from(self.0.as_str())
+    }
+}
+
+fn sitemap_index(article_pages: i64) -> BytesMut {
+    let mut index = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+"#,
+    );
+    let mut push = |path: String| {
+        writeln!(
+            index,
+            "<sitemap><loc>{}</loc></sitemap>",
+            xml_escape(&format!("{}{}", site_url(), path))
+        )
+        .unwrap()
+    };
+    push("/sitemap/pages".to_string());
+    for page in 0..article_pages {
+        push(format!("/sitemap/articles/{}", page));
+    }
+    index.push_str("</sitemapindex>\n");
+    BytesMut::from(index.as_str())
+}
+
+/// A single sitemap while everything fits in one file, a sitemap index otherwise
+#[handler]
+async fn sitemap(res: &mut Response) -> Result<(), StatusError> {
+    let size = get_config().site.sitemap_size;
+    let internal_error = |e| from_code(StatusCode::INTERNAL_SERVER_ERROR, e);
+    let article_count = ArticleList::published_count()
+        .await
+        .map_err(internal_error)?;
+    let tags = TagLastModify::view_all().await.map_err(internal_error)?;
+
+    let total = article_count + tags.len() as i64 + STATIC_PAGES.len() as i64;
+    if total > size {
+        let article_pages = (article_count + size - 1) / size;
+        set_xml_text_response(res, sitemap_index(article_pages));
+        return Ok(());
+    }
+
+    let articles = ArticleList::query_sitemap(size, 0)
+        .await
+        .map_err(internal_error)?;
+    let mut urls = UrlSet::new();
+    urls.push_static_pages();
+    urls.push_tags(&tags);
+    urls.push_articles(&articles);
+    set_xml_text_response(res, urls.finish());
+    Ok(())
+}
+
+#[handler]
+async fn sitemap_pages(res: &mut Response) -> Result<(), StatusError> {
+    let tags = TagLastModify::view_all()
+        .await
+        .map_err(|e| from_code(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let mut urls = UrlSet::new();
+    urls.push_static_pages();
+    urls.push_tags(&tags);
+    set_xml_text_response(res, urls.finish());
+    Ok(())
+}
+
+#[handler]
+async fn sitemap_articles(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let page = req
+        .param::<i64>("page")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+    let size = get_config().site.sitemap_size;
+    let offset = page
+        .checked_mul(size)
+        .ok_or_else(|| from_code(StatusCode::NOT_FOUND, "Sitemap page not found"))?;
+
+    let articles = ArticleList::query_sitemap(size, offset)
+        .await
+        .map_err(|e| from_code(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if articles.is_empty() {
+        return Err(from_code(StatusCode::NOT_FOUND, "Sitemap page not found"));
+    }
+
+    let mut urls = UrlSet::new();
+    urls.push_articles(&articles);
+    set_xml_text_response(res, urls.finish());
+    Ok(())
+}
+
+#[handler]
+async fn robots(res: &mut Response) {
+    let body = format!(
+        r#"User-Agent: *
+Allow: /
+Allow: /*.css
+Allow: /*.js
+
+Sitemap: {}/sitemap.xml
+"#,
+        site_url()
+    );
+    set_plain_text_response(res, BytesMut::from(body.as_str()));
+}
+
+pub struct Sitemap;
+
+impl Routers for Sitemap {
+    fn build(self) -> Vec<Router> {
+        vec![
+            // http {ip}/robots.txt
+            Router::new().path("robots.txt").get(robots),
+            // http {ip}/sitemap.xml
+            Router::new().path("sitemap.xml").get(sitemap),
+            // http {ip}/sitemap/pages
+            Router::new().path("sitemap/pages").get(sitemap_pages),
+            // http {ip}/sitemap/articles/{page}
+            Router::new()
+                .path(r"sitemap/articles/{page|\d+}")
+                .get(sitemap_articles),
+        ]
+    }
+}
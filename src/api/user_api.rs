@@ -5,15 +5,24 @@ use salvo::{
 };
 
 use crate::{
-    COOKIE, PERMISSION, Routers, USER_INFO,
-    api::{JsonErrResponse, JsonOkResponse, block_unlogin},
+    COOKIE, Routers, USER_INFO,
+    api::{JsonErrResponse, JsonOkResponse, audit, block_unlogin},
     models::{
+        api_token::{ApiToken, NewApiToken, RevokeApiToken, available_scopes},
         articles::ArticlesWithTag,
-        comment::{DeleteComment, NewComments},
+        comment::{CommentAuthor, CommentStatus, DeleteComment, EditComment, NewComments},
         notify::UserNotify,
+        role::Role,
+        session::{RevokeSession, Session},
         user::{ChangePassword, EditUser, LoginUser, UserInfo},
+        user_totp::{TotpCode, UserTotp},
+    },
+    utils::{
+        client_ip, from_code,
+        mail::mail_enabled,
+        parse_json_body, set_json_response,
+        spam::{self, CommentDraft, Verdict},
     },
-    utils::{from_code, parse_json_body, set_json_response},
 };
 
 #[handler]
@@ -57,10 +66,205 @@ async fn edit(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Resul
     Ok(())
 }
 
+#[handler]
+async fn resend_verification(depot: &mut Depot, res: &mut Response) {
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    // The session may be older than the last email change or verification
+    let res_data = match UserInfo::view_user(info.id).await {
+        Ok(info) if info.email_verified => Err("Email is already verified".to_string()),
+        Ok(_) if !mail_enabled() => Err("Mail is not configured".to_string()),
+        Ok(info) => info.send_verification_mail().await,
+        Err(e) => Err(e),
+    };
+    match res_data {
+        Ok(()) => set_json_response(res, 32, JsonOkResponse::status(true)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+}
+
+#[handler]
+async fn totp_status(depot: &mut Depot, res: &mut Response) {
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    match UserTotp::status(info.id, info.role).await {
+        Ok(data) => set_json_response(res, 64, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+}
+
+#[handler]
+async fn totp_setup(depot: &mut Depot, res: &mut Response) {
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    match UserTotp::setup(info.id, &info.account).await {
+        Ok(data) => set_json_response(res, 4096, JsonOkResponse::ok(data)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+}
+
+#[handler]
+async fn totp_enable(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<TotpCode>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    match UserTotp::enable(info.id, &body.code).await {
+        Ok(codes) => set_json_response(res, 256, JsonOkResponse::ok(codes)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn totp_disable(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<TotpCode>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    if UserTotp::required(info.role) {
+        set_json_response(
+            res,
+            32,
+            JsonErrResponse::err("Two-factor authentication is required for admins"),
+        );
+        return Ok(());
+    }
+    let res_data = match UserTotp::check(info.id, &body.code).await {
+        Ok(()) => UserTotp::disable(info.id).await,
+        Err(e) => Err(e),
+    };
+    match res_data {
+        Ok(()) => set_json_response(res, 32, JsonOkResponse::status(true)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn totp_recovery_codes(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<TotpCode>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    let res_data = match UserTotp::check(info.id, &body.code).await {
+        Ok(()) => UserTotp::new_recovery_codes(info.id).await,
+        Err(e) => Err(e),
+    };
+    match res_data {
+        Ok(codes) => set_json_response(res, 256, JsonOkResponse::ok(codes)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn list_sessions(depot: &mut Depot, res: &mut Response) {
+    let cookie = depot.remove::<String>(COOKIE).ok().unwrap();
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    set_json_response(
+        res,
+        512,
+        JsonOkResponse::ok(Session::list(info.id, &cookie).await),
+    );
+}
+
+#[handler]
+async fn list_tokens(depot: &mut Depot, res: &mut Response) {
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    match ApiToken::list(info.id).await {
+        Ok(tokens) => set_json_response(
+            res,
+            256,
+            JsonOkResponse::ok(
+                serde_json::json!({ "tokens": tokens, "scopes": available_scopes(info.role) }),
+            ),
+        ),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+}
+
+#[handler]
+async fn new_token(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<NewApiToken>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    match body.insert(info.id, info.role).await {
+        Ok(token) => set_json_response(res, 64, JsonOkResponse::ok(token)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn revoke_token(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<RevokeApiToken>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    match ApiToken::revoke(info.id, body.id).await {
+        Ok(num) => set_json_response(res, 32, JsonOkResponse::status(num == 1)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn revoke_session(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<RevokeSession>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    let cookie = depot.remove::<String>(COOKIE).ok().unwrap();
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+
+    match body.id {
+        Some(id) => set_json_response(
+            res,
+            32,
+            JsonOkResponse::status(Session::revoke(info.id, &id).await),
+        ),
+        None => set_json_response(
+            res,
+            32,
+            JsonOkResponse::ok(Session::revoke_all(info.id, Some(&cookie)).await),
+        ),
+    }
+    Ok(())
+}
+
 #[handler]
 async fn sign_out(depot: &mut Depot, res: &mut Response) {
     let cookie = depot.remove::<String>(COOKIE).ok().unwrap();
-    let a = LoginUser::sign_out(&cookie).await;
+    let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    let a = LoginUser::sign_out(info.id, &cookie).await;
     set_json_response(res, 32, JsonOkResponse::status(a));
 }
 
@@ -77,50 +281,150 @@ async fn new_comment(
     let article = ArticlesWithTag::query_without_article(body.article_id(), false)
         .await
         .map_err(|_| from_code(StatusCode::NOT_FOUND, "Article doesn't exist"))?;
-    let admin = UserInfo::view_admin().await;
     let user = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
+    if mail_enabled() && !UserInfo::is_email_verified(user.id).await {
+        set_json_response(res, 32, JsonErrResponse::err("Email is not verified"));
+        return Ok(());
+    }
+    if body.comment().trim().is_empty() {
+        set_json_response(res, 32, JsonErrResponse::err("Comment is empty"));
+        return Ok(());
+    }
 
-    match body.reply_user_id() {
-        // Reply comment
-        Some(reply_user_id) => {
-            // Notification reply
-            let user_reply_notify = UserNotify {
-                user_id: reply_user_id,
-                send_user_name: user.nickname.clone(),
+    let parent = match body.parent().await {
+        Ok(parent) => parent,
+        Err(e) => {
+            set_json_response(res, 32, JsonErrResponse::err(e));
+            return Ok(());
+        }
+    };
+
+    let reply_user_id = match &parent {
+        Some(parent) => parent.user_id,
+        None => body.reply_user_id(),
+    };
+    let status = if user.role.can("comment:moderate") {
+        CommentStatus::Approved
+    } else {
+        let ip = client_ip(req);
+        let draft = CommentDraft {
+            text: body.comment(),
+            article_id: article.id,
+            user_id: Some(user.id),
+            ip: ip.as_deref(),
+            comment_id: None,
+        };
+        match spam::check(&draft).await {
+            Verdict::Reject(reason) => {
+                set_json_response(res, 32, JsonErrResponse::err(reason));
+                return Ok(());
+            }
+            Verdict::Moderate(_) => CommentStatus::Pending,
+            Verdict::Pass => body.status(user.id, user.role).await,
+        }
+    };
+    if !body
+        .insert(&CommentAuthor::User(user.id), parent.as_ref(), status)
+        .await
+    {
+        set_json_response(res, 32, JsonOkResponse::status(false));
+        return Ok(());
+    }
+
+    match status {
+        // The reply is notified once the comment is approved
+        CommentStatus::Pending => {
+            let pending_notify = UserNotify {
+                user_id: UserInfo::view_admin().await.id,
+                send_user_name: user.nickname,
                 article_id: article.id,
-                article_title: article.title.clone(),
-                notify_type: "reply".into(),
+                article_title: article.title,
+                notify_type: "pending".into(),
             };
-            user_reply_notify.cache().await;
-
-            // If the sender is not an admin and also the responder is also not admin, notify admin
-            if reply_user_id != admin.id && user.groups != 0 {
-                let comment_notify = UserNotify {
-                    user_id: admin.id,
-                    send_user_name: user.nickname,
-                    article_id: article.id,
-                    article_title: article.title,
-                    notify_type: "comment".into(),
-                };
-                comment_notify.cache().await;
-            }
+            pending_notify.cache().await;
+        }
+        _ => {
+            UserNotify::published_comment(
+                &user.nickname,
+                user.role == Role::Admin,
+                article.id,
+                &article.title,
+                reply_user_id,
+            )
+            .await
         }
-        // Normal comment
-        None => {
-            if user.groups != 0 {
-                let comment_notify = UserNotify {
-                    user_id: admin.id,
-                    send_user_name: user.nickname,
-                    article_id: article.id,
-                    article_title: article.title,
-                    notify_type: "comment".into(),
-                };
-                comment_notify.cache().await;
+    }
+
+    set_json_response(res, 32, JsonOkResponse::ok(status));
+    Ok(())
+}
+
+#[handler]
+async fn edit_comment(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), StatusError> {
+    let body = parse_json_body::<EditComment>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+    // Kept in the depot for the audit log
+    let user = depot.get::<UserInfo>(USER_INFO).ok().unwrap().clone();
+    if mail_enabled() && !UserInfo::is_email_verified(user.id).await {
+        set_json_response(res, 32, JsonErrResponse::err("Email is not verified"));
+        return Ok(());
+    }
+    if body.comment().trim().is_empty() {
+        set_json_response(res, 32, JsonErrResponse::err("Comment is empty"));
+        return Ok(());
+    }
+
+    let target = match body.target(user.id, user.role == Role::Admin).await {
+        Ok(target) => target,
+        Err(e) => {
+            set_json_response(res, 32, JsonErrResponse::err(e));
+            return Ok(());
+        }
+    };
+
+    let status = if user.role.can("comment:moderate") {
+        target.status
+    } else {
+        let ip = client_ip(req);
+        let draft = CommentDraft {
+            text: body.comment(),
+            article_id: target.article_id,
+            user_id: Some(user.id),
+            ip: ip.as_deref(),
+            comment_id: Some(body.comment_id()),
+        };
+        match spam::check(&draft).await {
+            Verdict::Reject(reason) => {
+                set_json_response(res, 32, JsonErrResponse::err(reason));
+                return Ok(());
             }
+            Verdict::Moderate(_) => CommentStatus::Pending,
+            Verdict::Pass => body.status(&target, user.id, user.role).await,
         }
+    };
+    if !body.update(user.id, status).await {
+        set_json_response(res, 32, JsonOkResponse::status(false));
+        return Ok(());
     }
 
-    set_json_response(res, 32, JsonOkResponse::status(body.insert(user.id).await));
+    if target.user_id != Some(user.id) {
+        audit(
+            req,
+            depot,
+            "comment.edit",
+            format!("comment:{}", body.comment_id()),
+        )
+        .before(serde_json::json!({ "comment": target.comment }))
+        .after(serde_json::json!({ "comment": body.comment() }))
+        .insert()
+        .await;
+    }
+    set_json_response(res, 32, JsonOkResponse::ok(status));
     Ok(())
 }
 
@@ -133,13 +437,12 @@ async fn delete_comment(
     let body = parse_json_body::<DeleteComment>(req)
         .await
         .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
-    let permission = depot.remove::<Option<i16>>(PERMISSION).unwrap();
     let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
 
     set_json_response(
         res,
         32,
-        JsonOkResponse::status(body.delete(info.id, permission).await),
+        JsonOkResponse::status(body.delete(info.id, info.role).await),
     );
     Ok(())
 }
@@ -159,13 +462,57 @@ impl Routers for User {
                 .push(Router::new().path("change_pwd").post(change_pwd))
                 // http {ip}/user/sign_out
                 .push(Router::new().path("sign_out").get(sign_out))
+                // http {ip}/PREFIX/user/sessions
+                .push(
+                    Router::new()
+                        .path("sessions")
+                        .get(list_sessions)
+                        // http post {ip}/PREFIX/user/sessions/revoke id=xxx, every other session without id
+                        .push(Router::new().path("revoke").post(revoke_session)),
+                )
+                // http {ip}/PREFIX/user/tokens
+                .push(
+                    Router::new()
+                        .path("tokens")
+                        .get(list_tokens)
+                        // http post {ip}/PREFIX/user/tokens/new name=ci scopes:='["article:write"]' expires_in_days:=90
+                        .push(Router::new().path("new").post(new_token))
+                        // http post {ip}/PREFIX/user/tokens/revoke id=xxx
+                        .push(Router::new().path("revoke").post(revoke_token)),
+                )
                 // http post {ip}/user/edit nickname=xxx say=xxx email=xxx
-                .push(Router::new().path("edit").post(edit)),
+                .push(Router::new().path("edit").post(edit))
+                // http post {ip}/PREFIX/user/verify_email/resend
+                .push(
+                    Router::new()
+                        .path("verify_email/resend")
+                        .post(resend_verification),
+                )
+                // http {ip}/PREFIX/user/totp
+                .push(
+                    Router::new()
+                        .path("totp")
+                        .get(totp_status)
+                        // http post {ip}/PREFIX/user/totp/setup
+                        .push(Router::new().path("setup").post(totp_setup))
+                        // http post {ip}/PREFIX/user/totp/enable code=xxx
+                        .push(Router::new().path("enable").post(totp_enable))
+                        // http post {ip}/PREFIX/user/totp/disable code=xxx
+                        .push(Router::new().path("disable").post(totp_disable))
+                        // http post {ip}/PREFIX/user/totp/recovery_codes code=xxx
+                        .push(
+                            Router::new()
+                                .path("recovery_codes")
+                                .post(totp_recovery_codes),
+                        ),
+                ),
             Router::new()
                 .path(PREFIX.to_owned() + "comment")
                 .hoop(block_unlogin)
                 // http post {ip}/comment/new comment=xxx article_id=xxx reply_user_id=xxx
                 .push(Router::new().path("new").post(new_comment))
+                // http post {ip}/comment/edit comment_id=xxx comment=xxx
+                .push(Router::new().path("edit").post(edit_comment))
                 // http post {ip}/comment/delete comment_id=xxx user_id=xxx
                 .push(Router::new().path("delete").post(delete_comment)),
         ]
@@ -1,28 +1,30 @@
-use chrono::offset::TimeZone;
-use rss::{ChannelBuilder, Item, ItemBuilder};
-use salvo::http::header::{self, HeaderValue};
 use salvo::{
     Depot, Request, Response, Router,
-    http::{ResBody, StatusCode, StatusError},
+    http::{StatusCode, StatusError, header},
     prelude::handler,
 };
 use uuid::Uuid;
 
 use crate::{
-    PERMISSION, Routers, USER_INFO,
+    Routers, USER_INFO,
     api::{JsonErrResponse, JsonOkResponse, current_size},
+    config::get_config,
     models::{
-        articles::{ArticleList, ArticlesWithTag},
-        comment::Comments,
-        user::{LoginUser, RegisteredUser, UserInfo},
+        articles::{ArticleList, ArticleSearch, ArticlesWithTag},
+        comment::{CommentStatus, Comments, NewGuestComment},
+        comment_challenge::CommentChallenge,
+        notify::UserNotify,
+        role::Role,
+        user::{
+            ForgotPassword, LoginStep, LoginUser, RegisteredUser, ResetPassword, TotpLogin,
+            UserInfo, VerifyEmail,
+        },
     },
     utils::{
-        from_code,
-        github_information::{get_github_account_nickname_address, get_github_token},
-        parse_json_body, parse_last_path, parse_query, set_cookie, set_json_response,
-        set_plain_text_response,
+        client_ip, from_code, parse_json_body, parse_last_path, parse_query, set_cookie,
+        set_json_response, set_plain_text_response,
+        spam::{self, CommentDraft, Verdict},
     },
-    web::Cache,
 };
 use bytes::BytesMut;
 
@@ -56,6 +58,19 @@ async fn list_all_article_filter_by_tag(
     Ok(())
 }
 
+#[handler]
+async fn search_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let keyword = parse_query::<String>(req, "q")?;
+    let limit = parse_query::<i64>(req, "limit")?;
+    let offset = parse_query::<i64>(req, "offset")?;
+
+    match ArticleSearch::search(&keyword, limit, offset, false).await {
+        Ok(data) => set_json_response(res, 256, JsonOkResponse::ok(data)),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+    Ok(())
+}
+
 #[handler]
 async fn list_comments(
     req: &mut Request,
@@ -66,24 +81,23 @@ async fn list_comments(
     let limit = parse_query::<i64>(req, "limit")?;
     let offset = parse_query::<i64>(req, "offset")?;
 
-    let (user_id, admin) = match depot.remove::<Option<i16>>(PERMISSION).unwrap() {
-        Some(0) => {
-            let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
-            (Some(info.id), true)
-        }
-        Some(_) => {
-            let info = depot.remove::<UserInfo>(USER_INFO).ok().unwrap();
-            (Some(info.id), false)
-        }
-        None => (None, false),
+    let (user_id, moderator, admin) = match depot.remove::<UserInfo>(USER_INFO).ok() {
+        Some(info) => (
+            Some(info.id),
+            info.role.can("comment:moderate"),
+            info.role == Role::Admin,
+        ),
+        None => (None, false, false),
     };
 
-    match Comments::query(limit, offset, article_id).await {
+    match Comments::query(limit, offset, article_id, user_id, moderator).await {
         Ok(data) => {
             #[derive(serde::Deserialize, serde::Serialize)]
             struct Tmp<T> {
                 status: bool,
                 data: T,
+                moderator: bool,
+                /// Can edit any comment
                 admin: bool,
                 user_id: Option<Uuid>,
             }
@@ -93,6 +107,7 @@ async fn list_comments(
                 &Tmp {
                     status: true,
                     data,
+                    moderator,
                     admin,
                     user_id,
                 },
@@ -104,6 +119,95 @@ async fn list_comments(
     Ok(())
 }
 
+fn guest_enabled() -> Result<(), StatusError> {
+    if get_config().comment.guest {
+        Ok(())
+    } else {
+        Err(from_code(
+            StatusCode::NOT_FOUND,
+            "Guest comments are disabled",
+        ))
+    }
+}
+
+#[handler]
+async fn comment_challenge(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    guest_enabled()?;
+    match CommentChallenge::issue(client_ip(req).as_deref()).await {
+        Ok(challenge) => set_json_response(res, 64, JsonOkResponse::ok(challenge)),
+        Err(e) => set_json_response(res, 32, JsonErrResponse::err(e)),
+    }
+    Ok(())
+}
+
+/// Guests solve a challenge first, their comments always wait for a moderator
+#[handler]
+async fn new_guest_comment(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    guest_enabled()?;
+    let body = parse_json_body::<NewGuestComment>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    let article = ArticlesWithTag::query_without_article(body.comment.article_id(), false)
+        .await
+        .map_err(|_| from_code(StatusCode::NOT_FOUND, "Article doesn't exist"))?;
+    if body.comment.comment().trim().is_empty() {
+        set_json_response(res, 32, JsonErrResponse::err("Comment is empty"));
+        return Ok(());
+    }
+    let author = match body.author() {
+        Ok(author) => author,
+        Err(e) => {
+            set_json_response(res, 32, JsonErrResponse::err(e));
+            return Ok(());
+        }
+    };
+    let (challenge_id, nonce) = body.challenge();
+    if !CommentChallenge::verify(challenge_id, nonce).await {
+        set_json_response(res, 32, JsonErrResponse::err("Invalid proof of work"));
+        return Ok(());
+    }
+    let parent = match body.comment.parent().await {
+        Ok(parent) => parent,
+        Err(e) => {
+            set_json_response(res, 32, JsonErrResponse::err(e));
+            return Ok(());
+        }
+    };
+
+    let ip = client_ip(req);
+    let draft = CommentDraft {
+        text: body.comment.comment(),
+        article_id: article.id,
+        user_id: None,
+        ip: ip.as_deref(),
+        comment_id: None,
+    };
+    if let Verdict::Reject(reason) = spam::check(&draft).await {
+        set_json_response(res, 32, JsonErrResponse::err(reason));
+        return Ok(());
+    }
+    if !body
+        .comment
+        .insert(&author, parent.as_ref(), CommentStatus::Pending)
+        .await
+    {
+        set_json_response(res, 32, JsonOkResponse::status(false));
+        return Ok(());
+    }
+
+    let pending_notify = UserNotify {
+        user_id: UserInfo::view_admin().await.id,
+        send_user_name: body.name().to_string(),
+        article_id: article.id,
+        article_title: article.title,
+        notify_type: "pending".into(),
+    };
+    pending_notify.cache().await;
+    set_json_response(res, 32, JsonOkResponse::ok(CommentStatus::Pending));
+    Ok(())
+}
+
 #[handler]
 async fn view_article(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
     let id = parse_query::<Uuid>(req, "id")?;
@@ -140,11 +244,13 @@ async fn login(req: &mut Request, res: &mut Response) -> Result<(), StatusError>
         None
     };
 
-    match body.verification(&max_age).await {
-        Ok(cookie) => {
+    match body.verification(&max_age, client_ip(req)).await {
+        Ok(LoginStep::Session(cookie)) => {
             set_cookie(res, cookie, None, Some("/"), None, max_age);
             set_plain_text_response(res, BytesMut::from(r#"{"status": true}"#));
         }
+        // No cookie until the one-time password is checked
+        Ok(LoginStep::Totp(challenge)) => set_json_response(res, 64, JsonOkResponse::ok(challenge)),
         Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
     }
 
@@ -152,27 +258,31 @@ async fn login(req: &mut Request, res: &mut Response) -> Result<(), StatusError>
 }
 
 #[handler]
-async fn login_with_github(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
-    let code = parse_query::<String>(req, "code")?;
-
-    let token = get_github_token(&code)
-        .await
-        .map_err(|e| from_code(StatusCode::NOT_ACCEPTABLE, e))?;
-    let (account, nickname, github_address) = get_github_account_nickname_address(&token)
+async fn login_totp(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let body = parse_json_body::<TotpLogin>(req)
         .await
-        .map_err(|e| from_code(StatusCode::NOT_ACCEPTABLE, e))?;
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
 
-    match LoginUser::login_with_github(github_address, nickname, account, &token).await {
-        Ok(cookie) => {
-            set_cookie(res, cookie, None, Some("/"), None, Some(24));
-            res.status_code(StatusCode::FOUND);
-            res.headers_mut()
-                .insert(header::LOCATION, "/home".parse().unwrap());
-            set_plain_text_response(res, BytesMut::from(r#"{"status": true}"#));
+    match body.finish().await {
+        Ok(done) => {
+            set_cookie(res, done.cookie, None, Some("/"), None, done.max_age);
+            set_json_response(res, 256, JsonOkResponse::ok(done.recovery_codes));
         }
         Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
     }
+    Ok(())
+}
 
+#[handler]
+async fn login_totp_setup(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let body = parse_json_body::<TotpLogin>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    match body.setup().await {
+        Ok(setup) => set_json_response(res, 4096, JsonOkResponse::ok(setup)),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
     Ok(())
 }
 
@@ -193,51 +303,49 @@ async fn create_user(req: &mut Request, res: &mut Response) -> Result<(), Status
 }
 
 #[handler]
-async fn rss_path(res: &mut Response) {
-    let mut channel = ChannelBuilder::default()
-        .title("driftluo's blog")
-        .link("https://driftluo.com")
-        .description("This is driftluo's Personal Blog's RSS feed.")
-        .build();
-
-    let hour = 3600;
-    let fix_offset = chrono::FixedOffset::east_opt(8 * hour).unwrap();
-
-    match ArticleList::query_article(10, 0, false).await {
-        Ok(articles) => {
-            let mut items: Vec<Item> = Vec::with_capacity(10);
-            for article in articles {
-                let item = ItemBuilder::default()
-                    .title(article.title.clone())
-                    .link(
-                        "https://www.driftluo.com".to_owned()
-                            + "/article/"
-                            + &article.id.to_string(),
-                    )
-                    .description(article.title)
-                    .pub_date(
-                        fix_offset
-                            .from_local_datetime(&article.create_time)
-                            .unwrap()
-                            .format("%Y-%m-%d %H:%M:%S")
-                            .to_string(),
-                    )
-                    .build();
-
-                items.push(item);
-            }
-            channel.set_items(items);
-            let mut bytes = Cache::new();
-            channel.write_to(&mut bytes).unwrap();
-            res.headers_mut().insert(
-                header::CONTENT_TYPE,
-                HeaderValue::from_static("text/xml; charset=utf-8"),
-            );
-            res.body(ResBody::Once(bytes.into_inner()));
-            res.status_code(StatusCode::OK);
+async fn forgot_password(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let body = parse_json_body::<ForgotPassword>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    match body.send_reset_mail(client_ip(req)).await {
+        Ok(()) => set_json_response(res, 32, JsonOkResponse::status(true)),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn reset_password(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let body = parse_json_body::<ResetPassword>(req)
+        .await
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Json body is Incorrect"))?;
+
+    match body.reset().await {
+        Ok(num) => set_json_response(res, 32, JsonOkResponse::ok(num)),
+        Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
+    }
+    Ok(())
+}
+
+#[handler]
+async fn verify_email(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
+    let query = VerifyEmail {
+        id: parse_query::<Uuid>(req, "id")?,
+        email: parse_query::<String>(req, "email")?,
+        expires: parse_query::<i64>(req, "expires")?,
+        sig: parse_query::<String>(req, "sig")?,
+    };
+
+    match query.verify().await {
+        Ok(_) => {
+            res.status_code(StatusCode::FOUND);
+            res.headers_mut()
+                .insert(header::LOCATION, "/home".parse().unwrap());
         }
         Err(err) => set_json_response(res, 32, JsonErrResponse::err(err)),
     }
+    Ok(())
 }
 
 pub struct Visitor;
@@ -257,6 +365,10 @@ impl Routers for Visitor {
                         + r"article/view_all/{tag_id|[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}}",
                 )
                 .get(list_all_article_filter_by_tag),
+            // http {ip}/PREFIX/article/search?q={keyword}&&limit={number}&&offset={number}
+            Router::new()
+                .path(PREFIX.to_owned() + "article/search")
+                .get(search_article),
             // http {ip}/PREFIX/article/view_comment/{id}
             Router::new()
                 .path(
@@ -264,6 +376,15 @@ impl Routers for Visitor {
                         + r"article/view_comment/{id|[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}}",
                 )
                 .get(list_comments),
+            // http {ip}/PREFIX/comment/guest/challenge
+            Router::new()
+                .path(PREFIX.to_owned() + "comment/guest/challenge")
+                .get(comment_challenge),
+            // http POST {ip}/PREFIX/comment/guest/new comment={} article_id={} parent_id={}
+            // name={} email={} challenge_id={} answer:={number}
+            Router::new()
+                .path(PREFIX.to_owned() + "comment/guest/new")
+                .post(new_guest_comment),
             // http {ip}/PREFIX/article/view/<id>
             Router::new()
                 .path(PREFIX.to_owned() + "article/view")
@@ -272,20 +393,34 @@ impl Routers for Visitor {
             Router::new()
                 .path(PREFIX.to_owned() + "article/navigation")
                 .get(view_article_navigation),
-            // http {ip}/PREFIX/login_with_github
-            Router::new()
-                .path(PREFIX.to_owned() + "login_with_github")
-                .get(login_with_github),
             // http POST {ip}/PREFIX/user/login?code={code}
             Router::new()
                 .path(PREFIX.to_owned() + "user/login")
                 .post(login),
+            // http POST {ip}/PREFIX/user/login/totp token={} code={}
+            Router::new()
+                .path(PREFIX.to_owned() + "user/login/totp")
+                .post(login_totp),
+            // http POST {ip}/PREFIX/user/login/totp/setup token={}
+            Router::new()
+                .path(PREFIX.to_owned() + "user/login/totp/setup")
+                .post(login_totp_setup),
             // http POST {ip}/PREFIX/user/new account={} password={} remember:={bool}
             Router::new()
                 .path(PREFIX.to_owned() + "user/new")
                 .post(create_user),
-            // http {ip}/rss
-            Router::new().path("rss").get(rss_path),
+            // http {ip}/PREFIX/user/verify_email?id={id}&&email={email}&&expires={ts}&&sig={sig}
+            Router::new()
+                .path(PREFIX.to_owned() + "user/verify_email")
+                .get(verify_email),
+            // http POST {ip}/PREFIX/user/forgot email={}
+            Router::new()
+                .path(PREFIX.to_owned() + "user/forgot")
+                .post(forgot_password),
+            // http POST {ip}/PREFIX/user/reset token={} password={}
+            Router::new()
+                .path(PREFIX.to_owned() + "user/reset")
+                .post(reset_password),
         ]
     }
 }
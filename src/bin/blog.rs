@@ -1,19 +1,19 @@
-use bytes::BytesMut;
 use new_blog::{
-    PERMISSION, Routers, WEB,
-    api::{AdminArticle, AdminFund, AdminUser, ChartData, Tag, User, Visitor, init_page_size},
+    ROLE, Routers, WEB,
+    api::{
+        AdminArticle, AdminAudit, AdminComment, AdminFund, AdminUser, ChartData, Feed, OAuth,
+        Sitemap, Tag, User, Visitor, init_page_size, publish_scheduled,
+    },
+    config::init_config,
     db_wrapper::{create_pg_pool, create_redis_pool},
+    models::article_slug::ArticleSlug,
     utils::get_identity_and_web_context,
     web::{Admin, ArticleWeb},
 };
 use salvo::prelude::Listener;
 use salvo::{
-    Depot, Request, Response, Router, Server,
-    conn::TcpListener,
-    http::{ResBody, StatusCode, header},
-    prelude::handler,
-    routing::FlowCtrl,
-    serve_static::StaticDir,
+    Depot, Request, Response, Router, Server, conn::TcpListener, prelude::handler,
+    routing::FlowCtrl, serve_static::StaticDir,
 };
 use tracing::{Instrument, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -25,18 +25,31 @@ fn main() {
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
     dotenv::dotenv().ok();
-    let listen_port = ::std::env::var("LISTEN_PORT")
-        .expect("LISTEN_PORT must be set")
-        .parse::<u16>()
-        .unwrap();
+    let config = match init_config() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
     let rt = tokio::runtime::Runtime::new().unwrap();
 
     rt.block_on(async {
         // load lua to redis
         create_redis_pool(Some("lua/visitor_log.lua")).await;
         create_pg_pool().await;
+        ArticleSlug::fill_missing().await;
         init_page_size().await;
 
+        // check scheduled articles every minute
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                publish_scheduled().await;
+            }
+        });
+
         let root = Router::new()
             .hoop(global)
             .append(&mut ArticleWeb.build())
@@ -46,9 +59,13 @@ fn main() {
             .append(&mut Tag.build())
             .append(&mut AdminArticle.build())
             .append(&mut AdminFund.build())
+            .append(&mut AdminAudit.build())
+            .append(&mut AdminComment.build())
             .append(&mut User.build())
             .append(&mut Visitor.build())
-            .push(Router::new().path("robots.txt").get(robot))
+            .append(&mut OAuth.build())
+            .append(&mut Feed.build())
+            .append(&mut Sitemap.build())
             .push(
                 Router::new()
                     .path("{*path}")
@@ -62,7 +79,7 @@ fn main() {
                     })),
             );
 
-        let acceptor = TcpListener::new(format!("127.0.0.1:{}", listen_port))
+        let acceptor = TcpListener::new(format!("{}:{}", config.server.bind, config.server.port))
             .bind()
             .await;
         Server::new(acceptor)
@@ -76,24 +93,7 @@ fn main() {
 async fn global(req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
     let (identity, web) = get_identity_and_web_context(req, depot).await;
 
-    depot.insert(PERMISSION, identity);
+    depot.insert(ROLE, identity);
     depot.insert(WEB, web);
     ctrl.call_next(req, depot, res).await;
 }
-
-#[handler]
-async fn robot(res: &mut Response) {
-    const ROBOT: &str = r#"User-Agent: *
-Allow: /
-Allow: /*.css
-Allow: /*.js
-
-Sitemap:https://www.driftluo.com/rss
-"#;
-    res.headers_mut().insert(
-        header::CONTENT_TYPE,
-        header::HeaderValue::from_static("text/plain; charset=utf-8"),
-    );
-    res.body(ResBody::Once(BytesMut::from(ROBOT).freeze()));
-    res.status_code(StatusCode::OK);
-}
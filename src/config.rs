@@ -0,0 +1,678 @@
+use serde::Deserialize;
+use std::{env, fmt::Display, net::IpAddr, path::Path, str::FromStr, sync::OnceLock};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Used when `BLOG_CONFIG` is not set, the file is optional in that case
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+const MIN_SECRET_KEY_LEN: usize = 32;
+
+/// The sitemap protocol allows at most 50000 urls per file
+const MAX_SITEMAP_SIZE: i64 = 50000;
+
+/// Already minutes of hashing in a browser
+const MAX_GUEST_DIFFICULTY: u32 = 28;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub site: SiteConfig,
+    pub github: GithubConfig,
+    /// OpenID Connect providers, e.g. a self-hosted Gitea or Keycloak
+    pub oidc: Vec<OidcConfig>,
+    pub smtp: SmtpConfig,
+    pub ipstack: IpstackConfig,
+    pub comment: CommentConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Env `LISTEN_ADDR`
+    pub bind: String,
+    /// Env `LISTEN_PORT`
+    pub port: u16,
+    /// Key of the signed links, env `SECRET_KEY`. A random one is used when empty,
+    /// so the links sent before a restart stop working
+    pub secret_key: String,
+    /// Admins must log in with a one-time password, env `REQUIRE_ADMIN_TOTP`
+    pub require_admin_totp: bool,
+    /// Addresses of the reverse proxies whose `X-Real-IP` is believed, env `TRUSTED_PROXIES`
+    /// separated by commas. Any other client could pick its own ip with the header
+    pub trusted_proxies: Vec<String>,
+}
+
+impl ServerConfig {
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|proxy| proxy.parse::<IpAddr>() == Ok(ip))
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "127.0.0.1".to_string(),
+            port: 8080,
+            secret_key: String::new(),
+            require_admin_totp: true,
+            trusted_proxies: vec!["127.0.0.1".to_string(), "::1".to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Env `DATABASE_URL`
+    pub postgres_url: String,
+    /// Env `REDIS_URL`
+    pub redis_url: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    /// Public url of the site without the trailing `/`, env `SITE_URL`
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    /// Number of articles in a feed, env `FEED_SIZE`
+    pub feed_size: i64,
+    /// Urls per sitemap file before switching to a sitemap index, env `SITEMAP_SIZE`
+    pub sitemap_size: i64,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        SiteConfig {
+            url: String::new(),
+            title: "driftluo's blog".to_string(),
+            description: "This is driftluo's Personal Blog.".to_string(),
+            feed_size: 10,
+            sitemap_size: MAX_SITEMAP_SIZE,
+        }
+    }
+}
+
+/// Github login is disabled when the client is not set
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GithubConfig {
+    /// Env `GITHUB_CLIENT_ID`
+    pub client_id: String,
+    /// Env `GITHUB_CLIENT_SECRET`
+    pub client_secret: String,
+    /// Callback registered on the oauth app, `{site.url}/api/v1/login_with_github` by default
+    pub redirect_uri: Option<String>,
+}
+
+impl GithubConfig {
+    pub fn enabled(&self) -> bool {
+        !self.client_id.is_empty()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OidcConfig {
+    /// Used in the login urls, `/api/v1/oauth/{name}/authorize`
+    pub name: String,
+    /// Shown on the login page, the name by default
+    pub display_name: Option<String>,
+    /// `{issuer}/.well-known/openid-configuration` must exist
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: String,
+    /// `{site.url}/api/v1/oauth/{name}/callback` by default
+    pub redirect_uri: Option<String>,
+}
+
+fn default_oidc_scopes() -> String {
+    "openid profile email".to_string()
+}
+
+/// Mails are not sent when the host is not set
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpConfig {
+    /// Env `SMTP_HOST`
+    pub host: String,
+    /// Env `SMTP_PORT`
+    pub port: u16,
+    /// `starttls`, `tls` or `none`, the latter is meant for a local test server
+    pub security: SmtpSecurity,
+    /// Env `SMTP_USERNAME`, no authentication when empty
+    pub username: String,
+    /// Env `SMTP_PASSWORD`
+    pub password: String,
+    /// Sender of the mails, e.g. `Blog <noreply@example.com>`, env `SMTP_FROM`
+    pub from: String,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        SmtpConfig {
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::Starttls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+        }
+    }
+}
+
+impl SmtpConfig {
+    pub fn enabled(&self) -> bool {
+        !self.host.is_empty()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    Starttls,
+    Tls,
+    None,
+}
+
+/// Visitor ip locations are not queried when the key is not set
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpstackConfig {
+    /// https://ipstack.com access key, env `IPSTACK_KEY`
+    pub key: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommentConfig {
+    /// Which comments wait for a moderator, env `COMMENT_MODERATION`
+    pub moderation: ModerationMode,
+    /// Comments containing one of them wait in the `rules` mode, the case is ignored
+    pub moderation_words: Vec<String>,
+    /// Comments with more distinct links wait for a moderator
+    pub max_links: usize,
+    /// Comments a user may post in a minute
+    pub user_rate_limit: i64,
+    /// Comments an ip may post in a minute
+    pub ip_rate_limit: i64,
+    /// Comments the classifier finds more likely to be spam wait for a moderator
+    pub spam_threshold: f64,
+    /// Minutes during which authors can edit their comments, admins always can
+    pub edit_window: i64,
+    /// Visitors without an account can comment, always moderated, env `COMMENT_GUEST`
+    pub guest: bool,
+    /// Leading zero bits of the proof of work a guest computes before commenting
+    pub guest_difficulty: u32,
+}
+
+impl Default for CommentConfig {
+    fn default() -> Self {
+        CommentConfig {
+            moderation: ModerationMode::None,
+            moderation_words: Vec::new(),
+            max_links: 2,
+            user_rate_limit: 5,
+            ip_rate_limit: 20,
+            spam_threshold: 0.9,
+            edit_window: 15,
+            guest: false,
+            guest_difficulty: 18,
+        }
+    }
+}
+
+/// The comments of the moderators are never held
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationMode {
+    /// Every comment is published at once
+    #[default]
+    None,
+    All,
+    /// Until the user has an approved comment
+    First,
+    /// The comments matching `moderation_words`
+    Rules,
+}
+
+impl FromStr for ModerationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ModerationMode::None),
+            "all" => Ok(ModerationMode::All),
+            "first" => Ok(ModerationMode::First),
+            "rules" => Ok(ModerationMode::Rules),
+            _ => Err("expected none, all, first or rules".to_string()),
+        }
+    }
+}
+
+impl Config {
+    /// Read the file named by `BLOG_CONFIG` or `config.toml`, then apply the env overrides
+    pub fn load() -> Result<Config, String> {
+        let (path, explicit) = match env::var("BLOG_CONFIG") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_PATH.to_string(), false),
+        };
+
+        let mut config = if Path::new(&path).exists() {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| format!("can't read config file '{}': {}", path, e))?;
+            Config::parse(&text).map_err(|e| format!("config file '{}': {}", path, e))?
+        } else if explicit {
+            return Err(format!("config file '{}' not found", path));
+        } else {
+            Config::default()
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| format!("{}", e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        override_with_env("LISTEN_ADDR", &mut self.server.bind)?;
+        override_with_env("LISTEN_PORT", &mut self.server.port)?;
+        override_with_env("SECRET_KEY", &mut self.server.secret_key)?;
+        override_with_env("REQUIRE_ADMIN_TOTP", &mut self.server.require_admin_totp)?;
+        if let Ok(raw) = env::var("TRUSTED_PROXIES") {
+            self.server.trusted_proxies = raw
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        override_with_env("DATABASE_URL", &mut self.database.postgres_url)?;
+        override_with_env("REDIS_URL", &mut self.database.redis_url)?;
+        override_with_env("SITE_URL", &mut self.site.url)?;
+        override_with_env("FEED_SIZE", &mut self.site.feed_size)?;
+        override_with_env("SITEMAP_SIZE", &mut self.site.sitemap_size)?;
+        override_with_env("GITHUB_CLIENT_ID", &mut self.github.client_id)?;
+        override_with_env("GITHUB_CLIENT_SECRET", &mut self.github.client_secret)?;
+        override_with_env("SMTP_HOST", &mut self.smtp.host)?;
+        override_with_env("SMTP_PORT", &mut self.smtp.port)?;
+        override_with_env("SMTP_USERNAME", &mut self.smtp.username)?;
+        override_with_env("SMTP_PASSWORD", &mut self.smtp.password)?;
+        override_with_env("SMTP_FROM", &mut self.smtp.from)?;
+        override_with_env("IPSTACK_KEY", &mut self.ipstack.key)?;
+        override_with_env("COMMENT_MODERATION", &mut self.comment.moderation)?;
+        override_with_env("COMMENT_GUEST", &mut self.comment.guest)?;
+        Ok(())
+    }
+
+    /// Check every value and report all the problems at once
+    pub fn validate(&mut self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        if self.server.bind.parse::<IpAddr>().is_err() {
+            errors.push(format!(
+                "server.bind (LISTEN_ADDR): '{}' is not an ip address",
+                self.server.bind
+            ));
+        }
+        if self.server.port == 0 {
+            errors.push("server.port (LISTEN_PORT): must not be 0".to_string());
+        }
+        if !self.server.secret_key.is_empty() && self.server.secret_key.len() < MIN_SECRET_KEY_LEN {
+            errors.push(format!(
+                "server.secret_key (SECRET_KEY): must be at least {} characters",
+                MIN_SECRET_KEY_LEN
+            ));
+        }
+        for proxy in &self.server.trusted_proxies {
+            if proxy.parse::<IpAddr>().is_err() {
+                errors.push(format!(
+                    "server.trusted_proxies (TRUSTED_PROXIES): '{}' is not an ip address",
+                    proxy
+                ));
+            }
+        }
+        if self.database.postgres_url.is_empty() {
+            errors.push("database.postgres_url (DATABASE_URL): must be set".to_string());
+        }
+        if self.database.redis_url.is_empty() {
+            errors.push("database.redis_url (REDIS_URL): must be set".to_string());
+        }
+
+        self.site.url = self.site.url.trim_end_matches('/').to_string();
+        match url::Url::parse(&self.site.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => (),
+            _ => errors.push(format!(
+                "site.url (SITE_URL): '{}' is not an http(s) url",
+                self.site.url
+            )),
+        }
+        if self.site.feed_size <= 0 {
+            errors.push("site.feed_size (FEED_SIZE): must be greater than 0".to_string());
+        }
+        if self.site.sitemap_size <= 0 || self.site.sitemap_size > MAX_SITEMAP_SIZE {
+            errors.push(format!(
+                "site.sitemap_size (SITEMAP_SIZE): must be between 1 and {}",
+                MAX_SITEMAP_SIZE
+            ));
+        }
+
+        if self.github.client_id.is_empty() != self.github.client_secret.is_empty() {
+            errors.push(
+                "github: client_id (GITHUB_CLIENT_ID) and client_secret (GITHUB_CLIENT_SECRET) must be set together"
+                    .to_string(),
+            );
+        }
+
+        let mut names = vec!["github"];
+        for (index, oidc) in self.oidc.iter().enumerate() {
+            let valid_name = !oidc.name.is_empty()
+                && oidc
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !valid_name {
+                errors.push(format!(
+                    "oidc[{}].name: '{}' must be lowercase letters, digits and '-'",
+                    index, oidc.name
+                ));
+            } else if names.contains(&oidc.name.as_str()) {
+                errors.push(format!(
+                    "oidc[{}].name: '{}' is used twice",
+                    index, oidc.name
+                ));
+            } else {
+                names.push(&oidc.name);
+            }
+            if url::Url::parse(&oidc.issuer).is_err() {
+                errors.push(format!(
+                    "oidc[{}].issuer: '{}' is not an url",
+                    index, oidc.issuer
+                ));
+            }
+            if oidc.client_id.is_empty() || oidc.client_secret.is_empty() {
+                errors.push(format!(
+                    "oidc[{}]: client_id and client_secret must be set",
+                    index
+                ));
+            }
+        }
+
+        if self.smtp.enabled() {
+            if self.smtp.port == 0 {
+                errors.push("smtp.port (SMTP_PORT): must not be 0".to_string());
+            }
+            if self.smtp.from.parse::<lettre::message::Mailbox>().is_err() {
+                errors.push(format!(
+                    "smtp.from (SMTP_FROM): '{}' is not a mailbox",
+                    self.smtp.from
+                ));
+            }
+        }
+
+        if self.comment.moderation == ModerationMode::Rules
+            && self.comment.moderation_words.is_empty()
+        {
+            errors.push("comment.moderation_words: must be set in the rules mode".to_string());
+        }
+        if self.comment.user_rate_limit <= 0 || self.comment.ip_rate_limit <= 0 {
+            errors.push(
+                "comment: user_rate_limit and ip_rate_limit must be greater than 0".to_string(),
+            );
+        }
+        if !(0.5..=1.0).contains(&self.comment.spam_threshold) {
+            errors.push("comment.spam_threshold: must be between 0.5 and 1".to_string());
+        }
+        if self.comment.edit_window < 0 {
+            errors.push("comment.edit_window: must not be negative".to_string());
+        }
+        if !(1..=MAX_GUEST_DIFFICULTY).contains(&self.comment.guest_difficulty) {
+            errors.push(format!(
+                "comment.guest_difficulty: must be between 1 and {}",
+                MAX_GUEST_DIFFICULTY
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("invalid config:\n  {}", errors.join("\n  ")))
+        }
+    }
+}
+
+fn override_with_env<T>(name: &str, value: &mut T) -> Result<(), String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(raw) = env::var(name) {
+        *value = raw
+            .parse()
+            .map_err(|e| format!("env {}: invalid value '{}': {}", name, raw, e))?;
+    }
+    Ok(())
+}
+
+/// Load the config, must be called once at startup before anything reads it
+pub fn init_config() -> Result<&'static Config, String> {
+    let config = Config::load()?;
+    CONFIG
+        .set(config)
+        .map_err(|_| "config is already initialized".to_string())?;
+    Ok(get_config())
+}
+
+#[inline]
+pub fn get_config() -> &'static Config {
+    CONFIG.get().expect("Config is not initialized")
+}
+
+#[cfg(test)]
+mod test {
+    use super::Config;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let mut config = Config::parse(
+            r#"
+            [server]
+            bind = "0.0.0.0"
+
+            [database]
+            postgres_url = "postgres://localhost/blog"
+            redis_url = "redis://127.0.0.1:6379/1"
+
+            [site]
+            url = "https://example.com/"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.site.url, "https://example.com");
+        assert_eq!(config.site.feed_size, 10);
+        assert!(!config.github.enabled());
+        assert!(config.server.require_admin_totp);
+        assert!(config.server.is_trusted_proxy("127.0.0.1".parse().unwrap()));
+        assert!(!config.server.is_trusted_proxy("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_validate_reports_every_error() {
+        let mut config = Config::parse(
+            r#"
+            [server]
+            bind = "localhost"
+            secret_key = "short"
+            trusted_proxies = ["nginx"]
+
+            [site]
+            url = "example.com"
+            sitemap_size = 60000
+
+            [github]
+            client_id = "id"
+            "#,
+        )
+        .unwrap();
+        let err = config.validate().unwrap_err();
+        for field in [
+            "server.bind",
+            "server.secret_key",
+            "server.trusted_proxies",
+            "database.postgres_url",
+            "database.redis_url",
+            "site.url",
+            "site.sitemap_size",
+            "github",
+        ] {
+            assert!(err.contains(field), "{} missing in {}", field, err);
+        }
+    }
+
+    #[test]
+    fn test_oidc_providers() {
+        let mut config = Config::parse(
+            r#"
+            [database]
+            postgres_url = "postgres://localhost/blog"
+            redis_url = "redis://127.0.0.1:6379/1"
+
+            [site]
+            url = "https://example.com"
+
+            [[oidc]]
+            name = "gitea"
+            issuer = "https://git.example.com"
+            client_id = "id"
+            client_secret = "secret"
+
+            [[oidc]]
+            name = "gitea"
+            issuer = "not an url"
+            client_id = "id"
+            client_secret = ""
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.oidc[0].scopes, "openid profile email");
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("oidc[1].name"));
+        assert!(err.contains("oidc[1].issuer"));
+        assert!(err.contains("oidc[1]: client_id"));
+        assert!(!err.contains("oidc[0]"));
+    }
+
+    #[test]
+    fn test_smtp() {
+        let mut config = Config::parse(
+            r#"
+            [database]
+            postgres_url = "postgres://localhost/blog"
+            redis_url = "redis://127.0.0.1:6379/1"
+
+            [site]
+            url = "https://example.com"
+
+            [smtp]
+            host = "127.0.0.1"
+            port = 1025
+            security = "none"
+            from = "Blog <noreply@example.com>"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(config.smtp.enabled());
+        assert_eq!(config.smtp.security, super::SmtpSecurity::None);
+
+        config.smtp.from = "noreply".to_string();
+        assert!(config.validate().unwrap_err().contains("smtp.from"));
+        assert!(Config::parse("[smtp]\nsecurity = \"ssl\"").is_err());
+    }
+
+    #[test]
+    fn test_comment_moderation() {
+        let mut config = Config::parse(
+            r#"
+            [database]
+            postgres_url = "postgres://localhost/blog"
+            redis_url = "redis://127.0.0.1:6379/1"
+
+            [site]
+            url = "https://example.com"
+
+            [comment]
+            moderation = "rules"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.comment.moderation, super::ModerationMode::Rules);
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .contains("comment.moderation_words")
+        );
+
+        config.comment.moderation_words = vec!["casino".to_string()];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.comment.max_links, 2);
+
+        config.comment.spam_threshold = 0.2;
+        config.comment.ip_rate_limit = 0;
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("comment.spam_threshold"));
+        assert!(err.contains("ip_rate_limit"));
+        config.comment.spam_threshold = 0.9;
+        config.comment.ip_rate_limit = 20;
+        config.comment.edit_window = -1;
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .contains("comment.edit_window")
+        );
+        config.comment.edit_window = 0;
+        assert!(config.validate().is_ok());
+        config.comment.guest_difficulty = 0;
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .contains("comment.guest_difficulty")
+        );
+        config.comment.guest_difficulty = 18;
+        assert_eq!(
+            Config::default().comment.moderation,
+            super::ModerationMode::None
+        );
+        assert_eq!("first".parse(), Ok(super::ModerationMode::First));
+        assert!("some".parse::<super::ModerationMode>().is_err());
+        assert!(Config::parse("[comment]\nmoderation = \"some\"").is_err());
+        assert!(!Config::default().comment.guest);
+        assert!(
+            Config::parse("[comment]\nguest = true")
+                .unwrap()
+                .comment
+                .guest
+        );
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        assert!(Config::parse("[server]\nlisten = 1").is_err());
+    }
+}
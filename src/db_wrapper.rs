@@ -1,7 +1,9 @@
 use redis::aio::ConnectionManager;
 use sqlx::postgres::PgPool;
+use std::fmt;
 use std::sync::OnceLock;
-use std::{env, fmt};
+
+use crate::config::get_config;
 
 static REDIS: OnceLock<RedisManager> = OnceLock::new();
 static POSTGRES: OnceLock<PgPool> = OnceLock::new();
@@ -211,6 +213,27 @@ impl RedisManager {
         }
     }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn hincrby(&self, redis_key: &str, hash_key: &str, delta: i64) -> i64 {
+        loop {
+            match redis::cmd("hincrby")
+                .arg(redis_key)
+                .arg(hash_key)
+                .arg(delta)
+                .query_async(&mut self.pool.clone())
+                .await
+            {
+                Ok(res) => return res,
+                Err(e) => {
+                    // https://docs.rs/redis/0.20.0/redis/aio/struct.ConnectionManager.html
+                    // Connection drop is an expected error, just need to be executed again
+                    tracing::info!("{}", e);
+                    continue;
+                }
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn hget<T>(&self, redis_key: &str, hash_key: &str) -> Result<T, redis::RedisError>
     where
@@ -234,6 +257,28 @@ impl RedisManager {
         }
     }
 
+    #[tracing::instrument(skip(self))]
+    pub async fn hgetall<T>(&self, redis_key: &str) -> T
+    where
+        T: redis::FromRedisValue + fmt::Debug,
+    {
+        loop {
+            match redis::cmd("hgetall")
+                .arg(redis_key)
+                .query_async(&mut self.pool.clone())
+                .await
+            {
+                Ok(res) => return res,
+                Err(e) => {
+                    // https://docs.rs/redis/0.20.0/redis/aio/struct.ConnectionManager.html
+                    // Connection drop is an expected error, just need to be executed again
+                    tracing::info!("{}", e);
+                    continue;
+                }
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn hexists(&self, redis_key: &str, hash_key: &str) -> bool {
         loop {
@@ -390,7 +435,7 @@ impl fmt::Debug for RedisManager {
 }
 
 pub async fn create_redis_pool(path: Option<&str>) {
-    let database_url = env::var("REDIS_URL").expect("DATABASE_URL must be set");
+    let database_url = &get_config().database.redis_url;
     let pool = match path {
         Some(path) => RedisManager::new_with_script(database_url.as_str(), path).await,
         None => RedisManager::new(database_url.as_str()).await,
@@ -400,8 +445,8 @@ pub async fn create_redis_pool(path: Option<&str>) {
 }
 
 pub async fn create_pg_pool() {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = PgPool::connect(&database_url)
+    let database_url = &get_config().database.postgres_url;
+    let pool = PgPool::connect(database_url)
         .await
         .expect("Failed to create pool.");
     POSTGRES
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 pub mod api;
+pub mod config;
 pub mod db_wrapper;
 pub mod models;
 pub mod utils;
@@ -9,7 +10,10 @@ pub trait Routers {
     fn build(self) -> Vec<salvo::Router>;
 }
 
-pub const PERMISSION: &str = "permission";
+/// `Option<Role>` of the visitor
+pub const ROLE: &str = "role";
 pub const WEB: &str = "web";
 pub const COOKIE: &str = "cookie";
 pub const USER_INFO: &str = "user_info";
+/// `TokenScopes` of the requests authenticated with an api token
+pub const API_TOKEN: &str = "api_token";
@@ -0,0 +1,181 @@
+use crate::{
+    db_wrapper::get_postgres,
+    models::{role::Role, user::UserInfo},
+    utils::{random_string, sha3_256_encode},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Uuid, chrono::NaiveDateTime};
+
+/// `{resource}:read` is checked on GET requests, `{resource}:write` on the others.
+/// A token never does more than the role of its owner allows.
+pub const SCOPES: &[&str] = &[
+    "article:read",
+    "article:write",
+    "tag:read",
+    "tag:write",
+    "fund:read",
+    "fund:write",
+    "user:read",
+    "user:write",
+    "stats:read",
+    "audit:read",
+];
+
+/// Scopes a token of the role may be given
+pub fn available_scopes(role: Role) -> Vec<&'static str> {
+    SCOPES.iter().copied().filter(|s| role.can(s)).collect()
+}
+
+const TOKEN_PREFIX: &str = "blog_";
+/// Characters of the token kept to tell them apart
+const SHOWN_PREFIX_LEN: usize = 12;
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expire_time: Option<NaiveDateTime>,
+    pub last_used_time: Option<NaiveDateTime>,
+    pub last_used_ip: Option<String>,
+    pub create_time: NaiveDateTime,
+}
+
+/// Set on the depot for requests authenticated with a token
+#[derive(Debug, Clone)]
+pub struct TokenScopes(pub Vec<String>);
+
+impl TokenScopes {
+    pub fn allows(&self, scope: &str) -> bool {
+        self.0.iter().any(|s| s == scope)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NewApiToken {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Never expires when missing
+    pub expires_in_days: Option<i64>,
+}
+
+/// The token is only returned here
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiToken,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevokeApiToken {
+    pub id: Uuid,
+}
+
+#[derive(sqlx::FromRow)]
+struct TokenOwner {
+    token_id: Uuid,
+    scopes: Vec<String>,
+    #[sqlx(flatten)]
+    info: UserInfo,
+}
+
+impl NewApiToken {
+    pub async fn insert(self, user_id: Uuid, role: Role) -> Result<CreatedApiToken, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Name is empty".to_string());
+        }
+        if self.scopes.is_empty() {
+            return Err("At least one scope is needed".to_string());
+        }
+        if let Some(scope) = self.scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+            return Err(format!("Unknown scope '{}'", scope));
+        }
+        if let Some(scope) = self.scopes.iter().find(|s| !role.can(s)) {
+            return Err(format!("Your role doesn't allow '{}'", scope));
+        }
+        if matches!(self.expires_in_days, Some(days) if days <= 0) {
+            return Err("expires_in_days must be greater than 0".to_string());
+        }
+
+        let token = format!("{}{}", TOKEN_PREFIX, random_string(40));
+        let mut scopes = self.scopes;
+        scopes.sort();
+        scopes.dedup();
+        let info = sqlx::query_as::<_, ApiToken>(
+            r#"INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, expire_time)
+            VALUES ($1, $2, $3, $4, $5, current_timestamp + make_interval(days => $6::int))
+            RETURNING id, name, token_prefix, scopes, expire_time, last_used_time, last_used_ip, create_time"#,
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(sha3_256_encode(token.clone()))
+        .bind(&token[..SHOWN_PREFIX_LEN])
+        .bind(&scopes)
+        .bind(self.expires_in_days)
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        Ok(CreatedApiToken { token, info })
+    }
+}
+
+impl ApiToken {
+    pub async fn list(user_id: Uuid) -> Result<Vec<Self>, String> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"SELECT id, name, token_prefix, scopes, expire_time, last_used_time, last_used_ip, create_time
+            FROM api_tokens
+            WHERE user_id = $1
+            ORDER BY create_time"#,
+        )
+        .bind(user_id)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    pub async fn revoke(user_id: Uuid, id: Uuid) -> Result<u64, String> {
+        sqlx::query(r#"DELETE FROM api_tokens WHERE id = $1 AND user_id = $2"#)
+            .bind(id)
+            .bind(user_id)
+            .execute(get_postgres())
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// The owner of a live token and its scopes, the use is recorded at most once a minute
+    pub async fn authenticate(token: &str, ip: Option<String>) -> Option<(UserInfo, TokenScopes)> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let owner = sqlx::query_as::<_, TokenOwner>(
+            r#"SELECT t.id AS token_id, t.scopes,
+                u.id, u.account, u.nickname, u.role, u.say, u.email, u.email_verified, u.create_time
+            FROM api_tokens t JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = $1 AND u.disabled = 0
+            AND (t.expire_time IS NULL OR t.expire_time > current_timestamp)"#,
+        )
+        .bind(sha3_256_encode(token.to_string()))
+        .fetch_optional(get_postgres())
+        .await
+        .ok()
+        .flatten()?;
+
+        if let Err(e) = sqlx::query(
+            r#"UPDATE api_tokens SET last_used_time = current_timestamp, last_used_ip = $2
+            WHERE id = $1
+            AND (last_used_time IS NULL OR last_used_time < current_timestamp - interval '1 minute')"#,
+        )
+        .bind(owner.token_id)
+        .bind(ip)
+        .execute(get_postgres())
+        .await
+        {
+            tracing::warn!("can't record the use of token {}: {}", owner.token_id, e);
+        }
+        Some((owner.info, TokenScopes(owner.scopes)))
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    db_wrapper::get_postgres,
+    utils::{markdown_render, unified_diff},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Uuid, chrono::NaiveDateTime};
+
+/// A revision without its content, used on the revision list
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct RevisionSummary {
+    pub id: Uuid,
+    pub article_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author: Option<String>,
+    pub title: String,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct ArticleRevision {
+    pub id: Uuid,
+    pub article_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author: Option<String>,
+    pub title: String,
+    pub raw_content: String,
+    pub create_time: NaiveDateTime,
+}
+
+impl ArticleRevision {
+    /// Query all revisions of an article, newest first
+    pub async fn list(article_id: Uuid) -> Result<Vec<RevisionSummary>, String> {
+        sqlx::query_as::<_, RevisionSummary>(
+            r#"SELECT a.id, a.article_id, a.author_id, b.nickname AS author, a.title, a.create_time
+            FROM article_revisions a LEFT JOIN users b ON a.author_id = b.id
+            WHERE a.article_id = $1
+            ORDER BY a.create_time DESC"#,
+        )
+        .bind(article_id)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    pub async fn view(id: Uuid) -> Result<Self, String> {
+        sqlx::query_as::<_, ArticleRevision>(
+            r#"SELECT a.id, a.article_id, a.author_id, b.nickname AS author, a.title, a.raw_content, a.create_time
+            FROM article_revisions a LEFT JOIN users b ON a.author_id = b.id
+            WHERE a.id = $1"#,
+        )
+        .bind(id)
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => "Revision not found".to_string(),
+            other => format!("{}", other),
+        })
+    }
+
+    /// Unified diff between two revisions of the same article
+    pub async fn diff(from: Uuid, to: Uuid) -> Result<RevisionDiff, String> {
+        let old = Self::view(from).await?;
+        let new = Self::view(to).await?;
+        if old.article_id != new.article_id {
+            return Err("Revisions belong to different articles".to_string());
+        }
+
+        let diff = unified_diff(
+            &old.raw_content,
+            &new.raw_content,
+            &format!("{}\t{}", old.id, old.create_time),
+            &format!("{}\t{}", new.id, new.create_time),
+        );
+        Ok(RevisionDiff {
+            article_id: old.article_id,
+            from: old.id,
+            to: new.id,
+            old_title: old.title,
+            new_title: new.title,
+            diff,
+        })
+    }
+
+    /// Write the revision back to the article, the restore itself is recorded as a new revision
+    pub async fn restore(id: Uuid, author_id: Uuid) -> Result<u64, String> {
+        let revision = Self::view(id).await?;
+        sqlx::query(
+            r#"WITH updated AS (
+                UPDATE articles SET title = $1, content = $2, raw_content = $3 WHERE id = $4
+                RETURNING id, title, raw_content
+            )
+            INSERT INTO article_revisions (article_id, author_id, title, raw_content)
+            SELECT id, $5, title, raw_content FROM updated"#,
+        )
+        .bind(&revision.title)
+        .bind(markdown_render(&revision.raw_content))
+        .bind(&revision.raw_content)
+        .bind(revision.article_id)
+        .bind(author_id)
+        .execute(get_postgres())
+        .await
+        .map(|r| r.rows_affected())
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RevisionDiff {
+    pub article_id: Uuid,
+    pub from: Uuid,
+    pub to: Uuid,
+    pub old_title: String,
+    pub new_title: String,
+    pub diff: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RestoreRevision {
+    pub id: Uuid,
+}
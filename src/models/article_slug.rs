@@ -0,0 +1,166 @@
+use crate::{
+    db_wrapper::get_postgres,
+    utils::{is_valid_slug, slugify},
+};
+use sqlx::{PgConnection, Row};
+use uuid::Uuid;
+
+/// Where a slug from the url points to
+pub enum SlugTarget {
+    /// The current slug of an article
+    Current(Uuid),
+    /// An old slug, the article has been renamed to this slug
+    Moved(String),
+}
+
+pub struct ArticleSlug;
+
+impl ArticleSlug {
+    /// Pick the slug of an article, either the manual one if it is free,
+    /// or one generated from the title with a numeric suffix on collision.
+    /// `article_id` is the article being edited, its own slugs never count as taken.
+    pub async fn choose(
+        manual: Option<&str>,
+        title: &str,
+        article_id: Option<Uuid>,
+    ) -> Result<String, String> {
+        match manual {
+            Some(slug) => {
+                if !is_valid_slug(slug) {
+                    return Err(format!(
+                        "Invalid slug '{}': only lowercase letters, digits and '-' are allowed",
+                        slug
+                    ));
+                }
+                if Self::is_taken(slug, article_id).await? {
+                    return Err(format!("Slug '{}' is already in use", slug));
+                }
+                Ok(slug.to_string())
+            }
+            None => {
+                let base = slugify(title);
+                let mut slug = base.clone();
+                let mut suffix = 2;
+                while Self::is_taken(&slug, article_id).await? {
+                    slug = format!("{}-{}", base, suffix);
+                    suffix += 1;
+                }
+                Ok(slug)
+            }
+        }
+    }
+
+    async fn is_taken(slug: &str, article_id: Option<Uuid>) -> Result<bool, String> {
+        sqlx::query(
+            r#"SELECT EXISTS (SELECT 1 FROM articles WHERE slug = $1 AND id IS DISTINCT FROM $2)
+                OR EXISTS (SELECT 1 FROM article_slug_history WHERE slug = $1 AND article_id IS DISTINCT FROM $2)"#,
+        )
+        .bind(slug)
+        .bind(article_id)
+        .map(|row: sqlx::postgres::PgRow| row.get::<bool, _>(0))
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    /// Change the slug of an article, the old one is kept in history.
+    /// Runs on the transaction of the caller, so that the edit and the history stay together.
+    pub async fn rename(
+        conn: &mut PgConnection,
+        article_id: Uuid,
+        old: Option<&str>,
+        new: &str,
+    ) -> Result<(), String> {
+        if old == Some(new) {
+            return Ok(());
+        }
+        sqlx::query(r#"UPDATE articles SET slug = $1 WHERE id = $2"#)
+            .bind(new)
+            .bind(article_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("{}", e))?;
+        // the article may take back one of its old slugs
+        sqlx::query(r#"DELETE FROM article_slug_history WHERE slug = $1"#)
+            .bind(new)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("{}", e))?;
+        if let Some(old) = old {
+            sqlx::query(
+                r#"INSERT INTO article_slug_history (slug, article_id) VALUES ($1, $2)
+                ON CONFLICT (slug) DO UPDATE SET article_id = EXCLUDED.article_id"#,
+            )
+            .bind(old)
+            .bind(article_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("{}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Current slug of an article
+    pub async fn current(article_id: Uuid, admin: bool) -> Option<String> {
+        sqlx::query(r#"SELECT slug FROM articles WHERE id = $1 AND (published = true OR $2)"#)
+            .bind(article_id)
+            .bind(admin)
+            .map(|row: sqlx::postgres::PgRow| row.get::<Option<String>, _>(0))
+            .fetch_optional(get_postgres())
+            .await
+            .ok()
+            .flatten()
+            .flatten()
+    }
+
+    /// Resolve a slug of a published article, old slugs resolve to the current one
+    pub async fn lookup(slug: &str) -> Option<SlugTarget> {
+        if let Ok(Some(id)) =
+            sqlx::query(r#"SELECT id FROM articles WHERE slug = $1 AND published = true"#)
+                .bind(slug)
+                .map(|row: sqlx::postgres::PgRow| row.get::<Uuid, _>(0))
+                .fetch_optional(get_postgres())
+                .await
+        {
+            return Some(SlugTarget::Current(id));
+        }
+
+        sqlx::query(
+            r#"SELECT b.slug FROM article_slug_history a JOIN articles b ON a.article_id = b.id
+            WHERE a.slug = $1 AND b.published = true AND b.slug IS NOT NULL"#,
+        )
+        .bind(slug)
+        .map(|row: sqlx::postgres::PgRow| row.get::<String, _>(0))
+        .fetch_optional(get_postgres())
+        .await
+        .ok()
+        .flatten()
+        .map(SlugTarget::Moved)
+    }
+
+    /// Generate slugs for the articles created before slugs existed
+    pub async fn fill_missing() {
+        let articles = sqlx::query(r#"SELECT id, title FROM articles WHERE slug IS NULL"#)
+            .map(|row: sqlx::postgres::PgRow| (row.get::<Uuid, _>(0), row.get::<String, _>(1)))
+            .fetch_all(get_postgres())
+            .await
+            .unwrap_or_default();
+
+        for (id, title) in articles {
+            let slug = match Self::choose(None, &title, Some(id)).await {
+                Ok(slug) => slug,
+                Err(e) => {
+                    tracing::error!("can't generate slug of article {}: {}", id, e);
+                    continue;
+                }
+            };
+            let res = match get_postgres().acquire().await {
+                Ok(mut conn) => Self::rename(&mut conn, id, None, &slug).await,
+                Err(e) => Err(format!("{}", e)),
+            };
+            if let Err(e) = res {
+                tracing::error!("can't set slug of article {}: {}", id, e);
+            }
+        }
+    }
+}
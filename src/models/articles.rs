@@ -1,10 +1,11 @@
 use crate::{
     db_wrapper::get_postgres,
     models::{
+        article_slug::ArticleSlug,
         article_tag_relation::{RelationTag, Relations},
         notify::UserNotify,
     },
-    utils::markdown_render,
+    utils::{HIGHLIGHT_START, HIGHLIGHT_STOP, highlight_to_html, markdown_render},
 };
 use serde::{Deserialize, Serialize};
 use sqlx::types::{Uuid, chrono::NaiveDateTime};
@@ -12,28 +13,51 @@ struct InsertArticle<'a> {
     title: &'a str,
     raw_content: &'a str,
     content: String,
+    slug: String,
+    publish_at: Option<NaiveDateTime>,
+    author_id: Uuid,
 }
 
 impl<'a> InsertArticle<'a> {
-    fn new(title: &'a str, raw_content: &'a str) -> Self {
+    fn new(
+        title: &'a str,
+        raw_content: &'a str,
+        slug: String,
+        publish_at: Option<NaiveDateTime>,
+        author_id: Uuid,
+    ) -> Self {
         let content = markdown_render(raw_content);
         InsertArticle {
             title,
             raw_content,
             content,
+            slug,
+            publish_at,
+            author_id,
         }
     }
 
+    /// Insert the article together with its first revision
     async fn insert(self) -> Result<Uuid, String> {
         use sqlx::Row;
         sqlx::query(
-            r#"Insert into articles (title, raw_content, content) VALUES ($1, $2, $3)
-            RETURNING id
+            r#"WITH inserted AS (
+                Insert into articles (title, raw_content, content, slug, publish_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, title, raw_content
+            ), revision AS (
+                INSERT INTO article_revisions (article_id, author_id, title, raw_content)
+                SELECT id, $6, title, raw_content FROM inserted
+            )
+            SELECT id FROM inserted
             "#,
         )
         .bind(self.title)
         .bind(self.raw_content)
         .bind(self.content)
+        .bind(self.slug)
+        .bind(self.publish_at)
+        .bind(self.author_id)
         .map(|row: sqlx::postgres::PgRow| row.get::<Uuid, _>(0))
         .fetch_one(get_postgres())
         .await
@@ -47,30 +71,58 @@ pub struct NewArticle {
     pub raw_content: String,
     pub exist_tags: Option<Vec<Uuid>>,
     pub new_tags: Option<Vec<String>>,
+    /// Publish automatically once this time is reached
+    pub publish_at: Option<NaiveDateTime>,
+    /// Generated from the title when not given
+    pub slug: Option<String>,
 }
 
 impl NewArticle {
-    pub async fn insert(self) -> bool {
-        let id = match InsertArticle::new(&self.title, &self.raw_content)
-            .insert()
-            .await
+    /// Id of the new article, `None` when it failed
+    pub async fn insert(self, author_id: Uuid) -> Option<Uuid> {
+        let slug = match ArticleSlug::choose(self.slug.as_deref(), &self.title, None).await {
+            Ok(slug) => slug,
+            Err(e) => {
+                tracing::error!("Failed to choose article slug: {}", e);
+                return None;
+            }
+        };
+        let id = match InsertArticle::new(
+            &self.title,
+            &self.raw_content,
+            slug,
+            self.publish_at,
+            author_id,
+        )
+        .insert()
+        .await
         {
             Ok(id) => id,
             Err(e) => {
                 tracing::error!("Failed to insert article: {}", e);
-                return false;
+                return None;
             }
         };
-        if self.new_tags.is_some() || self.exist_tags.is_some() {
-            RelationTag::new(id, self.new_tags, self.exist_tags)
+        if (self.new_tags.is_some() || self.exist_tags.is_some())
+            && !RelationTag::new(id, self.new_tags, self.exist_tags)
                 .insert_all()
                 .await
-        } else {
-            true
+        {
+            return None;
         }
+        Some(id)
     }
 }
 
+/// `Some` for any value given, `null` included, a missing field stays `None` through `default`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct EditArticle {
     id: Uuid,
@@ -79,36 +131,78 @@ pub struct EditArticle {
     new_choice_already_exists_tags: Option<Vec<Uuid>>,
     deselect_tags: Option<Vec<Uuid>>,
     new_tags: Option<Vec<String>>,
+    /// The schedule is kept when missing and cleared by `null`
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    publish_at: Option<Option<NaiveDateTime>>,
+    /// Regenerated from the title when not given and the title changed
+    slug: Option<String>,
 }
 
 impl EditArticle {
-    pub async fn edit_article(self) -> Result<u64, String> {
-        let res = sqlx::query(
-            r#"UPDATE articles SET title = $1, content = $2, raw_content = $3 WHERE id = $4"#,
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Update the article, every edit is kept as a revision
+    pub async fn edit_article(self, author_id: Uuid) -> Result<u64, String> {
+        #[derive(sqlx::FromRow)]
+        struct Old {
+            title: String,
+            slug: Option<String>,
+        }
+        let old = sqlx::query_as::<_, Old>(r#"SELECT title, slug FROM articles WHERE id = $1"#)
+            .bind(self.id)
+            .fetch_one(get_postgres())
+            .await
+            .map_err(|e| format!("{}", e))?;
+        let slug = match (&self.slug, old.slug.as_deref()) {
+            (None, Some(slug)) if old.title == self.title => slug.to_string(),
+            (manual, _) => {
+                ArticleSlug::choose(manual.as_deref(), &self.title, Some(self.id)).await?
+            }
+        };
+
+        let mut tx = get_postgres().begin().await.map_err(|e| format!("{}", e))?;
+        let r = sqlx::query(
+            r#"WITH updated AS (
+                UPDATE articles SET title = $1, content = $2, raw_content = $3,
+                publish_at = CASE WHEN $7 THEN $4 ELSE publish_at END
+                WHERE id = $5
+                RETURNING id, title, raw_content
+            )
+            INSERT INTO article_revisions (article_id, author_id, title, raw_content)
+            SELECT id, $6, title, raw_content FROM updated"#,
         )
         .bind(&self.title)
         .bind(markdown_render(&self.raw_content))
         .bind(&self.raw_content)
+        .bind(self.publish_at.flatten())
         .bind(self.id)
-        .execute(get_postgres())
+        .bind(author_id)
+        .bind(self.publish_at.is_some())
+        .execute(&mut *tx)
         .await
-        .map(|r| r.rows_affected());
-        match res {
-            Ok(r) => {
-                if self.new_tags.is_some() || self.new_choice_already_exists_tags.is_some() {
-                    RelationTag::new(self.id, self.new_tags, self.new_choice_already_exists_tags)
-                        .insert_all()
-                        .await;
-                }
-                if let Some(deselect_tags) = self.deselect_tags {
-                    for i in deselect_tags {
-                        Relations::new(self.id, i).delete_relation().await;
-                    }
-                }
-                Ok(r)
+        .map(|r| r.rows_affected())
+        .map_err(|e| format!("{}", e))?;
+        // Dropping the transaction on error rolls back the content and the revision
+        ArticleSlug::rename(&mut tx, self.id, old.slug.as_deref(), &slug).await?;
+        tx.commit().await.map_err(|e| format!("{}", e))?;
+
+        if self.new_tags.is_some() || self.new_choice_already_exists_tags.is_some() {
+            RelationTag::new(self.id, self.new_tags, self.new_choice_already_exists_tags)
+                .insert_all()
+                .await;
+        }
+        if let Some(deselect_tags) = self.deselect_tags {
+            for i in deselect_tags {
+                Relations::new(self.id, i).delete_relation().await;
             }
-            Err(e) => Err(format!("{}", e)),
         }
+        Ok(r)
     }
 }
 
@@ -119,6 +213,11 @@ pub struct ArticleList {
     pub published: bool,
     pub create_time: NaiveDateTime,
     pub modify_time: NaiveDateTime,
+    /// Only queried on admin lists, an unpublished article with it is scheduled
+    #[sqlx(default)]
+    pub publish_at: Option<NaiveDateTime>,
+    #[sqlx(default)]
+    pub slug: Option<String>,
 }
 
 impl ArticleList {
@@ -132,7 +231,7 @@ impl ArticleList {
         let limit = limit.min(50);
         let res = if admin {
             sqlx::query_as::<_, ArticleList>(
-                r#"SELECT id, title, published, create_time, modify_time
+                r#"SELECT id, title, published, create_time, modify_time, publish_at, slug
                     FROM articles
                     ORDER BY create_time DESC
                     LIMIT $1 OFFSET $2 "#,
@@ -143,7 +242,7 @@ impl ArticleList {
             .await
         } else {
             sqlx::query_as::<_, ArticleList>(
-                r#"SELECT id, title, published, create_time, modify_time
+                r#"SELECT id, title, published, create_time, modify_time, slug
                     FROM articles
                     WHERE published = true
                     ORDER BY create_time DESC
@@ -166,7 +265,7 @@ impl ArticleList {
     pub async fn view_unpublished(limit: i64, offset: i64) -> Result<Vec<ArticleList>, String> {
         let limit = limit.min(50);
         let res = sqlx::query_as::<_, ArticleList>(
-            r#"SELECT id, title, published, create_time, modify_time
+            r#"SELECT id, title, published, create_time, modify_time, publish_at, slug
                     FROM articles
                     WHERE published = false
                     ORDER BY create_time DESC
@@ -186,7 +285,7 @@ impl ArticleList {
     pub async fn query_with_tag(tag_id: Uuid) -> Result<Vec<ArticleList>, String> {
         let sql = format!(
             r#"
-        SELECT id, title, published, create_time, modify_time FROM article_with_tag
+        SELECT id, title, published, create_time, modify_time, slug FROM article_with_tag
         WHERE ('{}' = any(tags_id)) AND published = true
         ORDER BY create_time DESC"#,
             tag_id
@@ -198,6 +297,29 @@ impl ArticleList {
         }
     }
 
+    /// Published articles for the sitemap, oldest first so that pages stay stable
+    pub async fn query_sitemap(limit: i64, offset: i64) -> Result<Vec<ArticleList>, String> {
+        sqlx::query_as::<_, ArticleList>(
+            r#"SELECT id, title, published, create_time, modify_time, slug
+                FROM articles
+                WHERE published = true
+                ORDER BY create_time ASC, id ASC
+                LIMIT $1 OFFSET $2"#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    pub async fn published_count() -> Result<i64, String> {
+        sqlx::query_scalar::<_, i64>(r#"SELECT count(*) FROM articles WHERE published = true"#)
+            .fetch_one(get_postgres())
+            .await
+            .map_err(|e| format!("{}", e))
+    }
+
     pub async fn size_count() -> usize {
         #[derive(sqlx::FromRow, Default)]
         struct TP {
@@ -212,6 +334,77 @@ impl ArticleList {
     }
 }
 
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct ArticleSearch {
+    pub id: Uuid,
+    pub title: String,
+    pub published: bool,
+    pub slug: Option<String>,
+    pub rank: f32,
+    /// Title with the matched words wrapped in `<mark>`
+    pub title_highlight: String,
+    /// Fragments of the markdown source around the matched words
+    pub snippet: String,
+    pub create_time: NaiveDateTime,
+    pub modify_time: NaiveDateTime,
+}
+
+impl ArticleSearch {
+    /// Full-text search on title and raw content, ordered by rank
+    /// Max limit is 50 to prevent loading too much data
+    pub async fn search(
+        keyword: &str,
+        limit: i64,
+        offset: i64,
+        admin: bool,
+    ) -> Result<Vec<ArticleSearch>, String> {
+        let keyword = keyword.trim();
+        if keyword.is_empty() {
+            return Err("Search keyword is empty".to_string());
+        }
+        let limit = limit.min(50);
+        let title_options = format!(
+            "StartSel={}, StopSel={}, HighlightAll=true",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        let snippet_options = format!(
+            "StartSel={}, StopSel={}, MaxFragments=3, MaxWords=30, MinWords=10",
+            HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        let res = sqlx::query_as::<_, ArticleSearch>(
+            r#"SELECT id, title, published, slug,
+                    ts_rank_cd(search_vector, query) AS rank,
+                    ts_headline('simple', title, query, $5) AS title_highlight,
+                    ts_headline('simple', raw_content, query, $6) AS snippet,
+                    create_time, modify_time
+                FROM articles, websearch_to_tsquery('simple', $1) query
+                WHERE search_vector @@ query AND (published = true OR $2)
+                ORDER BY rank DESC, create_time DESC
+                LIMIT $3 OFFSET $4"#,
+        )
+        .bind(keyword)
+        .bind(admin)
+        .bind(limit)
+        .bind(offset)
+        .bind(title_options)
+        .bind(snippet_options)
+        .fetch_all(get_postgres())
+        .await;
+
+        match res {
+            Ok(data) => Ok(data
+                .into_iter()
+                .map(|mut item| {
+                    item.title_highlight = highlight_to_html(&item.title_highlight);
+                    item.snippet = highlight_to_html(&item.snippet);
+                    item
+                })
+                .collect()),
+            Err(err) => Err(format!("{}", err)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ArticlesWithTag {
     pub id: Uuid,
@@ -222,6 +415,8 @@ pub struct ArticlesWithTag {
     pub tags: Option<Vec<String>>,
     pub create_time: NaiveDateTime,
     pub modify_time: NaiveDateTime,
+    pub publish_at: Option<NaiveDateTime>,
+    pub slug: Option<String>,
 }
 
 #[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
@@ -276,7 +471,8 @@ impl ArticlesWithTag {
     }
 
     pub async fn publish_article(data: ModifyPublish) -> Result<u64, String> {
-        sqlx::query(r#"UPDATE articles SET published = $1 WHERE id = $2"#)
+        // A manual toggle overrides any schedule
+        sqlx::query(r#"UPDATE articles SET published = $1, publish_at = NULL WHERE id = $2"#)
             .bind(data.publish)
             .bind(data.id)
             .execute(get_postgres())
@@ -285,6 +481,40 @@ impl ArticlesWithTag {
             .map_err(|e| format!("{}", e))
     }
 
+    /// Publish every unpublished article whose `publish_at` is due
+    pub async fn publish_scheduled() -> Result<Vec<Uuid>, String> {
+        use sqlx::Row;
+        sqlx::query(
+            r#"UPDATE articles SET published = true, publish_at = NULL
+            WHERE published = false AND publish_at <= current_timestamp
+            RETURNING id"#,
+        )
+        .map(|row: sqlx::postgres::PgRow| row.get::<Uuid, _>(0))
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    /// Latest published articles with rendered content, used by the feeds,
+    /// only the articles carrying `tag_id` if given
+    pub async fn query_feed(
+        limit: i64,
+        tag_id: Option<Uuid>,
+    ) -> Result<Vec<ArticlesWithTag>, String> {
+        sqlx::query_as::<_, RawArticlesWithTag>(
+            r#"select * from article_with_tag
+            where published = true AND ($2::uuid IS NULL OR $2 = any(tags_id))
+            ORDER BY create_time DESC
+            LIMIT $1"#,
+        )
+        .bind(limit)
+        .bind(tag_id)
+        .fetch_all(get_postgres())
+        .await
+        .map(|articles| articles.into_iter().map(|a| a.into_html()).collect())
+        .map_err(|e| format!("{}", e))
+    }
+
     pub async fn query_navigation(id: Uuid, admin: bool) -> Result<ArticleNavigation, String> {
         let navigation = ArticleNavigationRow::query(id).await.map_err(|e| match e {
             sqlx::Error::RowNotFound => "Article not found".to_string(),
@@ -309,6 +539,8 @@ struct RawArticlesWithTag {
     pub tags: Vec<Option<String>>,
     pub create_time: NaiveDateTime,
     pub modify_time: NaiveDateTime,
+    pub publish_at: Option<NaiveDateTime>,
+    pub slug: Option<String>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -409,6 +641,8 @@ impl RawArticlesWithTag {
             },
             create_time: self.create_time,
             modify_time: self.modify_time,
+            publish_at: self.publish_at,
+            slug: self.slug,
         }
     }
 
@@ -436,6 +670,8 @@ impl RawArticlesWithTag {
             },
             create_time: self.create_time,
             modify_time: self.modify_time,
+            publish_at: self.publish_at,
+            slug: self.slug,
         }
     }
 
@@ -472,6 +708,12 @@ pub struct ModifyPublish {
     publish: bool,
 }
 
+impl ModifyPublish {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
 async fn delete_article(id: Uuid) -> sqlx::Result<u64> {
     sqlx::query(r#"DELETE FROM articles WHERE id = $1"#)
         .bind(id)
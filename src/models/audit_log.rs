@@ -0,0 +1,113 @@
+use crate::db_wrapper::get_postgres;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::{
+    Uuid,
+    chrono::{NaiveDate, NaiveDateTime},
+};
+
+/// Account shown for the actions of the server itself
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// An entry of `audit_log`, rows are never updated nor deleted
+#[derive(Debug, Clone)]
+pub struct NewAuditLog {
+    pub actor_id: Option<Uuid>,
+    pub actor: String,
+    pub action: &'static str,
+    pub target: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+}
+
+impl NewAuditLog {
+    /// State of the target before the action
+    pub fn before<T: Serialize>(mut self, value: T) -> Self {
+        self.before = serde_json::to_value(value).ok();
+        self
+    }
+
+    /// State of the target after the action
+    pub fn after<T: Serialize>(mut self, value: T) -> Self {
+        self.after = serde_json::to_value(value).ok();
+        self
+    }
+
+    /// The action goes on when the entry can't be written, the error is only logged
+    pub async fn insert(self) {
+        if let Err(e) = sqlx::query(
+            r#"INSERT INTO audit_log (actor_id, actor, action, target, before, after, ip)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(self.actor_id)
+        .bind(&self.actor)
+        .bind(self.action)
+        .bind(&self.target)
+        .bind(&self.before)
+        .bind(&self.after)
+        .bind(&self.ip)
+        .execute(get_postgres())
+        .await
+        {
+            tracing::error!(
+                "can't write audit log {} {}: {}",
+                self.action,
+                self.target,
+                e
+            );
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct AuditLog {
+    pub id: i64,
+    pub actor_id: Option<Uuid>,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub create_time: NaiveDateTime,
+}
+
+/// Every field is optional, `action` also matches the actions under it, e.g. `article`
+/// matches `article.delete`, `to` is inclusive
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl AuditLog {
+    /// Latest entries first
+    pub async fn query(filter: &AuditFilter, limit: i64, offset: i64) -> Result<Vec<Self>, String> {
+        sqlx::query_as::<_, AuditLog>(
+            r#"SELECT id, actor_id, actor, action, target, before, after, ip, create_time
+            FROM audit_log
+            WHERE ($1::varchar IS NULL OR actor = $1)
+            AND ($2::varchar IS NULL OR action = $2 OR action LIKE $2 || '.%')
+            AND ($3::varchar IS NULL OR target = $3)
+            AND ($4::date IS NULL OR create_time >= $4)
+            AND ($5::date IS NULL OR create_time < $5 + 1)
+            ORDER BY id DESC
+            LIMIT $6
+            OFFSET $7"#,
+        )
+        .bind(&filter.actor)
+        .bind(&filter.action)
+        .bind(&filter.target)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+}
@@ -1,51 +1,238 @@
-use crate::db_wrapper::get_postgres;
+use crate::{
+    config::{ModerationMode, get_config},
+    db_wrapper::get_postgres,
+    models::role::Role,
+    utils::{comment_markdown_render, gravatar_hash, sanitize_comment_html},
+};
 use serde::{Deserialize, Serialize};
 use sqlx::types::{Uuid, chrono::NaiveDateTime};
+use std::collections::HashMap;
 
+/// Depth of the deepest replies, a reply to one of them goes next to it
+pub const MAX_DEPTH: i16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "comment_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CommentStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A comment with its replies, the author and the text of a deleted one are hidden
 #[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
 pub struct Comments {
     id: Uuid,
+    parent_id: Option<Uuid>,
+    depth: i16,
+    deleted: bool,
+    status: CommentStatus,
+    /// Markdown
     comment: String,
+    comment_html: Option<String>,
     article_id: Uuid,
-    user_id: Uuid,
-    nickname: String,
+    /// None for the guests
+    user_id: Option<Uuid>,
+    nickname: Option<String>,
+    /// Of the guests, for their Gravatar
+    email_hash: Option<String>,
     create_time: NaiveDateTime,
+    /// Time of the last edit
+    #[sqlx(rename = "edit_time")]
+    edited: Option<NaiveDateTime>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    replies: Vec<Comments>,
 }
 
 impl Comments {
-    /// Query comment list
+    /// Query the threads of an article, the pages count the top level comments only.
+    /// Pending comments are only shown to their author and the moderators
     /// Max limit is 50 to prevent loading too much data
-    pub async fn query(limit: i64, offset: i64, id: Uuid) -> Result<Vec<Self>, String> {
+    pub async fn query(
+        limit: i64,
+        offset: i64,
+        id: Uuid,
+        viewer: Option<Uuid>,
+        moderator: bool,
+    ) -> Result<Vec<Self>, String> {
         let limit = limit.min(50);
-        let sql = format!(
-            "SELECT a.id, a.comment, a.article_id, a.user_id, b.nickname, a.create_time FROM comments a JOIN users b ON a.user_id=b.id WHERE a.article_id='{}' ORDER BY a.create_time LIMIT $1 OFFSET $2",
-            id
-        );
-        sqlx::query_as(&sql)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(get_postgres())
-            .await
-            .map_err(|e| format!("{}", e))
+        sqlx::query_as::<_, Comments>(
+            r#"WITH RECURSIVE roots AS (
+                SELECT id FROM comments
+                WHERE article_id = $1 AND parent_id IS NULL
+                AND (status = 'approved' OR (status = 'pending' AND ($4 OR user_id = $5)))
+                ORDER BY create_time
+                LIMIT $2 OFFSET $3
+            ), thread AS (
+                SELECT id FROM roots
+                UNION ALL
+                SELECT c.id FROM comments c JOIN thread t ON c.parent_id = t.id
+                WHERE c.status = 'approved' OR (c.status = 'pending' AND ($4 OR c.user_id = $5))
+            )
+            SELECT a.id, a.parent_id, a.depth, a.deleted, a.status,
+                CASE WHEN a.deleted THEN '' ELSE a.comment END AS comment,
+                CASE WHEN a.deleted THEN '' ELSE a.comment_html END AS comment_html,
+                a.article_id,
+                CASE WHEN a.deleted THEN NULL ELSE a.user_id END AS user_id,
+                CASE WHEN a.deleted THEN NULL ELSE coalesce(b.nickname, a.guest_name) END AS nickname,
+                CASE WHEN a.deleted THEN NULL ELSE a.guest_email_hash END AS email_hash,
+                a.create_time, a.edit_time
+            FROM thread t JOIN comments a ON a.id = t.id LEFT JOIN users b ON a.user_id = b.id
+            ORDER BY a.create_time, a.id"#,
+        )
+        .bind(id)
+        .bind(limit)
+        .bind(offset)
+        .bind(moderator)
+        .bind(viewer)
+        .fetch_all(get_postgres())
+        .await
+        .map(|mut rows| {
+            // Written as html before the markdown
+            for comment in rows.iter_mut().filter(|c| c.comment_html.is_none()) {
+                comment.comment_html = Some(sanitize_comment_html(&comment.comment));
+            }
+            into_tree(rows)
+        })
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+/// Nest the rows under their parents, each level keeps the order of the rows
+fn into_tree(mut rows: Vec<Comments>) -> Vec<Comments> {
+    // The deepest first, so the replies of a comment are all collected before it
+    rows.sort_by_key(|c| std::cmp::Reverse(c.depth));
+    let mut replies: HashMap<Uuid, Vec<Comments>> = HashMap::new();
+    let mut roots = Vec::new();
+    for mut comment in rows {
+        comment.replies = replies.remove(&comment.id).unwrap_or_default();
+        match comment.parent_id {
+            Some(parent_id) => replies.entry(parent_id).or_default().push(comment),
+            None => roots.push(comment),
+        }
+    }
+    roots
+}
+
+/// Whether a comment written by `user_id` waits for a moderator, see `CommentConfig`
+async fn moderation_status(comment: &str, user_id: Uuid, role: Role) -> CommentStatus {
+    if role.can("comment:moderate") {
+        return CommentStatus::Approved;
+    }
+    let config = &get_config().comment;
+    let held = match config.moderation {
+        ModerationMode::None => false,
+        ModerationMode::All => true,
+        ModerationMode::First => !sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS (SELECT 1 FROM comments WHERE user_id = $1 AND status = 'approved')"#,
+        )
+        .bind(user_id)
+        .fetch_one(get_postgres())
+        .await
+        .unwrap_or(false),
+        ModerationMode::Rules => {
+            let comment = comment.to_lowercase();
+            config
+                .moderation_words
+                .iter()
+                .any(|word| comment.contains(&word.to_lowercase()))
+        }
+    };
+    if held {
+        CommentStatus::Pending
+    } else {
+        CommentStatus::Approved
     }
 }
 
+/// Where a reply goes
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct CommentParent {
+    id: Uuid,
+    parent_id: Option<Uuid>,
+    depth: i16,
+    /// Author of the comment replied to, None for a guest
+    pub user_id: Option<Uuid>,
+}
+
+/// Who writes a new comment
+#[derive(Debug, Clone)]
+pub enum CommentAuthor<'a> {
+    User(Uuid),
+    /// A visitor without an account
+    Guest {
+        name: &'a str,
+        email_hash: String,
+    },
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NewComments {
     comment: String,
     article_id: Uuid,
+    /// The comment replied to
+    parent_id: Option<Uuid>,
     reply_user_id: Option<Uuid>,
 }
 
 impl NewComments {
-    pub async fn insert(&self, user_id: Uuid) -> bool {
-        sqlx::query(r#"INSERT INTO comments (comment, article_id, user_id) VALUES ($1, $2, $3)"#)
-            .bind(&self.comment)
-            .bind(self.article_id)
-            .bind(user_id)
-            .execute(get_postgres())
-            .await
-            .is_ok()
+    /// The comment replied to, it must be on the same article, published and not deleted
+    pub async fn parent(&self) -> Result<Option<CommentParent>, String> {
+        let Some(parent_id) = self.parent_id else {
+            return Ok(None);
+        };
+        sqlx::query_as::<_, CommentParent>(
+            r#"SELECT id, parent_id, depth, user_id FROM comments
+            WHERE id = $1 AND article_id = $2 AND deleted = false AND status = 'approved'"#,
+        )
+        .bind(parent_id)
+        .bind(self.article_id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?
+        .map(Some)
+        .ok_or_else(|| "Comment not found".to_string())
+    }
+
+    /// Whether the comment waits for a moderator, see `CommentConfig`
+    pub async fn status(&self, user_id: Uuid, role: Role) -> CommentStatus {
+        moderation_status(&self.comment, user_id, role).await
+    }
+
+    pub async fn insert(
+        &self,
+        author: &CommentAuthor<'_>,
+        parent: Option<&CommentParent>,
+        status: CommentStatus,
+    ) -> bool {
+        let (parent_id, depth) = match parent {
+            Some(p) if p.depth >= MAX_DEPTH => (p.parent_id, p.depth),
+            Some(p) => (Some(p.id), p.depth + 1),
+            None => (None, 0),
+        };
+        let (user_id, guest_name, guest_email_hash) = match author {
+            CommentAuthor::User(id) => (Some(*id), None, None),
+            CommentAuthor::Guest { name, email_hash } => (None, Some(*name), Some(email_hash)),
+        };
+        sqlx::query(
+            r#"INSERT INTO comments (comment, comment_html, article_id, user_id, guest_name,
+                guest_email_hash, parent_id, depth, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+        )
+        .bind(&self.comment)
+        .bind(comment_markdown_render(&self.comment))
+        .bind(self.article_id)
+        .bind(user_id)
+        .bind(guest_name)
+        .bind(guest_email_hash)
+        .bind(parent_id)
+        .bind(depth)
+        .bind(status)
+        .execute(get_postgres())
+        .await
+        .is_ok()
     }
 
     pub fn reply_user_id(&mut self) -> Option<Uuid> {
@@ -55,35 +242,348 @@ impl NewComments {
     pub fn article_id(&self) -> Uuid {
         self.article_id
     }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+}
+
+/// Longest name of a guest, in characters
+const MAX_GUEST_NAME: usize = 32;
+
+/// A comment of a visitor without an account, see `CommentChallenge`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NewGuestComment {
+    #[serde(flatten)]
+    pub comment: NewComments,
+    name: String,
+    email: String,
+    challenge_id: Uuid,
+    nonce: u64,
+}
+
+impl NewGuestComment {
+    /// Check the name and the email, only the hash of the email is kept
+    pub fn author(&self) -> Result<CommentAuthor<'_>, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_GUEST_NAME {
+            return Err("Invalid name".to_string());
+        }
+        let email = self.email.trim();
+        let valid_email = email.len() <= 254
+            && !email.contains(char::is_whitespace)
+            && email
+                .split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+        if !valid_email {
+            return Err("Invalid email".to_string());
+        }
+        Ok(CommentAuthor::Guest {
+            name,
+            email_hash: gravatar_hash(email),
+        })
+    }
+
+    pub fn challenge(&self) -> (Uuid, u64) {
+        (self.challenge_id, self.nonce)
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.trim()
+    }
+}
+
+/// A comment in the moderation queue
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct ModerationComment {
+    pub id: Uuid,
+    pub comment: String,
+    pub status: CommentStatus,
+    pub article_id: Uuid,
+    pub article_title: String,
+    pub parent_id: Option<Uuid>,
+    /// None for the guests
+    pub user_id: Option<Uuid>,
+    pub nickname: String,
+    pub create_time: NaiveDateTime,
+    pub moderate_time: Option<NaiveDateTime>,
+}
+
+/// A comment that got a decision, with what the notifications need
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ModeratedComment {
+    pub id: Uuid,
+    pub comment: String,
+    /// None for the guests, who are not notified
+    pub user_id: Option<Uuid>,
+    pub article_id: Uuid,
+    pub article_title: String,
+    /// Of the author
+    pub nickname: String,
+    /// Author of the comment replied to
+    pub reply_user_id: Option<Uuid>,
+}
+
+/// Bulk decision on pending comments
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModerateComments {
+    pub ids: Vec<Uuid>,
+}
+
+impl ModerationComment {
+    /// Oldest first, so the queue is handled in order
+    /// Max limit is 50 to prevent loading too much data
+    pub async fn list(status: CommentStatus, limit: i64, offset: i64) -> Result<Vec<Self>, String> {
+        let limit = limit.min(50);
+        sqlx::query_as::<_, ModerationComment>(
+            r#"SELECT a.id, a.comment, a.status, a.article_id, c.title AS article_title,
+                a.parent_id, a.user_id, coalesce(b.nickname, a.guest_name) AS nickname,
+                a.create_time, a.moderate_time
+            FROM comments a LEFT JOIN users b ON a.user_id = b.id
+            JOIN articles c ON a.article_id = c.id
+            WHERE a.status = $1 AND a.deleted = false
+            ORDER BY a.create_time
+            LIMIT $2 OFFSET $3"#,
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+impl ModerateComments {
+    /// Only the pending comments change, the others are left out of the result
+    pub async fn decide(
+        &self,
+        moderator_id: Uuid,
+        status: CommentStatus,
+    ) -> Result<Vec<ModeratedComment>, String> {
+        sqlx::query_as::<_, ModeratedComment>(
+            r#"WITH decided AS (
+                UPDATE comments
+                SET status = $2, moderator_id = $3, moderate_time = current_timestamp
+                WHERE id = ANY($1) AND status = 'pending'
+                RETURNING id, comment, user_id, guest_name, article_id, parent_id
+            )
+            SELECT d.id, d.comment, d.user_id, d.article_id, b.title AS article_title,
+                coalesce(u.nickname, d.guest_name) AS nickname, p.user_id AS reply_user_id
+            FROM decided d JOIN articles b ON d.article_id = b.id
+            LEFT JOIN users u ON d.user_id = u.id
+            LEFT JOIN comments p ON d.parent_id = p.id"#,
+        )
+        .bind(&self.ids)
+        .bind(status)
+        .bind(moderator_id)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DeleteComment {
     comment_id: Uuid,
-    user_id: Uuid,
+    /// None for the comments of the guests
+    user_id: Option<Uuid>,
 }
 
 impl DeleteComment {
-    pub async fn delete(self, id: Uuid, permission: Option<i16>) -> bool {
-        match permission {
-            Some(0) => delete_with_comment_id(self.comment_id).await,
-            _ => {
-                if self.user_id == id {
-                    delete_with_comment_id(self.comment_id).await
-                } else {
-                    false
-                }
+    /// Authors delete their own comments, moderators any of them
+    pub async fn delete(self, id: Uuid, role: Role) -> bool {
+        if role.can("comment:moderate") {
+            delete_with_comment_id(self.comment_id, None).await
+        } else if self.user_id == Some(id) {
+            delete_with_comment_id(self.comment_id, Some(id)).await
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EditComment {
+    comment_id: Uuid,
+    comment: String,
+}
+
+/// The comment as it is before the edit
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct EditTarget {
+    pub article_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub status: CommentStatus,
+    pub comment: String,
+    in_window: bool,
+}
+
+impl EditComment {
+    /// Authors edit their comments within `edit_window` minutes, admins any of them.
+    /// Deleted and rejected comments can't be edited
+    pub async fn target(&self, editor: Uuid, admin: bool) -> Result<EditTarget, String> {
+        let target = sqlx::query_as::<_, EditTarget>(
+            r#"SELECT article_id, user_id, status, comment,
+                create_time > current_timestamp - $2 * interval '1 minute' AS in_window
+            FROM comments
+            WHERE id = $1 AND deleted = false AND status <> 'rejected'"#,
+        )
+        .bind(self.comment_id)
+        .bind(get_config().comment.edit_window)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?
+        .filter(|target| admin || target.user_id == Some(editor))
+        .ok_or_else(|| "Comment not found".to_string())?;
+
+        if !admin && !target.in_window {
+            Err("Edit window has passed".to_string())
+        } else if target.comment == self.comment {
+            Err("Comment is unchanged".to_string())
+        } else {
+            Ok(target)
+        }
+    }
+
+    /// Keep the current text as a revision, then replace it
+    pub async fn update(&self, editor: Uuid, status: CommentStatus) -> bool {
+        let res: Result<bool, sqlx::Error> = async {
+            let mut tx = get_postgres().begin().await?;
+            let kept = sqlx::query(
+                r#"INSERT INTO comment_revisions (comment_id, editor_id, comment, comment_html)
+                SELECT id, $2, comment, comment_html FROM comments
+                WHERE id = $1 AND deleted = false"#,
+            )
+            .bind(self.comment_id)
+            .bind(editor)
+            .execute(&mut *tx)
+            .await?;
+            if kept.rows_affected() == 0 {
+                return Ok(false);
             }
+            sqlx::query(
+                r#"UPDATE comments
+                SET comment = $2, comment_html = $3, status = $4, edit_time = current_timestamp
+                WHERE id = $1"#,
+            )
+            .bind(self.comment_id)
+            .bind(&self.comment)
+            .bind(comment_markdown_render(&self.comment))
+            .bind(status)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(true)
         }
+        .await;
+        res.unwrap_or_else(|e| {
+            tracing::error!("can't edit comment {}: {}", self.comment_id, e);
+            false
+        })
+    }
+
+    /// Held when it was still pending or when the new text would be
+    pub async fn status(&self, target: &EditTarget, user_id: Uuid, role: Role) -> CommentStatus {
+        match target.status {
+            CommentStatus::Pending => CommentStatus::Pending,
+            _ => moderation_status(&self.comment, user_id, role).await,
+        }
+    }
+
+    pub fn comment_id(&self) -> Uuid {
+        self.comment_id
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
     }
 }
 
-async fn delete_with_comment_id(comment_id: Uuid) -> bool {
-    sqlx::query(r#"DELETE FROM comments where id = $1"#)
+/// The text a comment had before an edit
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct CommentRevision {
+    pub id: Uuid,
+    pub comment_id: Uuid,
+    pub editor_id: Option<Uuid>,
+    pub editor: Option<String>,
+    pub comment: String,
+    /// When it was replaced
+    pub create_time: NaiveDateTime,
+}
+
+impl CommentRevision {
+    /// Newest first
+    pub async fn list(comment_id: Uuid) -> Result<Vec<Self>, String> {
+        sqlx::query_as::<_, CommentRevision>(
+            r#"SELECT a.id, a.comment_id, a.editor_id, b.nickname AS editor, a.comment, a.create_time
+            FROM comment_revisions a LEFT JOIN users b ON a.editor_id = b.id
+            WHERE a.comment_id = $1
+            ORDER BY a.create_time DESC"#,
+        )
         .bind(comment_id)
-        .execute(get_postgres())
+        .fetch_all(get_postgres())
         .await
-        .is_ok()
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+/// A comment with replies becomes a placeholder, otherwise it is removed along with
+/// the placeholders above it that are left without replies
+async fn delete_with_comment_id(comment_id: Uuid, author: Option<Uuid>) -> bool {
+    let res: Result<bool, sqlx::Error> = async {
+        let mut tx = get_postgres().begin().await?;
+        let target = sqlx::query_as::<_, (Option<Uuid>, bool)>(
+            r#"SELECT parent_id, EXISTS (SELECT 1 FROM comments c WHERE c.parent_id = a.id)
+            FROM comments a
+            WHERE id = $1 AND deleted = false AND ($2::uuid IS NULL OR user_id = $2)
+            FOR UPDATE"#,
+        )
+        .bind(comment_id)
+        .bind(author)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((mut parent_id, has_replies)) = target else {
+            return Ok(false);
+        };
+        if has_replies {
+            sqlx::query(
+                r#"UPDATE comments SET deleted = true, comment = '', comment_html = '' WHERE id = $1"#,
+            )
+            .bind(comment_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(r#"DELETE FROM comment_revisions WHERE comment_id = $1"#)
+                .bind(comment_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query(r#"DELETE FROM comments WHERE id = $1"#)
+                .bind(comment_id)
+                .execute(&mut *tx)
+                .await?;
+            while let Some(id) = parent_id {
+                parent_id = sqlx::query_scalar::<_, Option<Uuid>>(
+                    r#"DELETE FROM comments a
+                    WHERE id = $1 AND deleted = true
+                    AND NOT EXISTS (SELECT 1 FROM comments c WHERE c.parent_id = a.id)
+                    RETURNING parent_id"#,
+                )
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .flatten();
+            }
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+    res.unwrap_or_else(|e| {
+        tracing::error!("can't delete comment {}: {}", comment_id, e);
+        false
+    })
 }
 
 async fn delete_with_user_id(user_id: Uuid) -> bool {
@@ -0,0 +1,59 @@
+//! Guests prove some work before commenting: find a nonce so that `sha256(id ‖ nonce)` starts
+//! with `difficulty` zero bits. The difficulty is kept in `comment_challenge_{id}` for a few
+//! minutes and each challenge can be answered once. An ip gets a limited number of challenges.
+
+use crate::{config::get_config, db_wrapper::get_redis, utils::proof_of_work_bits};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+const CHALLENGE_TTL: i64 = 10 * 60;
+/// Challenges an ip may get in `CHALLENGE_TTL`
+const CHALLENGE_IP_LIMIT: i64 = 30;
+
+fn redis_key(id: Uuid) -> String {
+    format!("comment_challenge_{}", id)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommentChallenge {
+    pub id: Uuid,
+    /// Leading zero bits the hash must have
+    pub difficulty: u32,
+}
+
+impl CommentChallenge {
+    pub async fn issue(ip: Option<&str>) -> Result<Self, String> {
+        if let Some(ip) = ip {
+            let rate_key = format!("comment_challenge_rate_{}", ip);
+            let count = get_redis().hincrby(&rate_key, "count", 1).await;
+            if count == 1 {
+                get_redis().expire(&rate_key, CHALLENGE_TTL).await;
+            }
+            if count > CHALLENGE_IP_LIMIT {
+                return Err("Too many challenges, try again later".to_string());
+            }
+        }
+
+        let id = Uuid::new_v4();
+        let difficulty = get_config().comment.guest_difficulty;
+        let key = redis_key(id);
+        get_redis().set(&key, &difficulty.to_string()).await;
+        get_redis().expire(&key, CHALLENGE_TTL).await;
+        Ok(CommentChallenge { id, difficulty })
+    }
+
+    /// The challenge is used up whatever the nonce
+    pub async fn verify(id: Uuid, nonce: u64) -> bool {
+        let key = redis_key(id);
+        match get_redis().get(&key).await {
+            // Only the request that removes it may use it
+            Ok(Some(difficulty)) => {
+                get_redis().del(&key).await
+                    && difficulty
+                        .parse::<u32>()
+                        .is_ok_and(|d| proof_of_work_bits(&id.to_string(), nonce) >= d)
+            }
+            _ => false,
+        }
+    }
+}
@@ -218,6 +218,31 @@ impl FundEntry {
         .map_err(|e| format!("Failed to fetch entries: {}", e))
     }
 
+    /// Get a single entry by ID
+    pub async fn get_by_id(id: i32) -> Result<FundEntry, String> {
+        sqlx::query(
+            "SELECT id, portfolio_id, major_category, minor_category, fund_type,
+                fund_name, target_ratio, amount, sort_index
+             FROM fund_entries
+             WHERE id = $1",
+        )
+        .bind(id)
+        .map(|row: sqlx::postgres::PgRow| FundEntry {
+            id: row.get("id"),
+            portfolio_id: row.get("portfolio_id"),
+            major_category: row.get("major_category"),
+            minor_category: row.get("minor_category"),
+            fund_type: row.get("fund_type"),
+            fund_name: row.get("fund_name"),
+            target_ratio: decimal_to_f64(&row, "target_ratio"),
+            amount: decimal_to_f64(&row, "amount"),
+            sort_index: row.get("sort_index"),
+        })
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("Entry not found: {}", e))
+    }
+
     /// Create a new entry
     pub async fn create(data: NewFundEntry) -> Result<i32, String> {
         let id = sqlx::query(
@@ -0,0 +1,202 @@
+//! Failed logins are counted per account and per ip in `login_fail_{kind}_{key}` hashes
+//! with `count` and `locked_until`. After a few free attempts every failure locks the key
+//! for an exponentially growing delay, past the threshold the lock lasts `LOCKOUT`.
+
+use crate::{
+    db_wrapper::get_redis,
+    models::audit_log::{NewAuditLog, SYSTEM_ACTOR},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::Local;
+use std::fmt;
+
+const MAX_DELAY: i64 = 5 * 60;
+const LOCKOUT: i64 = 15 * 60;
+/// Counters are forgotten an hour after the last failure
+const FAILURE_WINDOW: i64 = 60 * 60;
+const ACCOUNT_FREE_ATTEMPTS: i64 = 3;
+const ACCOUNT_THRESHOLD: i64 = 10;
+/// Higher, many users may share an address
+const IP_FREE_ATTEMPTS: i64 = 20;
+const IP_THRESHOLD: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockKind {
+    Account,
+    Ip,
+}
+
+impl LockKind {
+    fn free_attempts(self) -> i64 {
+        match self {
+            LockKind::Account => ACCOUNT_FREE_ATTEMPTS,
+            LockKind::Ip => IP_FREE_ATTEMPTS,
+        }
+    }
+
+    fn threshold(self) -> i64 {
+        match self {
+            LockKind::Account => ACCOUNT_THRESHOLD,
+            LockKind::Ip => IP_THRESHOLD,
+        }
+    }
+}
+
+impl fmt::Display for LockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockKind::Account => write!(f, "account"),
+            LockKind::Ip => write!(f, "ip"),
+        }
+    }
+}
+
+/// A failure counter, as shown to admins
+#[derive(Debug, Clone, Serialize)]
+pub struct Lockout {
+    pub kind: LockKind,
+    pub key: String,
+    pub count: i64,
+    pub locked_until: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ClearLockout {
+    pub kind: LockKind,
+    pub key: String,
+}
+
+pub struct LoginThrottle {
+    account: String,
+    ip: Option<String>,
+}
+
+fn redis_key(kind: LockKind, key: &str) -> String {
+    format!("login_fail_{}_{}", kind, key)
+}
+
+/// Seconds to wait after the failure number `count`
+fn delay(kind: LockKind, count: i64) -> i64 {
+    if count >= kind.threshold() {
+        LOCKOUT
+    } else if count > kind.free_attempts() {
+        (1i64 << (count - kind.free_attempts()).min(16)).min(MAX_DELAY)
+    } else {
+        0
+    }
+}
+
+impl LoginThrottle {
+    pub fn new(account: &str, ip: Option<String>) -> Self {
+        LoginThrottle {
+            account: account.to_string(),
+            ip,
+        }
+    }
+
+    fn keys(&self) -> Vec<(LockKind, &str)> {
+        let mut keys = vec![(LockKind::Account, self.account.as_str())];
+        if let Some(ref ip) = self.ip {
+            keys.push((LockKind::Ip, ip.as_str()));
+        }
+        keys
+    }
+
+    /// Refuse while the account or the ip waits, the password isn't even checked
+    pub async fn check(&self) -> Result<(), String> {
+        let now = Local::now().timestamp();
+        let redis_pool = get_redis();
+        for (kind, key) in self.keys() {
+            let locked_until = redis_pool
+                .hget::<Option<i64>>(&redis_key(kind, key), "locked_until")
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            if locked_until > now {
+                return Err(format!(
+                    "Too many failed attempts, try again in {} seconds",
+                    locked_until - now
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn fail(&self) {
+        let now = Local::now().timestamp();
+        let redis_pool = get_redis();
+        for (kind, key) in self.keys() {
+            let redis_key = redis_key(kind, key);
+            let count = redis_pool.hincrby(&redis_key, "count", 1).await;
+            redis_pool.expire(&redis_key, FAILURE_WINDOW).await;
+
+            let wait = delay(kind, count);
+            if wait == 0 {
+                continue;
+            }
+            redis_pool
+                .hset(&redis_key, "locked_until", now + wait)
+                .await;
+            if count >= kind.threshold() {
+                tracing::warn!("login of {} {} locked after {} failures", kind, key, count);
+                NewAuditLog {
+                    actor_id: None,
+                    actor: SYSTEM_ACTOR.to_string(),
+                    action: "login.lockout",
+                    target: format!("{}:{}", kind, key),
+                    before: None,
+                    after: Some(serde_json::json!({
+                        "failures": count,
+                        "locked_until": now + wait,
+                    })),
+                    ip: self.ip.clone(),
+                }
+                .insert()
+                .await;
+            }
+        }
+    }
+
+    /// Only the account is cleared, a valid login must not reset the counter of an ip
+    pub async fn succeed(&self) {
+        get_redis()
+            .del(redis_key(LockKind::Account, &self.account))
+            .await;
+    }
+
+    pub async fn list() -> Vec<Lockout> {
+        let redis_pool = get_redis();
+        let mut lockouts = Vec::new();
+        for name in redis_pool.keys("login_fail_*").await {
+            let (kind, key) = if let Some(key) = name.strip_prefix("login_fail_account_") {
+                (LockKind::Account, key)
+            } else if let Some(key) = name.strip_prefix("login_fail_ip_") {
+                (LockKind::Ip, key)
+            } else {
+                continue;
+            };
+            let count = match redis_pool.hget::<Option<i64>>(&name, "count").await {
+                Ok(Some(count)) => count,
+                _ => continue,
+            };
+            lockouts.push(Lockout {
+                kind,
+                key: key.to_string(),
+                count,
+                locked_until: redis_pool
+                    .hget::<Option<i64>>(&name, "locked_until")
+                    .await
+                    .ok()
+                    .flatten(),
+            });
+        }
+        lockouts.sort_by_key(|l| std::cmp::Reverse(l.locked_until.unwrap_or_default()));
+        lockouts
+    }
+
+    pub async fn clear(data: &ClearLockout) -> bool {
+        get_redis().del(redis_key(data.kind, &data.key)).await
+    }
+}
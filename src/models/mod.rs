@@ -1,7 +1,18 @@
+pub mod api_token;
+pub mod article_revision;
+pub mod article_slug;
 pub mod article_tag_relation;
 pub mod articles;
+pub mod audit_log;
 pub mod comment;
+pub mod comment_challenge;
 pub mod fund;
+pub mod login_throttle;
 pub mod notify;
+pub mod role;
+pub mod session;
+pub mod spam;
 pub mod tag;
 pub mod user;
+pub mod user_oauth;
+pub mod user_totp;
@@ -1,4 +1,4 @@
-use crate::db_wrapper::get_redis;
+use crate::{db_wrapper::get_redis, models::user::UserInfo};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -33,6 +33,40 @@ impl UserNotify {
         redis_pool.ltrim(&notify_key, 0, 10).await;
     }
 
+    /// Tell the author of the comment replied to about a published comment,
+    /// and the admin unless one of them is the admin
+    pub async fn published_comment(
+        send_user_name: &str,
+        from_admin: bool,
+        article_id: Uuid,
+        article_title: &str,
+        reply_user_id: Option<Uuid>,
+    ) {
+        let admin = UserInfo::view_admin().await;
+        if let Some(reply_user_id) = reply_user_id {
+            UserNotify {
+                user_id: reply_user_id,
+                send_user_name: send_user_name.to_string(),
+                article_id,
+                article_title: article_title.to_string(),
+                notify_type: "reply".into(),
+            }
+            .cache()
+            .await;
+        }
+        if !from_admin && reply_user_id != Some(admin.id) {
+            UserNotify {
+                user_id: admin.id,
+                send_user_name: send_user_name.to_string(),
+                article_id,
+                article_title: article_title.to_string(),
+                notify_type: "comment".into(),
+            }
+            .cache()
+            .await;
+        }
+    }
+
     /// Get all the notifications about the user
     pub async fn get_notifys(user_id: Uuid) -> Option<Vec<UserNotify>> {
         let pattern = format!("notify:*:{}", user_id.hyphenated());
@@ -0,0 +1,77 @@
+//! Every role grants a fixed set of permissions, named `{resource}:{access}`.
+//! The routes check a permission rather than a role, see `api::RequirePermission`.
+
+use serde::{Deserialize, Serialize};
+
+pub const PERMISSIONS: &[&str] = &[
+    // Open the admin pages
+    "admin:access",
+    "article:read",
+    "article:write",
+    "tag:read",
+    "tag:write",
+    "fund:read",
+    "fund:write",
+    "user:read",
+    "user:write",
+    "stats:read",
+    // Browse the audit log
+    "audit:read",
+    // Delete the comments of others
+    "comment:moderate",
+];
+
+const EDITOR: &[&str] = &[
+    "admin:access",
+    "article:read",
+    "article:write",
+    "tag:read",
+    "tag:write",
+    "stats:read",
+];
+
+const MODERATOR: &[&str] = &["comment:moderate"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Editor,
+    Moderator,
+    #[default]
+    Reader,
+}
+
+impl Role {
+    pub fn permissions(self) -> &'static [&'static str] {
+        match self {
+            Role::Admin => PERMISSIONS,
+            Role::Editor => EDITOR,
+            Role::Moderator => MODERATOR,
+            Role::Reader => &[],
+        }
+    }
+
+    pub fn can(self, permission: &str) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleView {
+    pub role: Role,
+    pub permissions: &'static [&'static str],
+}
+
+impl RoleView {
+    pub fn all() -> Vec<RoleView> {
+        [Role::Admin, Role::Editor, Role::Moderator, Role::Reader]
+            .into_iter()
+            .map(|role| RoleView {
+                role,
+                permissions: role.permissions(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+//! A session is a redis hash keyed by the cookie:
+//!
+//! - `login_time`, `info`: set at login
+//! - `ip`, `user_agent`, `last_seen`: the latest request, refreshed at most once a minute
+//!
+//! `user_sessions_{user_id}` maps the ids of the sessions of a user to their cookies.
+//! The id is the sha3 of the cookie, so the cookie itself is never sent back.
+//! A session missing from the index can't be revoked nor refreshed, so it isn't accepted.
+
+use crate::{
+    db_wrapper::get_redis,
+    models::user::UserInfo,
+    utils::{random_string, sha3_256_encode},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Uuid, chrono::Local};
+use std::collections::HashMap;
+
+/// Longest session, a remembered login
+const INDEX_TTL: i64 = 90 * 24 * 60 * 60;
+const TOUCH_INTERVAL: i64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionView {
+    pub id: String,
+    pub login_time: i64,
+    pub last_seen: Option<i64>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// The session of the request
+    pub current: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevokeSession {
+    /// Every other session of the user when missing
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevokeUserSessions {
+    pub id: Uuid,
+}
+
+pub struct Session;
+
+impl Session {
+    pub fn id(cookie: &str) -> String {
+        sha3_256_encode(cookie.to_string())
+    }
+
+    fn index_key(user_id: Uuid) -> String {
+        format!("user_sessions_{}", user_id)
+    }
+
+    /// Store the user info under a new cookie
+    pub async fn create(info: &UserInfo, ttl: i64) -> String {
+        let cookie = sha3_256_encode(random_string(32));
+        let redis_pool = get_redis();
+        redis_pool
+            .hset(&cookie, "login_time", Local::now().timestamp())
+            .await;
+        redis_pool
+            .hset(&cookie, "info", serde_json::json!(info).to_string())
+            .await;
+        redis_pool.expire(&cookie, ttl).await;
+
+        let index = Self::index_key(info.id);
+        redis_pool.hset(&index, &Self::id(&cookie), &cookie).await;
+        redis_pool.expire(&index, INDEX_TTL).await;
+        cookie
+    }
+
+    /// Sessions created before the index existed are not in it
+    pub async fn is_indexed(user_id: Uuid, cookie: &str) -> bool {
+        get_redis()
+            .hexists(&Self::index_key(user_id), &Self::id(cookie))
+            .await
+    }
+
+    /// Record the client of the request
+    pub async fn touch(cookie: &str, ip: Option<String>, user_agent: Option<String>) {
+        let redis_pool = get_redis();
+        let now = Local::now().timestamp();
+        let last_seen = redis_pool
+            .hget::<Option<i64>>(cookie, "last_seen")
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if now - last_seen < TOUCH_INTERVAL {
+            return;
+        }
+
+        redis_pool.hset(cookie, "last_seen", now).await;
+        if let Some(ip) = ip {
+            redis_pool.hset(cookie, "ip", ip).await;
+        }
+        if let Some(user_agent) = user_agent {
+            redis_pool.hset(cookie, "user_agent", user_agent).await;
+        }
+        // Expired meanwhile, the fields above would stay without a ttl
+        if !redis_pool.hexists(cookie, "info").await {
+            redis_pool.del(cookie).await;
+        }
+    }
+
+    /// Live sessions of the user, the latest used first
+    pub async fn list(user_id: Uuid, current_cookie: &str) -> Vec<SessionView> {
+        let redis_pool = get_redis();
+        let index = Self::index_key(user_id);
+        let cookies = redis_pool.hgetall::<HashMap<String, String>>(&index).await;
+
+        let mut sessions = Vec::with_capacity(cookies.len());
+        for (id, cookie) in cookies {
+            let login_time = match redis_pool.hget::<Option<i64>>(&cookie, "login_time").await {
+                Ok(Some(time)) => time,
+                // Expired
+                _ => {
+                    redis_pool.hdel(&index, &id).await;
+                    continue;
+                }
+            };
+            sessions.push(SessionView {
+                current: cookie == current_cookie,
+                id,
+                login_time,
+                last_seen: redis_pool.hget(&cookie, "last_seen").await.ok().flatten(),
+                ip: redis_pool.hget(&cookie, "ip").await.ok().flatten(),
+                user_agent: redis_pool.hget(&cookie, "user_agent").await.ok().flatten(),
+            });
+        }
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_seen.unwrap_or(s.login_time)));
+        sessions
+    }
+
+    pub async fn revoke(user_id: Uuid, id: &str) -> bool {
+        let redis_pool = get_redis();
+        let index = Self::index_key(user_id);
+        match redis_pool.hget::<Option<String>>(&index, id).await {
+            Ok(Some(cookie)) => {
+                redis_pool.hdel(&index, id).await;
+                redis_pool.del(&cookie).await
+            }
+            _ => false,
+        }
+    }
+
+    /// Revoke every session of the user but `except`, returns how many were live
+    pub async fn revoke_all(user_id: Uuid, except: Option<&str>) -> usize {
+        let redis_pool = get_redis();
+        let index = Self::index_key(user_id);
+        let cookies = redis_pool.hgetall::<HashMap<String, String>>(&index).await;
+
+        let mut count = 0;
+        for (id, cookie) in cookies {
+            if Some(cookie.as_str()) == except {
+                continue;
+            }
+            redis_pool.hdel(&index, &id).await;
+            if redis_pool.del(&cookie).await {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Replace the cached user info of every live session
+    pub async fn refresh(info: &UserInfo) {
+        let redis_pool = get_redis();
+        let index = Self::index_key(info.id);
+        let cookies = redis_pool.hgetall::<HashMap<String, String>>(&index).await;
+        let info = serde_json::json!(info).to_string();
+
+        for (id, cookie) in cookies {
+            if !redis_pool.hexists(&cookie, "login_time").await {
+                redis_pool.hdel(&index, &id).await;
+                continue;
+            }
+            redis_pool.hset(&cookie, "info", info.as_str()).await;
+            // Expired meanwhile, the info above would stay without a ttl
+            if !redis_pool.hexists(&cookie, "login_time").await {
+                redis_pool.del(&cookie).await;
+            }
+        }
+    }
+
+    /// Sign out
+    pub async fn remove(user_id: Uuid, cookie: &str) -> bool {
+        let redis_pool = get_redis();
+        redis_pool
+            .hdel(&Self::index_key(user_id), Self::id(cookie))
+            .await;
+        redis_pool.del(cookie).await
+    }
+}
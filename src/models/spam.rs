@@ -0,0 +1,164 @@
+use crate::{
+    db_wrapper::get_postgres,
+    utils::spam::{spam_probability, tokenize},
+};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Uuid, chrono::NaiveDateTime};
+
+/// Learned comments of each kind needed before the classifier is trusted
+const MIN_TRAINING: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "spam_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SpamAction {
+    Reject,
+    Moderate,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct SpamRule {
+    pub id: Uuid,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: SpamAction,
+    pub create_time: NaiveDateTime,
+}
+
+impl SpamRule {
+    pub async fn list() -> Result<Vec<Self>, String> {
+        sqlx::query_as::<_, SpamRule>(
+            r#"SELECT id, pattern, is_regex, action, create_time FROM spam_rules ORDER BY create_time"#,
+        )
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    pub async fn delete(id: Uuid) -> Result<u64, String> {
+        sqlx::query(r#"DELETE FROM spam_rules WHERE id = $1"#)
+            .bind(id)
+            .execute(get_postgres())
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// The case is ignored, a regex that stopped compiling never matches
+    pub fn matches(&self, text: &str) -> bool {
+        if self.is_regex {
+            RegexBuilder::new(&self.pattern)
+                .case_insensitive(true)
+                .build()
+                .map(|re| re.is_match(text))
+                .unwrap_or(false)
+        } else {
+            text.to_lowercase().contains(&self.pattern.to_lowercase())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NewSpamRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub action: SpamAction,
+}
+
+impl NewSpamRule {
+    /// Id of the new rule
+    pub async fn insert(&self) -> Result<Uuid, String> {
+        let pattern = self.pattern.trim();
+        if pattern.is_empty() {
+            return Err("Pattern is empty".to_string());
+        }
+        if self.is_regex {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| format!("Invalid regex: {}", e))?;
+        }
+        sqlx::query_scalar::<_, Uuid>(
+            r#"INSERT INTO spam_rules (pattern, is_regex, action) VALUES ($1, $2, $3) RETURNING id"#,
+        )
+        .bind(pattern)
+        .bind(self.is_regex)
+        .bind(self.action)
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                "Rule already exists".to_string()
+            }
+            other => format!("{}", other),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TokenCount {
+    spam: i64,
+    ham: i64,
+}
+
+pub struct SpamBayes;
+
+impl SpamBayes {
+    /// Learn a comment from the decision of a moderator, only the first decision counts
+    pub async fn train(comment_id: Uuid, text: &str, spam: bool) -> Result<(), String> {
+        let tokens = tokenize(text);
+        let mut tx = get_postgres().begin().await.map_err(|e| format!("{}", e))?;
+        let res = sqlx::query(
+            r#"INSERT INTO spam_training (comment_id, spam) VALUES ($1, $2)
+            ON CONFLICT (comment_id) DO NOTHING"#,
+        )
+        .bind(comment_id)
+        .bind(spam)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("{}", e))?;
+        if res.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"INSERT INTO spam_tokens (token, spam, ham)
+            SELECT unnest($1::varchar[]), $2, $3
+            ON CONFLICT (token) DO UPDATE
+            SET spam = spam_tokens.spam + excluded.spam, ham = spam_tokens.ham + excluded.ham"#,
+        )
+        .bind(&tokens)
+        .bind(spam as i64)
+        .bind(!spam as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("{}", e))?;
+        tx.commit().await.map_err(|e| format!("{}", e))
+    }
+
+    /// None until enough comments of each kind were learned
+    pub async fn probability(text: &str) -> Result<Option<f64>, String> {
+        let (spam_docs, ham_docs) = sqlx::query_as::<_, (i64, i64)>(
+            r#"SELECT count(*) FILTER (WHERE spam), count(*) FILTER (WHERE NOT spam)
+            FROM spam_training"#,
+        )
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        if spam_docs < MIN_TRAINING || ham_docs < MIN_TRAINING {
+            return Ok(None);
+        }
+
+        let counts = sqlx::query_as::<_, TokenCount>(
+            r#"SELECT spam, ham FROM spam_tokens WHERE token = ANY($1)"#,
+        )
+        .bind(tokenize(text))
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        let counts: Vec<(i64, i64)> = counts.into_iter().map(|c| (c.spam, c.ham)).collect();
+        Ok(Some(spam_probability(&counts, spam_docs, ham_docs)))
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{db_wrapper::get_postgres, models::article_tag_relation::Relations};
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
+use sqlx::types::{Uuid, chrono::NaiveDateTime};
 
 #[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
 pub struct Tags {
@@ -9,12 +9,16 @@ pub struct Tags {
 }
 
 impl Tags {
-    pub async fn insert(tag: &str) -> Result<u64, String> {
-        sqlx::query(r#"INSERT INTO tags (tag) VALUES ($1)"#)
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Id of the new tag
+    pub async fn insert(tag: &str) -> Result<Uuid, String> {
+        sqlx::query_scalar::<_, Uuid>(r#"INSERT INTO tags (tag) VALUES ($1) RETURNING id"#)
             .bind(tag)
-            .execute(get_postgres())
+            .fetch_one(get_postgres())
             .await
-            .map(|r| r.rows_affected())
             .map_err(|e| format!("{}", e))
     }
 
@@ -25,6 +29,18 @@ impl Tags {
             .map_err(|e| format!("{}", e))
     }
 
+    /// Name of the tag
+    pub async fn query_name(id: Uuid) -> Result<String, String> {
+        sqlx::query_scalar::<_, String>(r#"SELECT tag FROM tags WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(get_postgres())
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => "Tag not found".to_string(),
+                other => format!("{}", other),
+            })
+    }
+
     pub async fn delete_tag(id: Uuid) -> Result<u64, String> {
         Relations::delete_all(id, false).await;
         sqlx::query(r#"DELETE FROM tags WHERE id = $1"#)
@@ -74,3 +90,25 @@ impl TagCount {
             .map_err(|e| format!("{}", e))
     }
 }
+
+/// A tag carried by published articles, with the last time one of them changed
+#[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
+pub struct TagLastModify {
+    pub id: Uuid,
+    pub modify_time: NaiveDateTime,
+}
+
+impl TagLastModify {
+    pub async fn view_all() -> Result<Vec<Self>, String> {
+        sqlx::query_as(
+            r#"SELECT a.tag_id AS id, max(b.modify_time) AS modify_time
+            FROM article_tag_relation a JOIN articles b ON a.article_id = b.id
+            WHERE b.published = true
+            GROUP BY a.tag_id
+            ORDER BY a.tag_id"#,
+        )
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+}
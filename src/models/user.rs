@@ -1,8 +1,19 @@
 use crate::{
     db_wrapper::{get_postgres, get_redis},
-    models::notify::UserNotify,
+    models::{
+        login_throttle::{ClearLockout, LockKind, LoginThrottle},
+        notify::UserNotify,
+        role::Role,
+        session::Session,
+        user_oauth::UserOAuth,
+        user_totp::{TotpSetup, UserTotp},
+    },
     utils::{
-        get_password, github_information::get_github_primary_email, random_string, sha3_256_encode,
+        get_password,
+        mail::{mail_enabled, send_mail},
+        oauth::OAuthIdentity,
+        password::{hash_password, needs_rehash, verify_password},
+        random_string, sha3_256_encode, signed_link, site_url,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -10,17 +21,26 @@ use sqlx::types::{
     Uuid,
     chrono::{Local, NaiveDateTime},
 };
+use std::collections::HashMap;
 
 #[derive(sqlx::FromRow, Debug, Clone, Deserialize, Serialize)]
 pub struct UserInfo {
     pub id: Uuid,
     pub account: String,
     pub nickname: String,
-    pub groups: i16,
+    /// Missing in the sessions created before the roles existed, they get no permission
+    #[serde(default)]
+    pub role: Role,
     pub say: Option<String>,
     pub email: String,
+    /// Missing in the sessions created before the verification existed
+    #[serde(default)]
+    pub email_verified: bool,
     pub create_time: NaiveDateTime,
-    pub github: Option<String>,
+    /// Providers of the linked accounts, only filled in the user list
+    #[sqlx(default)]
+    #[serde(default)]
+    pub oauth: Vec<String>,
 }
 
 impl UserInfo {
@@ -32,32 +52,110 @@ impl UserInfo {
             .map(|r| r.rows_affected())
             .map_err(|e| format!("{}", e))?;
         UserNotify::remove_with_user(id).await;
+        Session::revoke_all(id, None).await;
         Ok(res)
     }
 
-    pub async fn change_permission(data: ChangePermission) -> Result<u64, String> {
-        sqlx::query(r#"UPDATE users SET groups = $1 WHERE id = $2"#)
-            .bind(data.permission)
+    pub async fn change_role(data: ChangeRole) -> Result<u64, String> {
+        if data.role != Role::Admin {
+            let others = sqlx::query_scalar::<_, i64>(
+                r#"SELECT count(*) FROM users WHERE role = 'admin' AND disabled = 0 AND id <> $1"#,
+            )
             .bind(data.id)
-            .execute(get_postgres())
+            .fetch_one(get_postgres())
             .await
-            .map(|r| r.rows_affected())
-            .map_err(|e| format!("{}", e))
+            .map_err(|e| format!("{}", e))?;
+            if others == 0 {
+                return Err("The last admin can't lose the role".to_string());
+            }
+        }
+
+        let info = sqlx::query_as::<_, UserInfo>(
+            r#"UPDATE users SET role = $1 WHERE id = $2
+            RETURNING id, account, nickname, role, say, email, email_verified, create_time"#,
+        )
+        .bind(data.role)
+        .bind(data.id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+
+        match info {
+            // The sessions didn't go through the second factor an admin needs
+            Some(info) if UserTotp::required(info.role) && !UserTotp::is_enabled(info.id).await => {
+                Session::revoke_all(info.id, None).await;
+                Ok(1)
+            }
+            Some(info) => {
+                Session::refresh(&info).await;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Admin override of the email verification
+    pub async fn verify_email(data: VerifiedUser) -> Result<u64, String> {
+        let info = sqlx::query_as::<_, UserInfo>(
+            r#"UPDATE users SET email_verified = $1 WHERE id = $2
+            RETURNING id, account, nickname, role, say, email, email_verified, create_time"#,
+        )
+        .bind(data.verified)
+        .bind(data.id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+
+        match info {
+            Some(info) => {
+                Session::refresh(&info).await;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Read from the database, the session may predate the verification
+    pub async fn is_email_verified(id: Uuid) -> bool {
+        sqlx::query_scalar::<_, bool>(r#"SELECT email_verified FROM users WHERE id = $1"#)
+            .bind(id)
+            .fetch_one(get_postgres())
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Mail a signed link to the email, does nothing when mail is not configured
+    pub async fn send_verification_mail(&self) -> Result<(), String> {
+        if !mail_enabled() {
+            return Ok(());
+        }
+        let body = format!(
+            "Hi {},\n\nOpen the link below within {} hours to verify the email of '{}':\n\n{}\n",
+            self.nickname,
+            VERIFY_LINK_TTL / 3600,
+            self.account,
+            VerifyEmail::link(self.id, &self.email)
+        );
+        send_mail(&self.email, "Verify your email", body).await
     }
 
     pub async fn disabled_user(data: DisabledUser) -> Result<u64, String> {
-        sqlx::query(r#"UPDATE users SET disabled = $1 WHERE id = $2"#)
+        let res = sqlx::query(r#"UPDATE users SET disabled = $1 WHERE id = $2"#)
             .bind(data.disabled)
             .bind(data.id)
             .execute(get_postgres())
             .await
             .map(|r| r.rows_affected())
-            .map_err(|e| format!("{}", e))
+            .map_err(|e| format!("{}", e))?;
+        if data.disabled != 0 {
+            Session::revoke_all(data.id, None).await;
+        }
+        Ok(res)
     }
 
     pub async fn view_user(id: Uuid) -> Result<Self, String> {
         sqlx::query_as::<_, UserInfo>(
-            r#"SELECT id, account, nickname, groups, say, email, create_time, github FROM users
+            r#"SELECT id, account, nickname, role, say, email, email_verified, create_time FROM users
             WHERE id = $1"#,
         )
         .bind(id)
@@ -66,20 +164,9 @@ impl UserInfo {
         .map_err(|e| format!("{}", e))
     }
 
-    pub async fn view_user_with_github(github: &str) -> Result<Self, String> {
-        sqlx::query_as::<_, UserInfo>(
-            r#"SELECT id, account, nickname, groups, say, email, create_time, github FROM users
-            WHERE github = $1 AND disabled = 0"#,
-        )
-        .bind(github)
-        .fetch_one(get_postgres())
-        .await
-        .map_err(|e| format!("{}", e))
-    }
-
     pub async fn view_user_with_email(email: &str) -> Result<Self, String> {
         sqlx::query_as::<_, UserInfo>(
-            r#"SELECT id, account, nickname, groups, say, email, create_time, github FROM users
+            r#"SELECT id, account, nickname, role, say, email, email_verified, create_time FROM users
             WHERE email = $1 AND disabled = 0"#,
         )
         .bind(email)
@@ -97,7 +184,9 @@ impl UserInfo {
     pub async fn view_user_list(limit: i64, offset: i64) -> Result<Vec<Self>, String> {
         let limit = limit.min(50);
         sqlx::query_as::<_, UserInfo>(
-            r#"SELECT id, account, nickname, groups, say, email, create_time, github FROM users
+            r#"SELECT id, account, nickname, role, say, email, email_verified, create_time,
+            array(SELECT provider FROM user_oauth WHERE user_id = users.id ORDER BY provider) AS oauth
+            FROM users
             ORDER BY create_time
             LIMIT $1 OFFSET $2"#,
         )
@@ -116,7 +205,7 @@ impl UserInfo {
             serde_json::from_str::<UserInfo>(&info).unwrap()
         } else {
             let info = sqlx::query_as::<_, UserInfo>(
-                r#"SELECT id, account, nickname, groups, say, email, create_time, github FROM users WHERE account = 'admin'"#,
+                r#"SELECT id, account, nickname, role, say, email, email_verified, create_time FROM users WHERE account = 'admin'"#,
             )
                 .fetch_one(get_postgres())
                 .await
@@ -133,78 +222,208 @@ impl UserInfo {
 struct NewUser {
     pub account: String,
     pub password: String,
-    pub salt: String,
     pub nickname: String,
     pub say: Option<String>,
     pub email: String,
-    pub github: Option<String>,
 }
 
 impl NewUser {
-    pub fn new(reg: RegisteredUser) -> Result<Self, String> {
-        let salt = random_string(6);
+    pub async fn new(reg: RegisteredUser) -> Result<Self, String> {
         let password = get_password(&reg.password)
             .ok_or_else(|| "Invalid password format: length insufficient".to_string())?;
 
         Ok(NewUser {
             account: reg.account,
-            password: sha3_256_encode(password + &salt),
-            salt,
+            password: hash_password(password).await?,
             nickname: reg.nickname,
             say: reg.say,
             email: reg.email,
-            github: None,
         })
     }
-    pub fn new_with_github(
-        email: String,
-        github: String,
-        account: String,
-        nickname: String,
-    ) -> Self {
-        NewUser {
-            account,
-            password: sha3_256_encode(random_string(8)),
-            salt: random_string(6),
-            email,
-            say: None,
-            nickname,
-            github: Some(github),
-        }
-    }
 
     pub async fn insert(self) -> Result<String, String> {
         let res = sqlx::query_as::<_, UserInfo>(
-            r#"INSERT INTO users (account, password, salt, nickname, say, email, github)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, account, nickname, groups, say, email, create_time, github"#,
+            r#"INSERT INTO users (account, password, salt, nickname, say, email)
+            VALUES ($1, $2, '', $3, $4, $5)
+            RETURNING id, account, nickname, role, say, email, email_verified, create_time"#,
         )
         .bind(&self.account)
         .bind(&self.password)
-        .bind(&self.salt)
         .bind(&self.nickname)
         .bind(&self.say)
         .bind(&self.email)
-        .bind(&self.github)
         .fetch_one(get_postgres())
         .await;
         match res {
-            Ok(info) => self.set_cookies(info).await,
+            Ok(info) => {
+                // The account works without it, the mail can be sent again later
+                if let Err(e) = info.send_verification_mail().await {
+                    tracing::warn!("can't send verification mail to {}: {}", info.email, e);
+                }
+                Ok(Session::create(&info, 24 * 3600).await)
+            }
             Err(err) => Err(format!("{}", err)),
         }
     }
+}
+
+/// Seconds a login waits for the one-time password
+const PENDING_LOGIN_TTL: i64 = 5 * 60;
+const PENDING_LOGIN_ATTEMPTS: i64 = 5;
+
+/// What comes after the password or oauth check
+pub enum LoginStep {
+    /// The session cookie
+    Session(String),
+    /// The session is only created once the one-time password is checked
+    Totp(TotpChallenge),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpChallenge {
+    pub token: String,
+    /// The user has to enroll before logging in
+    pub setup: bool,
+}
+
+/// Session lifetime in seconds from the cookie max age in hours
+fn session_ttl(max_age: &Option<i64>) -> i64 {
+    match *max_age {
+        Some(t) => t * 3600,
+        None => 24 * 60 * 60,
+    }
+}
+
+/// Create the session, or hold it until the second factor is checked
+async fn start_login(info: UserInfo, max_age: &Option<i64>) -> LoginStep {
+    let enabled = UserTotp::is_enabled(info.id).await;
+    if !enabled && !UserTotp::required(info.role) {
+        return LoginStep::Session(Session::create(&info, session_ttl(max_age)).await);
+    }
 
-    async fn set_cookies(&self, info: UserInfo) -> Result<String, String> {
-        let cookie = sha3_256_encode(random_string(8));
+    let token = random_string(32);
+    let key = TotpLogin::redis_key(&token);
+    let redis_pool = get_redis();
+    redis_pool.hset(&key, "user_id", info.id.to_string()).await;
+    redis_pool
+        .hset(&key, "max_age", max_age.unwrap_or_default())
+        .await;
+    redis_pool.expire(&key, PENDING_LOGIN_TTL).await;
+    let index = TotpLogin::index_key(info.id);
+    redis_pool.hset(&index, &key, 1).await;
+    redis_pool.expire(&index, PENDING_LOGIN_TTL).await;
+    LoginStep::Totp(TotpChallenge {
+        token,
+        setup: !enabled,
+    })
+}
+
+/// Second step of a login
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TotpLogin {
+    pub token: String,
+    #[serde(default)]
+    pub code: String,
+}
+
+pub struct TotpLoginDone {
+    pub cookie: String,
+    pub max_age: Option<i64>,
+    /// Set when the login finished the enrollment
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+impl TotpLogin {
+    fn redis_key(token: &str) -> String {
+        format!("login_pending_{}", sha3_256_encode(token.to_string()))
+    }
+
+    /// `login_pending_user_{id}` holds the keys of the pending logins of a user
+    fn index_key(user_id: Uuid) -> String {
+        format!("login_pending_user_{}", user_id)
+    }
+
+    /// Drop the pending logins of the user, their password is no longer the one checked
+    pub async fn revoke_all(user_id: Uuid) {
         let redis_pool = get_redis();
-        redis_pool
-            .hset(&cookie, "login_time", Local::now().timestamp())
-            .await;
-        redis_pool
-            .hset(&cookie, "info", serde_json::json!(info).to_string())
-            .await;
-        redis_pool.expire(&cookie, 24 * 3600).await;
-        Ok(cookie)
+        let index = Self::index_key(user_id);
+        let keys = redis_pool.hgetall::<HashMap<String, String>>(&index).await;
+        for key in keys.keys() {
+            redis_pool.del(key).await;
+        }
+        redis_pool.del(&index).await;
+    }
+
+    /// The user waiting for the second step and the cookie max age
+    async fn pending(&self) -> Result<(Users, Option<i64>), String> {
+        let key = Self::redis_key(&self.token);
+        let redis_pool = get_redis();
+        let user_id = match redis_pool.hget::<Option<String>>(&key, "user_id").await {
+            Ok(Some(id)) => id.parse::<Uuid>().map_err(|e| format!("{}", e))?,
+            _ => {
+                redis_pool.del(&key).await;
+                return Err("Login has expired, please log in again".to_string());
+            }
+        };
+        let max_age = redis_pool
+            .hget::<Option<i64>>(&key, "max_age")
+            .await
+            .ok()
+            .flatten()
+            .filter(|t| *t > 0);
+
+        let user =
+            sqlx::query_as::<_, Users>(r#"SELECT * FROM users WHERE disabled = 0 AND id = $1"#)
+                .bind(user_id)
+                .fetch_one(get_postgres())
+                .await
+                .map_err(|e| format!("{}", e))?;
+        Ok((user, max_age))
+    }
+
+    /// Enrollment of a user who must use a second factor but has none yet
+    pub async fn setup(&self) -> Result<TotpSetup, String> {
+        let (user, _) = self.pending().await?;
+        UserTotp::setup(user.id, &user.account).await
+    }
+
+    pub async fn finish(&self) -> Result<TotpLoginDone, String> {
+        let (user, max_age) = self.pending().await?;
+        let key = Self::redis_key(&self.token);
+        let redis_pool = get_redis();
+        if redis_pool.hincrby(&key, "attempts", 1).await > PENDING_LOGIN_ATTEMPTS {
+            redis_pool.del(&key).await;
+            return Err("Too many attempts, please log in again".to_string());
+        }
+
+        let throttle = LoginThrottle::new(&user.account, None);
+        throttle.check().await?;
+
+        let res = if UserTotp::is_enabled(user.id).await {
+            UserTotp::check(user.id, &self.code).await.map(|_| None)
+        } else {
+            UserTotp::enable(user.id, &self.code).await.map(Some)
+        };
+        let recovery_codes = match res {
+            Ok(codes) => codes,
+            Err(e) => {
+                throttle.fail().await;
+                return Err(e);
+            }
+        };
+
+        // Single use, a concurrent request may have taken it
+        if !redis_pool.del(&key).await {
+            return Err("Login has expired, please log in again".to_string());
+        }
+        redis_pool.hdel(&Self::index_key(user.id), &key).await;
+        throttle.succeed().await;
+        let cookie = Session::create(&user.into_user_info(), session_ttl(&max_age)).await;
+        Ok(TotpLoginDone {
+            cookie,
+            max_age,
+            recovery_codes,
+        })
     }
 }
 
@@ -219,7 +438,7 @@ pub struct RegisteredUser {
 
 impl RegisteredUser {
     pub async fn insert(self) -> Result<String, String> {
-        NewUser::new(self)?.insert().await
+        NewUser::new(self).await?.insert().await
     }
 }
 
@@ -242,12 +461,10 @@ impl ChangePassword {
 
         let new_password = get_password(&self.new_password)
             .ok_or_else(|| "Invalid new password format: length insufficient".to_string())?;
-        let salt = random_string(6);
-        let password = sha3_256_encode(new_password + &salt);
+        let password = hash_password(new_password).await?;
 
-        sqlx::query(r#"UPDATE users SET password = $1, salt = $2 WHERE id = $3"#)
+        sqlx::query(r#"UPDATE users SET password = $1, salt = '' WHERE id = $2"#)
             .bind(&password)
-            .bind(&salt)
             .bind(info.id)
             .execute(get_postgres())
             .await
@@ -271,12 +488,201 @@ impl ChangePassword {
         .fetch_one(get_postgres())
         .await;
         match old_user {
-            Ok(old) => Ok(old.password == sha3_256_encode(old_password + &old.salt)),
+            Ok(old) => Ok(verify_password(old_password, old.password, old.salt).await),
             Err(_) => Ok(false),
         }
     }
 }
 
+/// Seconds a password reset link stays valid
+const RESET_TOKEN_TTL: i64 = 3600;
+/// Reset requests are counted per email and per ip for an hour
+const RESET_RATE_WINDOW: i64 = 3600;
+const RESET_EMAIL_LIMIT: i64 = 3;
+const RESET_IP_LIMIT: i64 = 10;
+
+/// Requests of the window so far, counted in `password_reset_rate_{kind}_{key}`
+async fn reset_rate_hit(kind: &str, key: &str) -> i64 {
+    let redis_key = format!("password_reset_rate_{}_{}", kind, key);
+    let count = get_redis().hincrby(&redis_key, "count", 1).await;
+    if count == 1 {
+        get_redis().expire(&redis_key, RESET_RATE_WINDOW).await;
+    }
+    count
+}
+
+/// Mail a reset link, redis keys are `password_reset_{sha3(token)}` holding the user id
+/// and `password_reset_user_{id}` holding the hash of the only valid token of the user
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ForgotPassword {
+    pub email: String,
+}
+
+impl ForgotPassword {
+    /// Succeeds once mail is enabled, whatever the email and however many requests were made,
+    /// the mail is sent in the background so that the api doesn't tell which emails are registered
+    pub async fn send_reset_mail(self, ip: Option<String>) -> Result<(), String> {
+        if !mail_enabled() {
+            return Err("Mail is not configured".to_string());
+        }
+        // Unknown emails are counted too, the limit mustn't tell them apart either
+        let email_over =
+            reset_rate_hit("email", &sha3_256_encode(self.email.trim().to_lowercase())).await
+                > RESET_EMAIL_LIMIT;
+        let ip_over = match ip {
+            Some(ref ip) => reset_rate_hit("ip", ip).await > RESET_IP_LIMIT,
+            None => false,
+        };
+        if email_over || ip_over {
+            tracing::info!("password reset request from {:?} throttled", ip);
+            return Ok(());
+        }
+
+        tokio::spawn(async move {
+            if let Err(e) = self.mail_link().await {
+                tracing::error!("can't send password reset mail: {}", e);
+            }
+        });
+        Ok(())
+    }
+
+    async fn mail_link(&self) -> Result<(), String> {
+        let info = match UserInfo::view_user_with_email(&self.email).await {
+            Ok(info) => info,
+            Err(_) => return Ok(()),
+        };
+
+        let token = random_string(32);
+        let token_hash = sha3_256_encode(token.clone());
+        let redis_pool = get_redis();
+        let user_key = format!("password_reset_user_{}", info.id);
+        // A new link replaces the previous one
+        if let Ok(Some(old)) = redis_pool.get(&user_key).await {
+            redis_pool.del(format!("password_reset_{}", old)).await;
+        }
+        let token_key = format!("password_reset_{}", token_hash);
+        redis_pool.set(&token_key, &info.id.to_string()).await;
+        redis_pool.expire(&token_key, RESET_TOKEN_TTL).await;
+        redis_pool.set(&user_key, &token_hash).await;
+        redis_pool.expire(&user_key, RESET_TOKEN_TTL).await;
+
+        let body = format!(
+            "Hi {},\n\nOpen the link below within {} minutes to set a new password for '{}':\n\n{}/reset_password?token={}\n\nIgnore this mail if you didn't ask for it.\n",
+            info.nickname,
+            RESET_TOKEN_TTL / 60,
+            info.account,
+            site_url(),
+            token
+        );
+        send_mail(&info.email, "Reset your password", body).await
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResetPassword {
+    pub token: String,
+    pub password: String,
+}
+
+impl ResetPassword {
+    pub async fn reset(&self) -> Result<u64, String> {
+        let password = get_password(&self.password)
+            .ok_or_else(|| "Invalid password format: length insufficient".to_string())?;
+
+        let redis_pool = get_redis();
+        let token_key = format!("password_reset_{}", sha3_256_encode(self.token.clone()));
+        let invalid = || "Invalid or expired token".to_string();
+        let id = match redis_pool.get(&token_key).await {
+            Ok(Some(id)) => id.parse::<Uuid>().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+        // Only the request that removes the token may use it
+        if !redis_pool.del(&token_key).await {
+            return Err(invalid());
+        }
+        redis_pool.del(format!("password_reset_user_{}", id)).await;
+
+        let password = hash_password(password).await?;
+        let account = sqlx::query_scalar::<_, String>(
+            r#"UPDATE users SET password = $1, salt = '' WHERE id = $2 RETURNING account"#,
+        )
+        .bind(&password)
+        .bind(id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        let account = match account {
+            Some(account) => account,
+            None => return Ok(0),
+        };
+
+        // Whoever knew the old password is signed out, and the owner may log in again at once
+        Session::revoke_all(id, None).await;
+        TotpLogin::revoke_all(id).await;
+        LoginThrottle::clear(&ClearLockout {
+            kind: LockKind::Account,
+            key: account,
+        })
+        .await;
+        Ok(1)
+    }
+}
+
+/// Seconds an email verification link stays valid
+const VERIFY_LINK_TTL: i64 = 48 * 3600;
+
+/// Query of the verification link, signed so that nothing has to be stored
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VerifyEmail {
+    pub id: Uuid,
+    pub email: String,
+    pub expires: i64,
+    pub sig: String,
+}
+
+impl VerifyEmail {
+    fn link(id: Uuid, email: &str) -> String {
+        let id = id.to_string();
+        let expires = (Local::now().timestamp() + VERIFY_LINK_TTL).to_string();
+        let sig = signed_link::sign(&["verify_email", &id, email, &expires]);
+        url::Url::parse_with_params(
+            &format!("{}/api/v1/user/verify_email", site_url()),
+            &[
+                ("id", id.as_str()),
+                ("email", email),
+                ("expires", expires.as_str()),
+                ("sig", sig.as_str()),
+            ],
+        )
+        .map(|url| url.to_string())
+        .unwrap_or_default()
+    }
+
+    /// The link is void once the user changes the email
+    pub async fn verify(&self) -> Result<u64, String> {
+        let id = self.id.to_string();
+        let expires = self.expires.to_string();
+        if !signed_link::verify(&["verify_email", &id, &self.email, &expires], &self.sig) {
+            return Err("Invalid verification link".to_string());
+        }
+        if self.expires < Local::now().timestamp() {
+            return Err("The verification link has expired".to_string());
+        }
+
+        match sqlx::query(r#"UPDATE users SET email_verified = true WHERE id = $1 AND email = $2"#)
+            .bind(self.id)
+            .bind(&self.email)
+            .execute(get_postgres())
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| format!("{}", e))?
+        {
+            0 => Err("The email of the account has changed".to_string()),
+            n => Ok(n),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EditUser {
     pub nickname: String,
@@ -291,9 +697,12 @@ impl EditUser {
             &redis_pool.hget::<String>(cookie, "info").await.unwrap(),
         )
         .unwrap();
+        // A new email has to be verified again
         let res = sqlx::query_as::<_, UserInfo>(
-            r#"UPDATE users SET nickname = $1, say = $2, email = $3 WHERE id = $4
-            RETURNING id, account, nickname, groups, say, email, create_time, github"#,
+            r#"UPDATE users SET nickname = $1, say = $2, email = $3,
+            email_verified = email_verified AND email = $3
+            WHERE id = $4
+            RETURNING id, account, nickname, role, say, email, email_verified, create_time"#,
         )
         .bind(&self.nickname)
         .bind(&self.say)
@@ -303,9 +712,13 @@ impl EditUser {
         .await;
         match res {
             Ok(data) => {
-                redis_pool
-                    .hset(cookie, "info", serde_json::json!(data).to_string())
-                    .await;
+                // Every session of the user shows the new info
+                Session::refresh(&data).await;
+                if data.email != info.email
+                    && let Err(e) = data.send_verification_mail().await
+                {
+                    tracing::warn!("can't send verification mail to {}: {}", data.email, e);
+                }
                 Ok(1)
             }
             Err(err) => Err(format!("{}", err)),
@@ -321,9 +734,15 @@ pub struct LoginUser {
 }
 
 impl LoginUser {
-    pub async fn verification(&self, max_age: &Option<i64>) -> Result<String, String> {
+    pub async fn verification(
+        &self,
+        max_age: &Option<i64>,
+        ip: Option<String>,
+    ) -> Result<LoginStep, String> {
         let password = get_password(&self.password)
             .ok_or_else(|| "Invalid password format: length insufficient".to_string())?;
+        let throttle = LoginThrottle::new(&self.account, ip);
+        throttle.check().await?;
         let res = sqlx::query_as::<_, Users>(
             r#"SELECT * FROM users WHERE disabled = 0 AND account = $1"#,
         )
@@ -332,31 +751,49 @@ impl LoginUser {
         .await;
         match res {
             Ok(data) => {
-                if data.password == sha3_256_encode(password + &data.salt) {
-                    let ttl = match *max_age {
-                        Some(t) => t * 3600,
-                        None => 24 * 60 * 60,
-                    };
-
-                    let redis_pool = get_redis();
-                    let cookie = sha3_256_encode(random_string(8));
-                    redis_pool
-                        .hset(&cookie, "login_time", Local::now().timestamp())
-                        .await;
-                    redis_pool
-                        .hset(
-                            &cookie,
-                            "info",
-                            serde_json::json!(data.into_user_info()).to_string(),
-                        )
-                        .await;
-                    redis_pool.expire(&cookie, ttl).await;
-                    Ok(cookie)
+                if verify_password(password.clone(), data.password.clone(), data.salt.clone()).await
+                {
+                    if needs_rehash(&data.password) {
+                        Self::upgrade_password(data.id, &data.password, password).await;
+                    }
+                    let step = start_login(data.into_user_info(), max_age).await;
+                    // Failures of the second step still count
+                    if let LoginStep::Session(_) = step {
+                        throttle.succeed().await;
+                    }
+                    Ok(step)
                 } else {
+                    throttle.fail().await;
                     Err(String::from("Invalid username or password"))
                 }
             }
-            Err(err) => Err(format!("{}", err)),
+            Err(err) => {
+                throttle.fail().await;
+                Err(format!("{}", err))
+            }
+        }
+    }
+
+    /// Replace a legacy hash now that the password is known, the login goes on if it fails
+    async fn upgrade_password(id: Uuid, old_hash: &str, password: String) {
+        let hash = match hash_password(password).await {
+            Ok(hash) => hash,
+            Err(e) => {
+                tracing::warn!("can't rehash password of {}: {}", id, e);
+                return;
+            }
+        };
+        // Skip if the password was changed meanwhile
+        if let Err(e) = sqlx::query(
+            r#"UPDATE users SET password = $1, salt = '' WHERE id = $2 AND password = $3"#,
+        )
+        .bind(&hash)
+        .bind(id)
+        .bind(old_hash)
+        .execute(get_postgres())
+        .await
+        {
+            tracing::warn!("can't rehash password of {}: {}", id, e);
         }
     }
 
@@ -364,68 +801,45 @@ impl LoginUser {
         self.remember
     }
 
-    pub async fn sign_out(cookies: &str) -> bool {
-        get_redis().del(cookies).await
+    pub async fn sign_out(user_id: Uuid, cookie: &str) -> bool {
+        Session::remove(user_id, cookie).await
     }
 
-    pub async fn login_with_github(
-        github: String,
-        nickname: String,
-        account: String,
-        token: &str,
-    ) -> Result<String, String> {
-        let ttl = 24 * 60 * 60;
-        let redis_pool = get_redis();
-        match UserInfo::view_user_with_github(&github).await {
-            // github already exists
+    /// Log in with the account, linking it by verified email or signing up when it is new
+    pub async fn login_with_oauth(
+        provider: &str,
+        identity: OAuthIdentity,
+    ) -> Result<LoginStep, String> {
+        let max_age = Some(24);
+        if let Some(user) = UserOAuth::find_user(provider, &identity).await? {
+            if user.disabled != 0 {
+                return Err("User is disabled".to_string());
+            }
+            return Ok(start_login(user.into_user_info(), &max_age).await);
+        }
+
+        let email = identity
+            .email
+            .clone()
+            .ok_or_else(|| format!("The {} account has no verified email", provider))?;
+
+        match UserInfo::view_user_with_email(&email).await {
+            // Whoever registered it may not own the email
+            Ok(data) if !data.email_verified => Err(format!(
+                "An account with an unverified email uses the email of the {} account",
+                provider
+            )),
+            // Account already exists but not linked
             Ok(data) => {
-                let cookie = sha3_256_encode(random_string(8));
-                redis_pool
-                    .hset(&cookie, "login_time", Local::now().timestamp())
-                    .await;
-                redis_pool
-                    .hset(&cookie, "info", serde_json::json!(data).to_string())
-                    .await;
-                redis_pool.expire(&cookie, ttl).await;
-                Ok(cookie)
+                UserOAuth::link(data.id, provider, &identity).await?;
+                Ok(start_login(data, &max_age).await)
             }
+            // sign up
             Err(_) => {
-                let email = match get_github_primary_email(token).await {
-                    Ok(data) => data,
-                    Err(e) => return Err(e),
-                };
-
-                match UserInfo::view_user_with_email(&email).await {
-                    // Account already exists but not linked
-                    Ok(mut data) => {
-                        match sqlx::query(r#"UPDATE users SET github = $1 WHERE id = $2"#)
-                            .bind(&github)
-                            .bind(data.id)
-                            .execute(get_postgres())
-                            .await
-                        {
-                            Ok(_) => {
-                                data.github = Some(github);
-                                let cookie = sha3_256_encode(random_string(8));
-                                redis_pool
-                                    .hset(&cookie, "login_time", Local::now().timestamp())
-                                    .await;
-                                redis_pool
-                                    .hset(&cookie, "info", serde_json::json!(data).to_string())
-                                    .await;
-                                redis_pool.expire(&cookie, ttl).await;
-                                Ok(cookie)
-                            }
-                            Err(err) => Err(format!("{}", err)),
-                        }
-                    }
-                    // sign up
-                    Err(_) => {
-                        NewUser::new_with_github(email, github, account, nickname)
-                            .insert()
-                            .await
-                    }
-                }
+                // Nobody knows the password, the user logs in with the provider
+                let password = hash_password(random_string(16)).await?;
+                let info = UserOAuth::sign_up(provider, &identity, &email, &password).await?;
+                Ok(start_login(info, &max_age).await)
             }
         }
     }
@@ -435,13 +849,13 @@ impl LoginUser {
 pub struct Users {
     pub id: Uuid,
     pub account: String,
-    pub github: Option<String>,
     pub password: String,
     pub salt: String,
-    pub groups: i16,
+    pub role: Role,
     pub nickname: String,
     pub say: Option<String>,
     pub email: String,
+    pub email_verified: bool,
     pub disabled: i16,
     pub create_time: NaiveDateTime,
 }
@@ -452,23 +866,30 @@ impl Users {
             id: self.id,
             account: self.account,
             nickname: self.nickname,
-            groups: self.groups,
+            role: self.role,
             say: self.say,
             email: self.email,
+            email_verified: self.email_verified,
             create_time: self.create_time,
-            github: self.github,
+            oauth: Vec::new(),
         }
     }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ChangePermission {
+pub struct ChangeRole {
     pub id: Uuid,
-    pub permission: i16,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VerifiedUser {
+    pub id: Uuid,
+    verified: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DisabledUser {
-    id: Uuid,
+    pub id: Uuid,
     disabled: i16,
 }
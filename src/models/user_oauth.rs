@@ -0,0 +1,130 @@
+use crate::{
+    db_wrapper::get_postgres,
+    models::user::{UserInfo, Users},
+    utils::oauth::OAuthIdentity,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Uuid, chrono::NaiveDateTime};
+
+/// An account on an oauth provider linked to a user
+#[derive(sqlx::FromRow, Debug, Clone, Serialize)]
+pub struct UserOAuth {
+    pub provider: String,
+    pub profile_url: Option<String>,
+    pub create_time: NaiveDateTime,
+}
+
+impl UserOAuth {
+    pub async fn view_with_user(user_id: Uuid) -> Result<Vec<Self>, String> {
+        sqlx::query_as::<_, UserOAuth>(
+            r#"SELECT provider, profile_url, create_time FROM user_oauth
+            WHERE user_id = $1
+            ORDER BY create_time"#,
+        )
+        .bind(user_id)
+        .fetch_all(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    /// The user the account is linked to, disabled users included
+    pub async fn find_user(
+        provider: &str,
+        identity: &OAuthIdentity,
+    ) -> Result<Option<Users>, String> {
+        // Github accounts linked before the provider layer used the profile url as subject
+        if let Some(ref profile_url) = identity.profile_url {
+            sqlx::query(
+                r#"UPDATE user_oauth SET subject = $1
+                WHERE provider = $2 AND subject = $3 AND profile_url = $3"#,
+            )
+            .bind(&identity.subject)
+            .bind(provider)
+            .bind(profile_url)
+            .execute(get_postgres())
+            .await
+            .map_err(|e| format!("{}", e))?;
+        }
+
+        sqlx::query_as::<_, Users>(
+            r#"SELECT u.* FROM user_oauth o JOIN users u ON u.id = o.user_id
+            WHERE o.provider = $1 AND o.subject = $2"#,
+        )
+        .bind(provider)
+        .bind(&identity.subject)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+
+    pub async fn link(
+        user_id: Uuid,
+        provider: &str,
+        identity: &OAuthIdentity,
+    ) -> Result<u64, String> {
+        sqlx::query(
+            r#"INSERT INTO user_oauth (provider, subject, user_id, profile_url)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING"#,
+        )
+        .bind(provider)
+        .bind(&identity.subject)
+        .bind(user_id)
+        .bind(&identity.profile_url)
+        .execute(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+        .and_then(|r| match r.rows_affected() {
+            0 => Err(format!(
+                "This {} account or another one is already linked",
+                provider
+            )),
+            n => Ok(n),
+        })
+    }
+
+    pub async fn unlink(user_id: Uuid, provider: &str) -> Result<u64, String> {
+        sqlx::query(r#"DELETE FROM user_oauth WHERE user_id = $1 AND provider = $2"#)
+            .bind(user_id)
+            .bind(provider)
+            .execute(get_postgres())
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| format!("{}", e))
+    }
+
+    /// Sign up a user with the account already linked, the provider verified the email
+    pub async fn sign_up(
+        provider: &str,
+        identity: &OAuthIdentity,
+        email: &str,
+        password: &str,
+    ) -> Result<UserInfo, String> {
+        sqlx::query_as::<_, UserInfo>(
+            r#"WITH new_user AS (
+                INSERT INTO users (account, password, salt, nickname, email, email_verified)
+                VALUES ($1, $2, '', $3, $4, true)
+                RETURNING id, account, nickname, role, say, email, email_verified, create_time
+            ), link AS (
+                INSERT INTO user_oauth (provider, subject, user_id, profile_url)
+                SELECT $5, $6, id, $7 FROM new_user
+            )
+            SELECT * FROM new_user"#,
+        )
+        .bind(&identity.account)
+        .bind(password)
+        .bind(&identity.nickname)
+        .bind(email)
+        .bind(provider)
+        .bind(&identity.subject)
+        .bind(&identity.profile_url)
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnlinkOAuth {
+    pub provider: String,
+}
@@ -0,0 +1,225 @@
+use crate::{
+    config::get_config,
+    db_wrapper::get_postgres,
+    models::role::Role,
+    utils::{random_string, sha3_256_encode, totp},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+const RECOVERY_CODES: usize = 10;
+
+/// What an authenticator app needs, returned once by the setup
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpSetup {
+    pub secret: String,
+    pub uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpStatus {
+    pub enabled: bool,
+    /// Admins can't turn it off
+    pub required: bool,
+    pub recovery_codes: i64,
+}
+
+/// A code from the authenticator app, or a recovery code
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TotpCode {
+    pub code: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct TotpSecret {
+    secret: String,
+    last_step: i64,
+}
+
+pub struct UserTotp;
+
+impl UserTotp {
+    /// Whether the user must use a second factor
+    pub fn required(role: Role) -> bool {
+        role == Role::Admin && get_config().server.require_admin_totp
+    }
+
+    pub async fn is_enabled(user_id: Uuid) -> bool {
+        sqlx::query_scalar::<_, bool>(r#"SELECT enabled FROM user_totp WHERE user_id = $1"#)
+            .bind(user_id)
+            .fetch_optional(get_postgres())
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+    }
+
+    pub async fn status(user_id: Uuid, role: Role) -> Result<TotpStatus, String> {
+        let recovery_codes = sqlx::query_scalar::<_, i64>(
+            r#"SELECT count(*) FROM user_recovery_code WHERE user_id = $1"#,
+        )
+        .bind(user_id)
+        .fetch_one(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        Ok(TotpStatus {
+            enabled: Self::is_enabled(user_id).await,
+            required: Self::required(role),
+            recovery_codes,
+        })
+    }
+
+    /// Start the enrollment with a new secret, it isn't used until `enable`
+    pub async fn setup(user_id: Uuid, account: &str) -> Result<TotpSetup, String> {
+        let secret = totp::generate_secret();
+        let res = sqlx::query(
+            r#"INSERT INTO user_totp (user_id, secret) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret = excluded.secret, last_step = 0, create_time = current_timestamp
+            WHERE user_totp.enabled = false"#,
+        )
+        .bind(user_id)
+        .bind(&secret)
+        .execute(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        if res.rows_affected() == 0 {
+            return Err("Two-factor authentication is already enabled".to_string());
+        }
+
+        let uri = totp::provisioning_uri(&secret, &get_config().site.title, account);
+        let qr_svg = totp::qr_code_svg(&uri)?;
+        Ok(TotpSetup {
+            secret,
+            uri,
+            qr_svg,
+        })
+    }
+
+    /// Finish the enrollment with a first code, returns the recovery codes
+    pub async fn enable(user_id: Uuid, code: &str) -> Result<Vec<String>, String> {
+        let data = sqlx::query_as::<_, TotpSecret>(
+            r#"SELECT secret, last_step FROM user_totp WHERE user_id = $1 AND enabled = false"#,
+        )
+        .bind(user_id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?
+        .ok_or_else(|| "Two-factor authentication is not set up".to_string())?;
+        let step = totp::verify(&data.secret, code, Utc::now().timestamp() as u64)
+            .ok_or_else(|| "Invalid code".to_string())?;
+
+        let res = sqlx::query(
+            r#"UPDATE user_totp SET enabled = true, last_step = $3
+            WHERE user_id = $1 AND secret = $2 AND enabled = false"#,
+        )
+        .bind(user_id)
+        .bind(&data.secret)
+        .bind(step as i64)
+        .execute(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        if res.rows_affected() == 0 {
+            return Err("Two-factor authentication is not set up".to_string());
+        }
+
+        Self::new_recovery_codes(user_id).await
+    }
+
+    /// Check a code of an enabled user, a recovery code is consumed
+    pub async fn check(user_id: Uuid, code: &str) -> Result<(), String> {
+        let data = sqlx::query_as::<_, TotpSecret>(
+            r#"SELECT secret, last_step FROM user_totp WHERE user_id = $1 AND enabled = true"#,
+        )
+        .bind(user_id)
+        .fetch_optional(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?
+        .ok_or_else(|| "Two-factor authentication is not enabled".to_string())?;
+
+        if let Some(step) = totp::verify(&data.secret, code, Utc::now().timestamp() as u64) {
+            // A code seen before may have been observed by someone else
+            if step as i64 <= data.last_step {
+                return Err("Code has already been used".to_string());
+            }
+            let res = sqlx::query(
+                r#"UPDATE user_totp SET last_step = $2 WHERE user_id = $1 AND last_step < $2"#,
+            )
+            .bind(user_id)
+            .bind(step as i64)
+            .execute(get_postgres())
+            .await
+            .map_err(|e| format!("{}", e))?;
+            return if res.rows_affected() == 1 {
+                Ok(())
+            } else {
+                Err("Code has already been used".to_string())
+            };
+        }
+
+        let res =
+            sqlx::query(r#"DELETE FROM user_recovery_code WHERE user_id = $1 AND code_hash = $2"#)
+                .bind(user_id)
+                .bind(hash_recovery_code(code))
+                .execute(get_postgres())
+                .await
+                .map_err(|e| format!("{}", e))?;
+        if res.rows_affected() == 1 {
+            Ok(())
+        } else {
+            Err("Invalid code".to_string())
+        }
+    }
+
+    /// Replace the recovery codes, the plain codes are only shown once
+    pub async fn new_recovery_codes(user_id: Uuid) -> Result<Vec<String>, String> {
+        let codes: Vec<String> = (0..RECOVERY_CODES)
+            .map(|_| {
+                let code = random_string(10).to_lowercase();
+                format!("{}-{}", &code[..5], &code[5..])
+            })
+            .collect();
+        let hashes: Vec<String> = codes.iter().map(|c| hash_recovery_code(c)).collect();
+
+        sqlx::query(
+            r#"WITH removed AS (
+                DELETE FROM user_recovery_code WHERE user_id = $1
+            )
+            INSERT INTO user_recovery_code (user_id, code_hash)
+            SELECT $1, unnest($2::varchar[])
+            ON CONFLICT DO NOTHING"#,
+        )
+        .bind(user_id)
+        .bind(&hashes)
+        .execute(get_postgres())
+        .await
+        .map_err(|e| format!("{}", e))?;
+        Ok(codes)
+    }
+
+    pub async fn disable(user_id: Uuid) -> Result<(), String> {
+        sqlx::query(
+            r#"WITH removed AS (
+                DELETE FROM user_recovery_code WHERE user_id = $1
+            )
+            DELETE FROM user_totp WHERE user_id = $1"#,
+        )
+        .bind(user_id)
+        .execute(get_postgres())
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("{}", e))
+    }
+}
+
+/// Recovery codes are random, a fast hash is enough
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    sha3_256_encode(normalized)
+}
@@ -0,0 +1,67 @@
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::header::ContentType,
+    transport::smtp::authentication::Credentials,
+};
+use std::sync::LazyLock;
+
+use crate::config::{SmtpConfig, SmtpSecurity, get_config};
+
+static MAILER: LazyLock<Option<Result<AsyncSmtpTransport<Tokio1Executor>, String>>> =
+    LazyLock::new(|| {
+        let smtp = &get_config().smtp;
+        smtp.enabled().then(|| transport(smtp))
+    });
+
+fn transport(smtp: &SmtpConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let mut builder = match smtp.security {
+        SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+            .map_err(|e| format!("{}", e))?,
+        SmtpSecurity::Tls => {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host).map_err(|e| format!("{}", e))?
+        }
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+    }
+    .port(smtp.port);
+    if !smtp.username.is_empty() {
+        builder = builder.credentials(Credentials::new(
+            smtp.username.clone(),
+            smtp.password.clone(),
+        ));
+    }
+    Ok(builder.build())
+}
+
+pub fn mail_enabled() -> bool {
+    get_config().smtp.enabled()
+}
+
+/// Send a plain text mail with the sender of `[smtp]`
+pub async fn send_mail(to: &str, subject: &str, body: String) -> Result<(), String> {
+    let mailer = match MAILER.as_ref() {
+        Some(Ok(mailer)) => mailer,
+        Some(Err(e)) => return Err(format!("can't connect to the mail server: {}", e)),
+        None => return Err("Mail is not configured".to_string()),
+    };
+
+    let message = Message::builder()
+        .from(
+            get_config()
+                .smtp
+                .from
+                .parse()
+                .map_err(|e| format!("{}", e))?,
+        )
+        .to(to
+            .parse()
+            .map_err(|e| format!("invalid email '{}': {}", to, e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| format!("{}", e))?;
+
+    mailer
+        .send(message)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("send mail error: {}", e))
+}
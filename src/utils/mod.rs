@@ -1,11 +1,14 @@
 use crate::{
-    COOKIE, USER_INFO,
+    API_TOKEN, COOKIE, USER_INFO,
+    config::get_config,
     db_wrapper::get_redis,
-    models::{notify::UserNotify, user::UserInfo},
+    models::{
+        api_token::ApiToken, notify::UserNotify, role::Role, session::Session, user::UserInfo,
+    },
     web::Cache,
 };
 use http_body_util::BodyExt;
-use pulldown_cmark::{Options, Parser, html};
+use pulldown_cmark::{Event, Options, Parser, html};
 use rand::Rng;
 use salvo::http::header;
 use salvo::{
@@ -18,13 +21,29 @@ use salvo::{
     routing::FlowCtrl,
 };
 use std::str::FromStr;
-use std::{fmt::Write, iter};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    iter,
+    net::IpAddr,
+    sync::LazyLock,
+};
 use tiny_keccak::Hasher;
 
-pub mod github_information;
+pub mod mail;
+pub mod oauth;
+pub mod password;
+pub mod signed_link;
+pub mod spam;
+pub mod totp;
 
 const COOKIE_NAME: &str = "blog_session";
 
+/// Public url of the site without the trailing `/`
+pub fn site_url() -> &'static str {
+    &get_config().site.url
+}
+
 #[inline]
 pub fn markdown_render(src: &str) -> String {
     let options = Options::all();
@@ -35,6 +54,127 @@ pub fn markdown_render(src: &str) -> String {
     html_output
 }
 
+/// Tags kept in comments, anything else is dropped with its text kept
+const COMMENT_TAGS: [&str; 13] = [
+    "a",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "strong",
+    "ul",
+];
+
+static COMMENT_SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut builder = ammonia::Builder::empty();
+    builder
+        .tags(HashSet::from(COMMENT_TAGS))
+        .generic_attributes(HashSet::new())
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href"])),
+            ("code", HashSet::from(["class"])),
+        ]))
+        // Only the language of a code block, used for the highlighting
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("code", "class") if !value.starts_with("language-") => None,
+            _ => Some(value.into()),
+        })
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .url_relative(ammonia::UrlRelative::Deny)
+        .link_rel(Some("nofollow ugc"));
+    builder
+});
+
+/// Clean the html of a comment, keeps the tags `comment_markdown_render` can produce
+pub fn sanitize_comment_html(src: &str) -> String {
+    COMMENT_SANITIZER.clean(src).to_string()
+}
+
+/// Render the markdown of a comment, raw html is shown as text
+pub fn comment_markdown_render(src: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH;
+    let parser = Parser::new_ext(src, options).map(|event| match event {
+        Event::Html(text) | Event::InlineHtml(text) => Event::Text(text),
+        event => event,
+    });
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, parser);
+
+    sanitize_comment_html(&html_output)
+}
+
+/// Markers handed to `ts_headline`, private use code points never appear in articles
+pub const HIGHLIGHT_START: char = '\u{E000}';
+pub const HIGHLIGHT_STOP: char = '\u{E001}';
+
+/// Escape a `ts_headline` result as html and turn the markers into `<mark>` tags
+pub fn highlight_to_html(src: &str) -> String {
+    let mut html = String::with_capacity(src.len() + 16);
+    for c in src.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+/// Line based unified diff with three lines of context
+pub fn unified_diff(old: &str, new: &str, old_header: &str, new_header: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_header, new_header)
+        .to_string()
+}
+
+const SLUG_MAX_LEN: usize = 80;
+
+/// Turn a title into a url slug, CJK and other scripts are transliterated to ascii
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in deunicode::deunicode(title).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    // slug is pure ascii here, truncating on any byte is safe
+    slug.truncate(SLUG_MAX_LEN);
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "article".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// A manual slug must look like a generated one and must not be taken for an article id
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= SLUG_MAX_LEN
+        && slug
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+        && slug.parse::<uuid::Uuid>().is_err()
+}
+
 #[inline]
 pub fn random_string(limit: usize) -> String {
     iter::repeat(())
@@ -57,6 +197,31 @@ pub fn sha3_256_encode(s: String) -> String {
     hex
 }
 
+/// Sha256 of the trimmed and lowercased email, the hash Gravatar looks avatars up with
+pub fn gravatar_hash(email: &str) -> String {
+    use sha2::Digest;
+    let res = sha2::Sha256::digest(email.trim().to_lowercase().as_bytes());
+    let mut hex = String::with_capacity(64);
+    for byte in res.iter() {
+        write!(hex, "{:02x}", byte).expect("Can't fail on writing to string");
+    }
+    hex
+}
+
+/// Leading zero bits of `sha256(challenge ‖ nonce)`, the nonce written in decimal
+pub fn proof_of_work_bits(challenge: &str, nonce: u64) -> u32 {
+    use sha2::Digest;
+    let res = sha2::Sha256::digest(format!("{}{}", challenge, nonce).as_bytes());
+    let mut bits = 0;
+    for byte in res.iter() {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
 /// Extract real password from frontend input
 /// Frontend adds 6 random characters as prefix for obfuscation
 /// Returns None if password length is less than 6 characters
@@ -69,34 +234,83 @@ pub fn get_password(raw: &str) -> Option<String> {
     Some(password.to_string())
 }
 
-/// Get visitor's permission and user info
-/// `0` means Admin
-/// `1` means User
+/// Get visitor's role and user info, `None` for anonymous visitors
 pub async fn get_identity_and_web_context(
     req: &Request,
     depot: &mut Depot,
-) -> (Option<i16>, tera::Context) {
+) -> (Option<Role>, tera::Context) {
     let mut web = tera::Context::new();
+    // Replaced below for a signed in user
+    web.insert("permissions", &[] as &[&str]);
     let redis_pool = get_redis();
 
     match req.cookies().get(COOKIE_NAME) {
         Some(v) => match redis_pool.hget::<Option<String>>(v.value(), "info").await {
             Ok(Some(info)) => {
                 let info = serde_json::from_str::<UserInfo>(&info).unwrap();
+                // Disabling, demoting or deleting the user wouldn't reach it
+                if !Session::is_indexed(info.id, v.value()).await {
+                    redis_pool.del(v.value()).await;
+                    return (None, web);
+                }
                 let notifys = UserNotify::get_notifys(info.id).await;
                 web.insert("user", &info);
                 web.insert("notifys", &notifys.unwrap_or_default());
-                let groups = info.groups;
+                web.insert("permissions", info.role.permissions());
+                let role = info.role;
+                Session::touch(
+                    v.value(),
+                    client_ip(req),
+                    req.header::<String>(header::USER_AGENT),
+                )
+                .await;
 
                 depot.insert(USER_INFO, info);
                 depot.insert(COOKIE, v.value().to_owned());
 
-                (Some(groups), web)
+                (Some(role), web)
             }
             _ => (None, web),
         },
-        None => (None, web),
+        None => match bearer_token(req) {
+            Some(token) => match ApiToken::authenticate(token, client_ip(req)).await {
+                Some((info, scopes)) => {
+                    let role = info.role;
+                    depot.insert(USER_INFO, info);
+                    depot.insert(API_TOKEN, scopes);
+                    (Some(role), web)
+                }
+                None => (None, web),
+            },
+            None => (None, web),
+        },
+    }
+}
+
+/// Tokens are only accepted by the json api, pages stay behind the cookie
+fn bearer_token(req: &Request) -> Option<&str> {
+    if !req.uri().path().starts_with("/api/") {
+        return None;
     }
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Address of the client, `X-Real-IP` is only used when it was set by a trusted proxy
+pub fn client_ip(req: &Request) -> Option<String> {
+    let remote = req.remote_addr().ip()?;
+    if get_config().server.is_trusted_proxy(remote)
+        && let Some(ip) = req
+            .header::<String>("X-Real-IP")
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok())
+    {
+        return Some(ip.to_string());
+    }
+    Some(remote.to_string())
 }
 
 pub fn set_cookie(
@@ -236,7 +450,8 @@ pub async fn visitor_log(
     ctrl: &mut FlowCtrl,
 ) {
     if let Some(ip) = req.header::<String>("X-Real-IP")
-        && let Ok(key) = ::std::env::var("IPSTACK_KEY")
+        && let key = &get_config().ipstack.key
+        && !key.is_empty()
     {
         let timestamp = chrono::Utc::now();
         tokio::spawn(async move {
@@ -278,7 +493,11 @@ pub async fn visitor_log(
 
 #[cfg(test)]
 mod test {
-    use super::{parse_last_path, parse_query};
+    use super::{
+        HIGHLIGHT_START, HIGHLIGHT_STOP, comment_markdown_render, gravatar_hash, highlight_to_html,
+        is_valid_slug, parse_last_path, parse_query, proof_of_work_bits, sanitize_comment_html,
+        slugify, unified_diff,
+    };
     use salvo::Request;
 
     fn build_request(uri: &str) -> Request {
@@ -319,4 +538,105 @@ mod test {
         let v = parse_last_path::<String>(&res).unwrap();
         assert_eq!(v, "d");
     }
+
+    #[test]
+    fn test_highlight_to_html() {
+        let raw = format!(
+            "<b>{}rust{} & {}salvo{}</b>",
+            HIGHLIGHT_START, HIGHLIGHT_STOP, HIGHLIGHT_START, HIGHLIGHT_STOP
+        );
+        assert_eq!(
+            highlight_to_html(&raw),
+            "&lt;b&gt;<mark>rust</mark> &amp; <mark>salvo</mark>&lt;/b&gt;"
+        );
+        assert_eq!(highlight_to_html("plain"), "plain");
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("a\nb\nc\n", "a\nB\nc\n", "old", "new");
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+
+        assert_eq!(unified_diff("same\n", "same\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Rust 2024 edition  "), "rust-2024-edition");
+        assert_eq!(slugify("北京"), "bei-jing");
+        assert_eq!(slugify("???"), "article");
+        assert!(slugify(&"a ".repeat(100)).len() <= 80);
+        assert!(!slugify(&"a ".repeat(100)).ends_with('-'));
+    }
+
+    #[test]
+    fn test_is_valid_slug() {
+        assert!(is_valid_slug("hello-world"));
+        assert!(is_valid_slug("rust-2024"));
+        assert!(!is_valid_slug(""));
+        assert!(!is_valid_slug("Hello"));
+        assert!(!is_valid_slug("-hello"));
+        assert!(!is_valid_slug("hello/world"));
+        assert!(!is_valid_slug("00000000-0000-0000-0000-000000000000"));
+    }
+
+    #[test]
+    fn test_comment_markdown_render() {
+        assert_eq!(
+            comment_markdown_render("**bold** ~~gone~~ `code`"),
+            "<p><strong>bold</strong> <del>gone</del> <code>code</code></p>\n"
+        );
+        assert_eq!(
+            comment_markdown_render("```rust\nfn main() {}\n```"),
+            "<pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n"
+        );
+        assert_eq!(
+            comment_markdown_render("[site](https://example.com)"),
+            "<p><a href=\"https://example.com\" rel=\"nofollow ugc\">site</a></p>\n"
+        );
+        // Raw html is escaped, unsafe links and images are dropped
+        assert_eq!(
+            comment_markdown_render("<script>alert(1)</script>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+        assert_eq!(
+            comment_markdown_render("[x](javascript:alert(1)) ![img](https://a.com/a.png)"),
+            "<p><a rel=\"nofollow ugc\">x</a> </p>\n"
+        );
+    }
+
+    #[test]
+    fn test_gravatar_hash() {
+        // Example of the Gravatar documentation
+        assert_eq!(
+            gravatar_hash(" MyEmailAddress@example.com "),
+            "84059b07d4be67b806386c0aad8070a23f18836bbaae342275dc0a83414c32ee"
+        );
+    }
+
+    #[test]
+    fn test_proof_of_work_bits() {
+        use sha2::Digest;
+        // sha256("abc0") starts with 0x56
+        assert_eq!(proof_of_work_bits("abc", 0), 1);
+        let nonce = (0..).find(|n| proof_of_work_bits("abc", *n) >= 12).unwrap();
+        let res = sha2::Sha256::digest(format!("abc{}", nonce).as_bytes());
+        assert_eq!(res[0], 0);
+        assert!(res[1] < 0x10);
+    }
+
+    #[test]
+    fn test_sanitize_comment_html() {
+        assert_eq!(
+            sanitize_comment_html(
+                r#"<p onclick="x()">hi<img src=x onerror=y><a href="/a" target="_blank">a</a></p>"#
+            ),
+            r#"<p>hi<a rel="nofollow ugc">a</a></p>"#
+        );
+        assert_eq!(
+            sanitize_comment_html(r#"<code class="hljs">x</code>"#),
+            "<code>x</code>"
+        );
+    }
 }
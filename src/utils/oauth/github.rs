@@ -1,7 +1,68 @@
+use super::{OAuthIdentity, OAuthProvider};
+use crate::config::{GithubConfig, get_config};
+
 /// GitHub API version header value
 const GITHUB_API_VERSION: &str = "2022-11-28";
 
-pub async fn get_github_token(code: &str) -> Result<String, String> {
+pub struct GithubProvider {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+}
+
+impl GithubProvider {
+    pub fn new(config: &GithubConfig) -> Self {
+        GithubProvider {
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            // The callback registered before the provider layer existed
+            redirect_uri: config
+                .redirect_uri
+                .clone()
+                .unwrap_or_else(|| format!("{}/api/v1/login_with_github", get_config().site.url)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthProvider for GithubProvider {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn display_name(&self) -> &str {
+        "GitHub"
+    }
+
+    async fn authorize_url(&self, state: &str) -> Result<String, String> {
+        url::Url::parse_with_params(
+            "https://github.com/login/oauth/authorize",
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("scope", "user:email"),
+                ("state", state),
+            ],
+        )
+        .map(|url| url.to_string())
+        .map_err(|e| format!("{}", e))
+    }
+
+    async fn identity(&self, code: &str) -> Result<OAuthIdentity, String> {
+        let token = get_github_token(self, code).await?;
+        let (subject, account, nickname, profile_url) = get_github_account(&token).await?;
+        let email = get_github_verified_email(&token).await?;
+        Ok(OAuthIdentity {
+            subject,
+            account,
+            nickname,
+            email,
+            profile_url: Some(profile_url),
+        })
+    }
+}
+
+async fn get_github_token(provider: &GithubProvider, code: &str) -> Result<String, String> {
     let client = reqwest::Client::new();
 
     #[derive(serde::Serialize)]
@@ -9,12 +70,14 @@ pub async fn get_github_token(code: &str) -> Result<String, String> {
         client_id: &'a str,
         client_secret: &'a str,
         code: &'a str,
+        redirect_uri: &'a str,
     }
 
     let request_body = TokenRequest {
-        client_id: "52b10cd3fff369999cd9",
-        client_secret: "212d9729ead001da8844c1dcc79d45240166bd4f",
+        client_id: &provider.client_id,
+        client_secret: &provider.client_secret,
         code,
+        redirect_uri: &provider.redirect_uri,
     };
 
     let mut headers = reqwest::header::HeaderMap::new();
@@ -48,9 +111,8 @@ pub async fn get_github_token(code: &str) -> Result<String, String> {
         .ok_or_else(|| String::from("No access_token in response"))
 }
 
-pub async fn get_github_account_nickname_address(
-    raw_token: &str,
-) -> Result<(String, String, String), String> {
+/// Id, login, name and profile url of the account
+async fn get_github_account(raw_token: &str) -> Result<(String, String, String, String), String> {
     let mut headers = reqwest::header::HeaderMap::new();
     // Use Bearer token authentication (required by current GitHub API)
     headers.append(
@@ -82,6 +144,12 @@ pub async fn get_github_account_nickname_address(
         .await
         .map_err(|e| format!("read body error: '{}'", e))?;
 
+    // id never changes, unlike login
+    let subject = res["id"]
+        .as_u64()
+        .ok_or_else(|| "Missing 'id' field in GitHub response".to_string())?
+        .to_string();
+
     // login is required field
     let account = res["login"]
         .as_str()
@@ -100,10 +168,11 @@ pub async fn get_github_account_nickname_address(
         .ok_or_else(|| "Missing 'html_url' field in GitHub response".to_string())?
         .to_string();
 
-    Ok((account, nickname, github_address))
+    Ok((subject, account, nickname, github_address))
 }
 
-pub async fn get_github_primary_email(raw_token: &str) -> Result<String, String> {
+/// Primary email of the account if it is verified, otherwise any verified email
+async fn get_github_verified_email(raw_token: &str) -> Result<Option<String>, String> {
     let mut headers = reqwest::header::HeaderMap::new();
     // Use Bearer token authentication (required by current GitHub API)
     headers.append(
@@ -135,30 +204,22 @@ pub async fn get_github_primary_email(raw_token: &str) -> Result<String, String>
         .await
         .map_err(|e| format!("read body error: '{}'", e))?;
 
-    // First try to find primary email
-    if let Some(primary_email) = emails
+    let verified = emails
         .iter()
+        .filter(|x| x["verified"].as_bool().unwrap_or(false));
+
+    // First try to find primary email
+    if let Some(primary_email) = verified
+        .clone()
         .filter(|x| x["primary"].as_bool().unwrap_or(false))
         .filter_map(|x| x["email"].as_str())
         .next()
     {
-        return Ok(primary_email.to_string());
+        return Ok(Some(primary_email.to_string()));
     }
 
-    // Fallback: return the first verified email
-    if let Some(verified_email) = emails
-        .iter()
-        .filter(|x| x["verified"].as_bool().unwrap_or(false))
+    Ok(verified
         .filter_map(|x| x["email"].as_str())
         .next()
-    {
-        return Ok(verified_email.to_string());
-    }
-
-    // Last resort: return any email
-    emails
-        .first()
-        .and_then(|x| x["email"].as_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| "No email found in GitHub account".to_string())
+        .map(|s| s.to_string()))
 }
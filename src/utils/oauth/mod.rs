@@ -0,0 +1,83 @@
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::config::get_config;
+
+mod github;
+mod oidc;
+
+pub use github::GithubProvider;
+pub use oidc::OidcProvider;
+
+/// The account of a user on an oauth provider
+#[derive(Debug, Clone)]
+pub struct OAuthIdentity {
+    /// Stable id of the account on the provider
+    pub subject: String,
+    pub account: String,
+    pub nickname: String,
+    /// Only set when the provider has verified it, used to link an existing user
+    pub email: Option<String>,
+    pub profile_url: Option<String>,
+}
+
+#[async_trait::async_trait]
+pub trait OAuthProvider: Send + Sync {
+    /// Name used in urls and stored with the linked accounts
+    fn name(&self) -> &str;
+
+    /// Name shown on the login page
+    fn display_name(&self) -> &str;
+
+    /// Where the browser is sent to authorize, `state` is given back to the callback
+    async fn authorize_url(&self, state: &str) -> Result<String, String>;
+
+    /// Exchange the code given to the callback for the account of the user
+    async fn identity(&self, code: &str) -> Result<OAuthIdentity, String>;
+}
+
+/// A provider shown on the login page
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInfo {
+    pub name: String,
+    pub display_name: String,
+}
+
+static PROVIDERS: LazyLock<Vec<Box<dyn OAuthProvider>>> = LazyLock::new(|| {
+    let config = get_config();
+    let mut providers: Vec<Box<dyn OAuthProvider>> = Vec::new();
+    if config.github.enabled() {
+        providers.push(Box::new(GithubProvider::new(&config.github)));
+    }
+    for oidc in &config.oidc {
+        providers.push(Box::new(OidcProvider::new(oidc)));
+    }
+    providers
+});
+
+/// Providers configured in `[github]` and `[[oidc]]`
+pub fn providers() -> &'static [Box<dyn OAuthProvider>] {
+    &PROVIDERS
+}
+
+pub fn find_provider(name: &str) -> Option<&'static dyn OAuthProvider> {
+    providers()
+        .iter()
+        .find(|provider| provider.name() == name)
+        .map(|provider| provider.as_ref())
+}
+
+pub fn provider_infos() -> Vec<ProviderInfo> {
+    providers()
+        .iter()
+        .map(|provider| ProviderInfo {
+            name: provider.name().to_string(),
+            display_name: provider.display_name().to_string(),
+        })
+        .collect()
+}
+
+/// Default callback of a provider
+fn callback_url(name: &str) -> String {
+    format!("{}/api/v1/oauth/{}/callback", get_config().site.url, name)
+}
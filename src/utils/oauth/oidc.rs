@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use super::{OAuthIdentity, OAuthProvider, callback_url};
+use crate::config::OidcConfig;
+
+/// The part of `/.well-known/openid-configuration` used by the login
+#[derive(Deserialize, Debug)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    name: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    profile: Option<String>,
+}
+
+pub struct OidcProvider {
+    name: String,
+    display_name: String,
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    scopes: String,
+    redirect_uri: String,
+    discovery: OnceCell<Discovery>,
+}
+
+impl OidcProvider {
+    pub fn new(config: &OidcConfig) -> Self {
+        OidcProvider {
+            name: config.name.clone(),
+            display_name: config
+                .display_name
+                .clone()
+                .unwrap_or_else(|| config.name.clone()),
+            issuer: config.issuer.trim_end_matches('/').to_string(),
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            scopes: config.scopes.clone(),
+            redirect_uri: config
+                .redirect_uri
+                .clone()
+                .unwrap_or_else(|| callback_url(&config.name)),
+            discovery: OnceCell::new(),
+        }
+    }
+
+    /// Fetched on first use, a failed fetch is retried on the next login
+    async fn discovery(&self) -> Result<&Discovery, String> {
+        self.discovery
+            .get_or_try_init(|| async {
+                reqwest::get(format!("{}/.well-known/openid-configuration", self.issuer))
+                    .await
+                    .map_err(|e| format!("reqwest's io error: '{}'", e))?
+                    .error_for_status()
+                    .map_err(|e| format!("{} discovery error: {}", self.name, e))?
+                    .json::<Discovery>()
+                    .await
+                    .map_err(|e| format!("read body err: {}", e))
+            })
+            .await
+    }
+
+    async fn token(&self, code: &str) -> Result<String, String> {
+        let discovery = self.discovery().await?;
+        let body = serde_urlencoded::to_string([
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ])
+        .map_err(|e| format!("{}", e))?;
+
+        let res = reqwest::Client::new()
+            .post(&discovery.token_endpoint)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("reqwest's io error: '{}'", e))?
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| format!("read body err: {}", e))?;
+
+        if let Some(error) = res.get("error").and_then(|e| e.as_str()) {
+            let description = res
+                .get("error_description")
+                .and_then(|d| d.as_str())
+                .unwrap_or("Unknown error");
+            return Err(format!(
+                "{} OAuth error: {} - {}",
+                self.name, error, description
+            ));
+        }
+
+        res.get("access_token")
+            .and_then(|t| t.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| String::from("No access_token in response"))
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthProvider for OidcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    async fn authorize_url(&self, state: &str) -> Result<String, String> {
+        let discovery = self.discovery().await?;
+        url::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("scope", self.scopes.as_str()),
+                ("state", state),
+            ],
+        )
+        .map(|url| url.to_string())
+        .map_err(|e| format!("{}", e))
+    }
+
+    async fn identity(&self, code: &str) -> Result<OAuthIdentity, String> {
+        let token = self.token(code).await?;
+        let discovery = self.discovery().await?;
+        let info = reqwest::Client::new()
+            .get(&discovery.userinfo_endpoint)
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("reqwest's io error: '{}'", e))?
+            .error_for_status()
+            .map_err(|e| format!("{} userinfo error: {}", self.name, e))?
+            .json::<UserInfo>()
+            .await
+            .map_err(|e| format!("read body err: {}", e))?;
+
+        let account = info
+            .preferred_username
+            .clone()
+            .unwrap_or_else(|| info.sub.clone());
+        Ok(OAuthIdentity {
+            nickname: info.name.unwrap_or_else(|| account.clone()),
+            account,
+            email: info.email.filter(|_| info.email_verified),
+            profile_url: info.profile,
+            subject: info.sub,
+        })
+    }
+}
@@ -0,0 +1,52 @@
+//! Hashes stored in `users.password`, the prefix tells the format:
+//!
+//! - `$argon2id$...`: PHC string, the salt is part of it
+//! - `$sha3-256${hex}`: legacy `sha3_256(password + users.salt)`, replaced on the next login
+
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+
+use super::sha3_256_encode;
+
+const ARGON2ID_PREFIX: &str = "$argon2id$";
+const LEGACY_SHA3_PREFIX: &str = "$sha3-256$";
+
+/// Hash with Argon2id, runs on the blocking pool as it takes tens of milliseconds
+pub async fn hash_password(password: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let salt =
+            SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| format!("{}", e))?;
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| format!("{}", e))
+    })
+    .await
+    .map_err(|e| format!("{}", e))?
+}
+
+/// `salt` is only used by legacy hashes
+pub async fn verify_password(password: String, hash: String, salt: String) -> bool {
+    if let Some(hex) = hash.strip_prefix(LEGACY_SHA3_PREFIX) {
+        return hex == sha3_256_encode(password + &salt);
+    }
+    if !hash.starts_with(ARGON2ID_PREFIX) {
+        return false;
+    }
+
+    tokio::task::spawn_blocking(move || match PasswordHash::new(&hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Whether the hash should be replaced after a successful login
+pub fn needs_rehash(hash: &str) -> bool {
+    !hash.starts_with(ARGON2ID_PREFIX)
+}
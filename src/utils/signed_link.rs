@@ -0,0 +1,45 @@
+//! Links that carry their own proof, e.g. `?id=..&expires=..&sig=..`, instead of a token on redis
+
+use std::{fmt::Write, sync::LazyLock};
+use tiny_keccak::{Hasher, Kmac};
+
+use super::random_string;
+use crate::config::get_config;
+
+static SECRET_KEY: LazyLock<String> = LazyLock::new(|| {
+    let key = &get_config().server.secret_key;
+    if key.is_empty() {
+        tracing::warn!("server.secret_key is not set, signed links are invalid after a restart");
+        random_string(64)
+    } else {
+        key.clone()
+    }
+});
+
+/// KMAC256 of the parts, hex encoded
+pub fn sign(parts: &[&str]) -> String {
+    let mut kmac = Kmac::v256(SECRET_KEY.as_bytes(), b"signed link");
+    for part in parts {
+        // Length prefixed, so that moving bytes between parts changes the signature
+        kmac.update(&(part.len() as u64).to_be_bytes());
+        kmac.update(part.as_bytes());
+    }
+    let mut res = [0u8; 32];
+    kmac.finalize(&mut res);
+    let mut hex = String::with_capacity(64);
+    for byte in res.iter() {
+        write!(hex, "{:02x}", byte).expect("Can't fail on writing to string");
+    }
+    hex
+}
+
+/// Compare in constant time
+pub fn verify(parts: &[&str], signature: &str) -> bool {
+    let expected = sign(parts);
+    expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+
+const MIN_WORD: usize = 2;
+const MAX_WORD: usize = 30;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}')
+}
+
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => (),
+        }
+    }
+    out.replace("&nbsp;", " ")
+}
+
+fn push_cjk(run: &[char], tokens: &mut BTreeSet<String>) {
+    match run.len() {
+        0 => (),
+        1 => {
+            tokens.insert(run[0].to_string());
+        }
+        _ => {
+            for pair in run.windows(2) {
+                tokens.insert(pair.iter().collect());
+            }
+        }
+    }
+}
+
+/// Distinct tokens of a comment: lowercase words, and the bigrams of CJK runs
+pub fn tokenize(text: &str) -> Vec<String> {
+    let text = strip_tags(text).to_lowercase();
+    let mut tokens = BTreeSet::new();
+    let mut word = String::new();
+    let mut run = Vec::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if is_cjk(c) {
+            run.push(c);
+        } else {
+            push_cjk(&run, &mut tokens);
+            run.clear();
+        }
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.push(c);
+        } else {
+            let len = word.chars().count();
+            if (MIN_WORD..=MAX_WORD).contains(&len) {
+                tokens.insert(std::mem::take(&mut word));
+            }
+            word.clear();
+        }
+    }
+    tokens.into_iter().collect()
+}
+
+/// Naive Bayes probability that a comment is spam, `counts` are the spam and ham
+/// comments each known token of the comment was seen in
+pub fn spam_probability(counts: &[(i64, i64)], spam_docs: i64, ham_docs: i64) -> f64 {
+    let (spam_docs, ham_docs) = (spam_docs.max(1) as f64, ham_docs.max(1) as f64);
+    let log_odds = counts.iter().filter(|(spam, ham)| spam + ham > 0).fold(
+        (spam_docs / ham_docs).ln(),
+        |acc, &(spam, ham)| {
+            let p_spam = (spam as f64 + 1.0) / (spam_docs + 2.0);
+            let p_ham = (ham as f64 + 1.0) / (ham_docs + 2.0);
+            acc + (p_spam / p_ham).ln()
+        },
+    );
+    1.0 / (1.0 + (-log_odds).exp())
+}
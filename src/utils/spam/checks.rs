@@ -0,0 +1,193 @@
+use regex::Regex;
+use std::{collections::HashSet, sync::LazyLock};
+
+use super::{CommentDraft, SpamCheck, Verdict};
+use crate::{
+    db_wrapper::{get_postgres, get_redis},
+    models::spam::{SpamAction, SpamBayes, SpamRule},
+};
+
+/// Counters are kept for a minute after the first comment
+const RATE_WINDOW: i64 = 60;
+
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s"'<>]+"#).unwrap());
+
+/// Distinct links in a comment
+pub fn count_links(text: &str) -> usize {
+    LINK.find_iter(text)
+        .map(|m| m.as_str().to_lowercase())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Hold the comments with too many links
+pub struct LinkLimit {
+    max: usize,
+}
+
+impl LinkLimit {
+    pub fn new(max: usize) -> Self {
+        LinkLimit { max }
+    }
+}
+
+#[async_trait::async_trait]
+impl SpamCheck for LinkLimit {
+    fn name(&self) -> &str {
+        "links"
+    }
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict {
+        let links = count_links(draft.text);
+        if links > self.max {
+            Verdict::Moderate(format!("{} links", links))
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+/// The banned words and patterns managed by the moderators
+pub struct Rules;
+
+#[async_trait::async_trait]
+impl SpamCheck for Rules {
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict {
+        let rules = match SpamRule::list().await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::info!("can't get spam rules with {}", e);
+                return Verdict::Pass;
+            }
+        };
+        let mut verdict = Verdict::Pass;
+        for rule in rules.iter().filter(|rule| rule.matches(draft.text)) {
+            match rule.action {
+                SpamAction::Reject => return Verdict::Reject("Comment rejected".to_string()),
+                SpamAction::Moderate => {
+                    verdict = Verdict::Moderate(format!("rule {}", rule.pattern))
+                }
+            }
+        }
+        verdict
+    }
+}
+
+/// Comments per minute of a user and of an ip
+pub struct RateLimit {
+    user_limit: i64,
+    ip_limit: i64,
+}
+
+impl RateLimit {
+    pub fn new(user_limit: i64, ip_limit: i64) -> Self {
+        RateLimit {
+            user_limit,
+            ip_limit,
+        }
+    }
+
+    async fn hit(kind: &str, key: &str) -> i64 {
+        let redis_key = format!("comment_rate_{}_{}", kind, key);
+        let count = get_redis().hincrby(&redis_key, "count", 1).await;
+        if count == 1 {
+            get_redis().expire(&redis_key, RATE_WINDOW).await;
+        }
+        count
+    }
+}
+
+#[async_trait::async_trait]
+impl SpamCheck for RateLimit {
+    fn name(&self) -> &str {
+        "rate"
+    }
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict {
+        // An edit adds no comment
+        if draft.comment_id.is_some() {
+            return Verdict::Pass;
+        }
+        let user_over = match draft.user_id {
+            Some(user_id) => Self::hit("user", &user_id.to_string()).await > self.user_limit,
+            None => false,
+        };
+        let ip_over = match draft.ip {
+            Some(ip) => Self::hit("ip", ip).await > self.ip_limit,
+            None => false,
+        };
+        if user_over || ip_over {
+            Verdict::Reject("Too many comments, try again later".to_string())
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+/// The same text posted again by its author in a day, or by anyone on the same article
+pub struct Duplicate;
+
+#[async_trait::async_trait]
+impl SpamCheck for Duplicate {
+    fn name(&self) -> &str {
+        "duplicate"
+    }
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict {
+        let duplicate = sqlx::query_scalar::<_, bool>(
+            r#"SELECT EXISTS (
+                SELECT 1 FROM comments
+                WHERE deleted = false AND lower(trim(comment)) = lower(trim($1))
+                AND (article_id = $2 OR (user_id = $3 AND create_time > now() - interval '1 day'))
+                AND id IS DISTINCT FROM $4
+            )"#,
+        )
+        .bind(draft.text)
+        .bind(draft.article_id)
+        .bind(draft.user_id)
+        .bind(draft.comment_id)
+        .fetch_one(get_postgres())
+        .await
+        .unwrap_or(false);
+        if duplicate {
+            Verdict::Reject("Duplicate comment".to_string())
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+/// The classifier trained from the decisions of the moderators
+pub struct Bayes {
+    threshold: f64,
+}
+
+impl Bayes {
+    pub fn new(threshold: f64) -> Self {
+        Bayes { threshold }
+    }
+}
+
+#[async_trait::async_trait]
+impl SpamCheck for Bayes {
+    fn name(&self) -> &str {
+        "bayes"
+    }
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict {
+        match SpamBayes::probability(draft.text).await {
+            Ok(Some(p)) if p >= self.threshold => {
+                Verdict::Moderate(format!("spam probability {:.2}", p))
+            }
+            Ok(_) => Verdict::Pass,
+            Err(e) => {
+                tracing::info!("can't classify comment with {}", e);
+                Verdict::Pass
+            }
+        }
+    }
+}
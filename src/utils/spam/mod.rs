@@ -0,0 +1,73 @@
+//! Every new comment goes through the checks below, the first rejection wins,
+//! otherwise any check may hold the comment for a moderator.
+
+use sqlx::types::Uuid;
+use std::sync::LazyLock;
+
+use crate::config::get_config;
+
+mod bayes;
+mod checks;
+
+pub use bayes::{spam_probability, tokenize};
+pub use checks::{Bayes, Duplicate, LinkLimit, RateLimit, Rules, count_links};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    /// Held for a moderator, with the reason
+    Moderate(String),
+    /// Refused, the reason is returned to the user
+    Reject(String),
+}
+
+/// A comment about to be inserted or edited
+#[derive(Debug, Clone, Copy)]
+pub struct CommentDraft<'a> {
+    pub text: &'a str,
+    pub article_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub ip: Option<&'a str>,
+    /// Set when editing, the comment is not a duplicate of itself
+    pub comment_id: Option<Uuid>,
+}
+
+#[async_trait::async_trait]
+pub trait SpamCheck: Send + Sync {
+    /// Name logged with the verdict
+    fn name(&self) -> &str;
+
+    async fn check(&self, draft: &CommentDraft<'_>) -> Verdict;
+}
+
+static CHECKS: LazyLock<Vec<Box<dyn SpamCheck>>> = LazyLock::new(|| {
+    let config = &get_config().comment;
+    vec![
+        Box::new(RateLimit::new(config.user_rate_limit, config.ip_rate_limit)),
+        Box::new(Duplicate),
+        Box::new(Rules),
+        Box::new(LinkLimit::new(config.max_links)),
+        Box::new(Bayes::new(config.spam_threshold)),
+    ]
+});
+
+/// Run all the checks configured in `[comment]`
+pub async fn check(draft: &CommentDraft<'_>) -> Verdict {
+    let mut verdict = Verdict::Pass;
+    for check in CHECKS.iter() {
+        match check.check(draft).await {
+            Verdict::Pass => (),
+            Verdict::Reject(reason) => {
+                tracing::info!("comment rejected by {}: {}", check.name(), reason);
+                return Verdict::Reject(reason);
+            }
+            Verdict::Moderate(reason) => {
+                tracing::info!("comment held by {}: {}", check.name(), reason);
+                if verdict == Verdict::Pass {
+                    verdict = Verdict::Moderate(reason);
+                }
+            }
+        }
+    }
+    verdict
+}
@@ -0,0 +1,118 @@
+//! Time-based one-time passwords (RFC 6238) as used by authenticator apps:
+//! HMAC-SHA1, 30 seconds steps and 6 digits
+
+use hmac::{Hmac, Mac};
+use qrcode::{QrCode, render::svg};
+use sha1::Sha1;
+
+const STEP: u64 = 30;
+const DIGITS: u32 = 6;
+/// Steps accepted before and after the current one, for clock drift
+const SKEW: u64 = 1;
+const SECRET_LEN: usize = 20;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A new random secret, base32 encoded without padding
+pub fn generate_secret() -> String {
+    base32_encode(&rand::random::<[u8; SECRET_LEN]>())
+}
+
+/// The `otpauth://` uri read by authenticator apps
+pub fn provisioning_uri(secret: &str, issuer: &str, account: &str) -> String {
+    let label = format!("{}:{}", issuer, account);
+    let mut url = url::Url::parse("otpauth://totp/").expect("static url");
+    url.path_segments_mut()
+        .expect("otpauth url has a path")
+        .pop_if_empty()
+        .push(&label);
+    url.query_pairs_mut()
+        .append_pair("secret", secret)
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &STEP.to_string());
+    url.to_string()
+}
+
+/// The uri as a svg QR code
+pub fn qr_code_svg(uri: &str) -> Result<String, String> {
+    let code = QrCode::new(uri.as_bytes()).map_err(|e| format!("{}", e))?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+/// The code of the step
+pub fn generate(secret: &str, step: u64) -> Result<String, String> {
+    let key = base32_decode(secret).ok_or_else(|| "Invalid totp secret".to_string())?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).map_err(|e| format!("{}", e))?;
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    Ok(format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    ))
+}
+
+/// The step matching the code around `unix_time`, if any
+pub fn verify(secret: &str, code: &str, unix_time: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let current = unix_time / STEP;
+    (current.saturating_sub(SKEW)..=current + SKEW).find(|step| {
+        generate(secret, *step)
+            .map(|expected| constant_time_eq(expected.as_bytes(), code.as_bytes()))
+            .unwrap_or(false)
+    })
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            res.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        res.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    res
+}
+
+/// Case insensitive, spaces and padding are ignored
+pub fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| *c != b' ' && *c != b'=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((buffer >> bits) as u8);
+        }
+    }
+    if res.is_empty() { None } else { Some(res) }
+}
@@ -3,7 +3,7 @@ use tera::Context;
 
 use crate::{
     Routers, WEB,
-    api::block_no_admin,
+    api::RequirePermission,
     models::{articles::ArticlesWithTag, tag::Tags},
     utils::parse_query,
     web::render,
@@ -119,36 +119,107 @@ async fn fund(depot: &mut Depot, res: &mut Response) {
     render(res, "admin/fund.html", &web)
 }
 
+#[handler]
+async fn audit(depot: &mut Depot, res: &mut Response) {
+    let web = depot.remove::<Context>(WEB).ok().unwrap();
+
+    render(res, "admin/audit.html", &web)
+}
+
+#[handler]
+async fn comments(depot: &mut Depot, res: &mut Response) {
+    let web = depot.remove::<Context>(WEB).ok().unwrap();
+
+    render(res, "admin/comments.html", &web)
+}
+
 pub struct Admin;
 
 impl Routers for Admin {
     fn build(self) -> Vec<Router> {
         vec![
+            // http {ip}/admin/comments, moderators don't open the other admin pages
+            Router::new()
+                .path("admin/comments")
+                .hoop(RequirePermission("comment:moderate"))
+                .get(comments),
             Router::new()
                 .path("admin")
-                .hoop(block_no_admin)
+                .hoop(RequirePermission("admin:access"))
                 // http {ip}/admin
                 .get(admin)
                 // http {ip}/admin/new
-                .push(Router::new().path("new").get(new_))
+                .push(
+                    Router::new()
+                        .path("new")
+                        .hoop(RequirePermission("article:write"))
+                        .get(new_),
+                )
                 // http {ip}/admin/list
-                .push(Router::new().path("list").get(admin_list))
+                .push(
+                    Router::new()
+                        .path("list")
+                        .hoop(RequirePermission("article:read"))
+                        .get(admin_list),
+                )
                 // http {ip}/admin/unpublished
-                .push(Router::new().path("unpublished").get(unpublished))
+                .push(
+                    Router::new()
+                        .path("unpublished")
+                        .hoop(RequirePermission("article:read"))
+                        .get(unpublished),
+                )
                 // http {ip}/admin/article/view?id=xxx
-                .push(Router::new().path("article/view").get(admin_view_article))
+                .push(
+                    Router::new()
+                        .path("article/view")
+                        .hoop(RequirePermission("article:read"))
+                        .get(admin_view_article),
+                )
                 // http {ip}/admin/article/edit?id=xxx
-                .push(Router::new().path("article/edit").get(article_edit))
+                .push(
+                    Router::new()
+                        .path("article/edit")
+                        .hoop(RequirePermission("article:write"))
+                        .get(article_edit),
+                )
                 // http {ip}/admin/tags
-                .push(Router::new().path("tags").get(tags))
+                .push(
+                    Router::new()
+                        .path("tags")
+                        .hoop(RequirePermission("tag:read"))
+                        .get(tags),
+                )
                 // http {ip}/admin/users
-                .push(Router::new().path("users").get(users))
+                .push(
+                    Router::new()
+                        .path("users")
+                        .hoop(RequirePermission("user:read"))
+                        .get(users),
+                )
                 // http {ip}/admin/ip
-                .push(Router::new().path("ip").get(visitor_ip_log))
+                .push(
+                    Router::new()
+                        .path("ip")
+                        .hoop(RequirePermission("stats:read"))
+                        .get(visitor_ip_log),
+                )
                 // http {ip}/admin/notify
                 .push(Router::new().path("notify").get(notify))
                 // http {ip}/admin/fund
-                .push(Router::new().path("fund").get(fund)),
+                .push(
+                    Router::new()
+                        .path("fund")
+                        .hoop(RequirePermission("fund:read"))
+                        .get(fund),
+                )
+                // http {ip}/admin/audit
+                .push(
+                    Router::new()
+                        .path("audit")
+                        .hoop(RequirePermission("audit:read"))
+                        .get(audit),
+                ),
         ]
     }
 }
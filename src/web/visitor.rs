@@ -1,16 +1,28 @@
 use salvo::{
     Depot, Request, Response, Router,
-    http::{StatusCode, StatusError},
+    http::{StatusCode, StatusError, header},
     prelude::handler,
 };
 use tera::Context;
 use uuid::Uuid;
 
 use crate::{
-    COOKIE, PERMISSION, Routers, WEB,
+    COOKIE, ROLE, Routers, WEB,
+    config::get_config,
     db_wrapper::get_redis,
-    models::{articles::ArticlesWithTag, notify::UserNotify, tag::TagCount, user::UserInfo},
-    utils::{from_code, parse_last_path, visitor_log},
+    models::{
+        article_slug::{ArticleSlug, SlugTarget},
+        articles::{ArticleList, ArticleSearch, ArticlesWithTag},
+        notify::UserNotify,
+        role::Role,
+        tag::{TagCount, Tags},
+        user::UserInfo,
+        user_oauth::UserOAuth,
+    },
+    utils::{
+        from_code, mail::mail_enabled, oauth::provider_infos, parse_last_path, parse_query,
+        visitor_log,
+    },
     web::render,
 };
 
@@ -41,18 +53,77 @@ async fn list(depot: &mut Depot, res: &mut Response) {
     render(res, "visitor/list.html", &web)
 }
 
+#[handler]
+async fn search(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    const PAGE_SIZE: i64 = 20;
+    let mut web = depot.remove::<Context>(WEB).ok().unwrap();
+    let keyword = parse_query::<String>(req, "q").unwrap_or_default();
+    // Bounded so that the offset can't overflow, pages past the results are just empty
+    let page = parse_query::<i64>(req, "page")
+        .unwrap_or(0)
+        .clamp(0, i64::MAX / PAGE_SIZE - 1);
+
+    if !keyword.trim().is_empty() {
+        match ArticleSearch::search(&keyword, PAGE_SIZE + 1, page * PAGE_SIZE, false).await {
+            Ok(mut data) => {
+                // One extra row is queried to know whether there is a next page
+                let has_next = data.len() as i64 > PAGE_SIZE;
+                data.truncate(PAGE_SIZE as usize);
+                web.insert("articles", &data);
+                web.insert("has_next", &has_next);
+            }
+            Err(e) => tracing::info!("can't search articles with {}", e),
+        }
+    }
+    web.insert("keyword", &keyword);
+    web.insert("page", &page);
+
+    render(res, "visitor/search.html", &web)
+}
+
+#[handler]
+async fn tag(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Result<(), StatusError> {
+    let tag_id = req
+        .param::<Uuid>("tag_id")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+    let name = Tags::query_name(tag_id)
+        .await
+        .map_err(|e| from_code(StatusCode::NOT_FOUND, e))?;
+    let mut web = depot.remove::<Context>(WEB).ok().unwrap();
+
+    match ArticleList::query_with_tag(tag_id).await {
+        Ok(data) => web.insert("articles", &data),
+        Err(e) => tracing::info!("can't get articles of tag with {}", e),
+    }
+    web.insert("tag_id", &tag_id);
+    web.insert("tag", &name);
+
+    render(res, "visitor/tag.html", &web);
+    Ok(())
+}
+
 #[handler]
 async fn home(depot: &mut Depot, res: &mut Response) {
-    let web = depot.remove::<Context>(WEB).ok().unwrap();
+    let mut web = depot.remove::<Context>(WEB).ok().unwrap();
 
-    let permission = depot.remove::<Option<i16>>(PERMISSION).unwrap();
+    let role = depot.remove::<Option<Role>>(ROLE).unwrap();
 
-    match permission {
+    match role {
         Some(_) => render(res, "visitor/user.html", &web),
-        None => render(res, "visitor/login.html", &web),
+        None => {
+            web.insert("oauth_providers", &provider_infos());
+            web.insert("mail_enabled", &mail_enabled());
+            render(res, "visitor/login.html", &web)
+        }
     }
 }
 
+#[handler]
+async fn reset_password(depot: &mut Depot, res: &mut Response) {
+    let web = depot.remove::<Context>(WEB).ok().unwrap();
+    render(res, "visitor/reset_password.html", &web)
+}
+
 #[handler]
 async fn user(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Result<(), StatusError> {
     let id = parse_last_path::<Uuid>(req)?;
@@ -61,6 +132,10 @@ async fn user(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Resul
     match UserInfo::view_user(id).await {
         Ok(ref data) => {
             web.insert("user_info", data);
+            web.insert(
+                "oauth",
+                &UserOAuth::view_with_user(id).await.unwrap_or_default(),
+            );
             render(res, "visitor/user_info.html", &web)
         }
         Err(_) => return Err(from_code(StatusCode::NOT_FOUND, "Query Param is Incorrect")),
@@ -74,12 +149,34 @@ async fn article_view(
     depot: &mut Depot,
     res: &mut Response,
 ) -> Result<(), StatusError> {
-    let id = parse_last_path::<Uuid>(req)?;
+    let slug = req
+        .param::<String>("slug")
+        .ok_or_else(|| from_code(StatusCode::BAD_REQUEST, "Path Param is Incorrect"))?;
+
+    let id = match slug.parse::<Uuid>() {
+        // Old uuid urls move to the slug permanently
+        Ok(id) => match ArticleSlug::current(id, false).await {
+            Some(slug) => {
+                permanent_redirect(res, &slug);
+                return Ok(());
+            }
+            None => id,
+        },
+        Err(_) => match ArticleSlug::lookup(&slug).await {
+            Some(SlugTarget::Current(id)) => id,
+            Some(SlugTarget::Moved(slug)) => {
+                permanent_redirect(res, &slug);
+                return Ok(());
+            }
+            None => return Err(from_code(StatusCode::NOT_FOUND, "Article not found")),
+        },
+    };
     let mut web = depot.remove::<Context>(WEB).ok().unwrap();
 
     match ArticlesWithTag::query_article(id, false).await {
         Ok(data) => {
             web.insert("article", &data);
+            web.insert("guest_comments", &get_config().comment.guest);
             if let Ok(cookie) = depot.remove::<String>(COOKIE)
                 && let Ok(info) = get_redis().hget::<String>(&cookie, "info").await
             {
@@ -97,6 +194,14 @@ async fn article_view(
     Ok(())
 }
 
+fn permanent_redirect(res: &mut Response, slug: &str) {
+    res.status_code(StatusCode::MOVED_PERMANENTLY);
+    res.headers_mut().insert(
+        header::LOCATION,
+        format!("/article/{}", slug).parse().unwrap(),
+    );
+}
+
 #[handler]
 async fn fund_visitor(depot: &mut Depot, res: &mut Response) {
     let mut web = depot.remove::<Context>(WEB).ok().unwrap();
@@ -118,12 +223,20 @@ impl Routers for ArticleWeb {
             Router::new().path("about").get(about),
             // http {ip}/list
             Router::new().path("list").get(list),
+            // http {ip}/search?q=<keyword>&page=<number>
+            Router::new().path("search").get(search),
+            // http {ip}/tag/{tag_id}
+            Router::new()
+                .path(r"tag/{tag_id|[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}}")
+                .get(tag),
             // http {ip}/home
             Router::new().path("home").get(home),
+            // http {ip}/reset_password?token=<token>
+            Router::new().path("reset_password").get(reset_password),
             // http {ip}/<id>
             Router::new().path("user/{id}").get(user),
-            // http {ip}/article/<id>
-            Router::new().path("article/{id}").get(article_view),
+            // http {ip}/article/<slug>, the old http {ip}/article/<id> redirects to the slug
+            Router::new().path("article/{slug}").get(article_view),
             // visitor fund page (read-only, no DB portfolios loaded from client)
             Router::new().path("fund").get(fund_visitor),
         ]
@@ -49,6 +49,8 @@ $("#submit").click(function (event) {
         new_choice_already_exists_tags: new_choice_already_exists_tags,
         deselect_tags: deselect_tags,
         new_tags: new_tag,
+        publish_at: getPublishAt(),
+        slug: getSlug(),
       }),
       headers: { "Content-Type": "application/json" },
       success: function (res) {
@@ -64,6 +66,12 @@ $(function () {
   $.getJSON("/api/v1/article/admin/view_raw?id=" + id, function (result) {
     $("#title").val(result.data.title);
     $("#editor").val(result.data.content);
+    $("#slug").val(result.data.slug);
+    if (result.data.publish_at) {
+      $("#publish_at").val(
+        moment.utc(result.data.publish_at).local().format("YYYY-MM-DDTHH:mm"),
+      );
+    }
     $("ul.tag li a").map(function () {
       if ($.inArray($(this).attr("data-id"), result.data.tags_id) !== -1) {
         $(this).addClass("a_click");
@@ -30,6 +30,12 @@ function getArticleList() {
           .utc(result.data[index].modify_time)
           .local()
           .format("YYYY-MM-DD HH:mm:ss");
+        if (result.data[index].publish_at) {
+          result.data[index].publish_at = moment
+            .utc(result.data[index].publish_at)
+            .local()
+            .format("YYYY-MM-DD HH:mm");
+        }
       }
       var html = template("tpl-article-list", result);
       $("tbody").append(html);
@@ -53,7 +59,7 @@ function publishButton() {
       .parent()
       .parent()
       .children("td:nth-child(3)")
-      .children()
+      .children(".publish-status")
       .html();
     var status = raw === "false" ? false : true;
     $.ajax({
@@ -67,12 +73,10 @@ function publishButton() {
         }
       },
     });
-    $(this)
-      .parent()
-      .parent()
-      .children("td:nth-child(3)")
-      .children()
-      .html((!status).toString());
+    var cell = $(this).parent().parent().children("td:nth-child(3)");
+    cell.children(".publish-status").html((!status).toString());
+    // A manual toggle cancels the schedule
+    cell.children(".scheduled").remove();
   });
 }
 
@@ -37,6 +37,8 @@ $("#submit").click(function (event) {
         raw_content: content,
         exist_tags: exist_tags,
         new_tags: new_tags,
+        publish_at: getPublishAt(),
+        slug: getSlug(),
       }),
       headers: { "Content-Type": "application/json" },
       success: function (res) {
@@ -0,0 +1,73 @@
+"use strict";
+var page = new Page("audit");
+
+$(function () {
+  getAuditList();
+});
+
+function auditQuery() {
+  var query = "limit=20&&offset=" + page.page * 20;
+  $("#audit_filter")
+    .serializeArray()
+    .forEach(function (field) {
+      var value = field.value.trim();
+      if (value) {
+        query += "&&" + field.name + "=" + encodeURIComponent(value);
+      }
+    });
+  return query;
+}
+
+function getAuditList() {
+  $.getJSON("/api/v1/audit/view?" + auditQuery(), function (result) {
+    if (!result.status) {
+      alert(result.error);
+      return;
+    }
+    if (result.data.length < 20) {
+      $("#next").attr({ disabled: "disabled" });
+    }
+    for (var index in result.data) {
+      var entry = result.data[index];
+      entry.create_time = moment
+        .utc(entry.create_time)
+        .local()
+        .format("YYYY-MM-DD HH:mm:ss");
+      entry.before = entry.before && JSON.stringify(entry.before, null, 2);
+      entry.after = entry.after && JSON.stringify(entry.after, null, 2);
+    }
+    var html = template("tpl-audit-list", result);
+    $("tbody").append(html);
+  });
+}
+
+$("#audit_filter").on("submit", function (event) {
+  event.preventDefault();
+  page.page = 0;
+  sessionStorage.setItem("audit", 0);
+  $("#previous").attr({ disabled: "disabled" });
+  $("#next").removeAttr("disabled");
+  $("tbody").html("");
+  getAuditList();
+});
+
+$("#previous").click(function (event) {
+  event.preventDefault();
+  page.sub();
+  $("#next").removeAttr("disabled");
+  if (page.page === 0) {
+    $("#previous").attr({ disabled: "disabled" });
+  }
+  $("tbody").html("");
+  getAuditList();
+});
+
+$("#next").click(function (event) {
+  event.preventDefault();
+  page.add();
+  if (page.page > 0) {
+    $("#previous").removeAttr("disabled");
+  }
+  $("tbody").html("");
+  getAuditList();
+});
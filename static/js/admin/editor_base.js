@@ -130,3 +130,18 @@ $(document).ready(function () {
       request.send(form);
     });
 });
+
+// Convert the local schedule input into the server's UTC time, empty means no schedule
+function getPublishAt() {
+  var value = $("#publish_at").val();
+  if (!value) {
+    return null;
+  }
+  return moment(value).utc().format("YYYY-MM-DDTHH:mm:ss");
+}
+
+// Empty slug lets the server generate one from the title
+function getSlug() {
+  var value = $.trim($("#slug").val());
+  return value === "" ? null : value;
+}
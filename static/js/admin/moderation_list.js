@@ -0,0 +1,103 @@
+"use strict";
+var page = new Page("moderation");
+
+$(function () {
+  getModerationList();
+});
+
+function getModerationList() {
+  $("#select_all").prop("checked", false);
+  $.getJSON(
+    "/api/v1/moderation/view?limit=10&&offset=" +
+      page.page * 10 +
+      "&&status=" +
+      $("#status").val(),
+    function (result) {
+      if (result.data.length < 10) {
+        $("#next").attr({ disabled: "disabled" });
+      }
+      for (var index in result.data) {
+        result.data[index].create_time = moment
+          .utc(result.data[index].create_time)
+          .local()
+          .format("YYYY-MM-DD HH:mm:ss");
+      }
+      var html = template("tpl-moderation-list", result);
+      $("#queue").append(html);
+    },
+  );
+}
+
+function decide(action, ids) {
+  if (ids.length === 0) {
+    return;
+  }
+  $.ajax({
+    url: "/api/v1/moderation/" + action,
+    type: "post",
+    dataType: "json",
+    data: JSON.stringify({ ids: ids }),
+    headers: { "Content-Type": "application/json" },
+    success: function (res) {
+      if (res.status) {
+        $.each(ids, function (index, id) {
+          var tr = $("tr[data-id='" + id + "']");
+          tr.hide(500, function () {
+            tr.remove();
+          });
+        });
+      } else {
+        alert(res.error);
+      }
+    },
+  });
+}
+
+$("#queue").on("click", ".decide", function (event) {
+  event.preventDefault();
+  decide($(this).attr("data-action"), [$(this).attr("data-id")]);
+});
+
+$(".bulk").on("click", function (event) {
+  event.preventDefault();
+  var ids = $("#queue .select:checked")
+    .map(function () {
+      return $(this).val();
+    })
+    .get();
+  decide($(this).attr("data-action"), ids);
+});
+
+$("#select_all").on("change", function () {
+  $("#queue .select").prop("checked", $(this).prop("checked"));
+});
+
+$("#status").on("change", function () {
+  page.page = 0;
+  sessionStorage.setItem("moderation", 0);
+  $("#previous").attr({ disabled: "disabled" });
+  $("#next").removeAttr("disabled");
+  $("#queue").html("");
+  getModerationList();
+});
+
+$("#previous").click(function (event) {
+  event.preventDefault();
+  page.sub();
+  $("#next").removeAttr("disabled");
+  if (page.page === 0) {
+    $("#previous").attr({ disabled: "disabled" });
+  }
+  $("#queue").html("");
+  getModerationList();
+});
+
+$("#next").click(function (event) {
+  event.preventDefault();
+  page.add();
+  if (page.page > 0) {
+    $("#previous").removeAttr("disabled");
+  }
+  $("#queue").html("");
+  getModerationList();
+});
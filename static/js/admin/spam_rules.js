@@ -0,0 +1,60 @@
+"use strict";
+
+$(function () {
+  getSpamRules();
+});
+
+function getSpamRules() {
+  $.getJSON("/api/v1/moderation/rules/view", function (result) {
+    for (var index in result.data) {
+      result.data[index].create_time = moment
+        .utc(result.data[index].create_time)
+        .local()
+        .format("YYYY-MM-DD HH:mm:ss");
+    }
+    $("#rules").html(template("tpl-spam-rules", result));
+  });
+}
+
+$("#new_rule").on("submit", function (event) {
+  event.preventDefault();
+  $.ajax({
+    url: "/api/v1/moderation/rules/new",
+    type: "post",
+    dataType: "json",
+    data: JSON.stringify({
+      pattern: $("#rule_pattern").val(),
+      is_regex: $("#rule_regex").prop("checked"),
+      action: $("#rule_action").val(),
+    }),
+    headers: { "Content-Type": "application/json" },
+    success: function (res) {
+      if (res.status) {
+        $("#rule_pattern").val("");
+        getSpamRules();
+      } else {
+        alert(res.error);
+      }
+    },
+  });
+});
+
+$("#rules").on("click", ".delete-rule", function (event) {
+  event.preventDefault();
+  var id = $(this).attr("data-id");
+  $.ajax({
+    url: "/api/v1/moderation/rules/delete/" + id,
+    type: "post",
+    dataType: "json",
+    success: function (res) {
+      if (res.status) {
+        var tr = $("#rules tr[data-id='" + id + "']");
+        tr.hide(500, function () {
+          tr.remove();
+        });
+      } else {
+        alert(res.error);
+      }
+    },
+  });
+});
@@ -10,8 +10,13 @@ function showModal(selector) {
   }
 }
 
+var roles = [];
+
 $(function () {
-  getUserList();
+  $.getJSON("/api/v1/user/roles", function (result) {
+    roles = result.data;
+    getUserList();
+  });
 });
 
 function getUserList() {
@@ -26,16 +31,14 @@ function getUserList() {
           .utc(result.data[index].create_time)
           .local()
           .format("YYYY-MM-DD HH:mm:ss");
-        if (result.data[index].groups === 0) {
-          result.data[index].group_name = "Admin";
-        } else {
-          result.data[index].group_name = "User";
-        }
       }
+      result.roles = roles;
       var html = template("tpl-user-list", result);
       $("tbody").append(html);
       deleteButton();
-      permissionButton();
+      roleSelect();
+      verifyButton();
+      signOutButton();
     },
   );
 }
@@ -65,29 +68,65 @@ function deleteButton() {
   });
 }
 
-function permissionButton() {
-  $(".permission").on("click", function (event) {
+function roleSelect() {
+  $(".role").on("change", function () {
+    var select = $(this);
+    var role = select.val();
+    $.ajax({
+      url: "/api/v1/user/role",
+      type: "post",
+      dataType: "json",
+      data: JSON.stringify({ id: select.attr("data-id"), role: role }),
+      headers: { "Content-Type": "application/json" },
+      success: function (res) {
+        if (res.status) {
+          select.attr("data-role", role);
+        } else {
+          alert(res.error);
+          select.val(select.attr("data-role"));
+        }
+      },
+    });
+  });
+}
+
+function verifyButton() {
+  $(".verify").on("click", function (event) {
     event.preventDefault();
-    var permission =
-      $(this).parent().prev().prev().prev().children().attr("data-id") === "1"
-        ? 0
-        : 1;
-    var permission_element = $(this).parent().prev().prev().prev().children();
+    var badge = $(this).closest("tr").find(".verified");
+    var verified = badge.attr("data-verified") !== "true";
     var id = $(this).attr("data-id");
     $.ajax({
-      url: "/api/v1/user/permission",
+      url: "/api/v1/user/verify",
       type: "post",
       dataType: "json",
-      data: JSON.stringify({ id: id, permission: permission }),
+      data: JSON.stringify({ id: id, verified: verified }),
       headers: { "Content-Type": "application/json" },
       success: function (res) {
         if (res.status) {
-          permission_element.attr("data-id", permission);
-          if (permission === 0) {
-            permission_element.text("Admin");
-          } else {
-            permission_element.text("User");
-          }
+          badge.attr("data-verified", String(verified));
+          badge.toggleClass("bg-success", verified);
+          badge.toggleClass("bg-warning text-dark", !verified);
+          badge.text(verified ? "已验证" : "未验证");
+        }
+      },
+    });
+  });
+}
+
+function signOutButton() {
+  $(".sign-out").on("click", function (event) {
+    event.preventDefault();
+    var button = $(this);
+    $.ajax({
+      url: "/api/v1/user/sessions/revoke_all",
+      type: "post",
+      dataType: "json",
+      data: JSON.stringify({ id: button.attr("data-id") }),
+      headers: { "Content-Type": "application/json" },
+      success: function (res) {
+        if (res.status) {
+          button.attr("title", res.data + " sessions revoked");
         }
       },
     });
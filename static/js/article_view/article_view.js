@@ -21,10 +21,24 @@ function hightlight($doms) {
   });
 }
 
+// The comment replied to and its author, sent with the next comment
+var replyParentId;
+var replyUserId;
+// The comment being edited instead
+var editCommentId;
+// Markdown of the loaded comments, by id
+var commentSource = {};
+
+function cancelReplyOrEdit() {
+  replyParentId = undefined;
+  replyUserId = undefined;
+  editCommentId = undefined;
+  $("#reply-to").html("").hide();
+}
+
 $("body").on("click", "ul.comment li a.delete", function () {
   var user_id = $(this).parent().children().first().attr("user-id");
   var comment_id = $(this).parent().children().first().attr("comment-id");
-  var li = $(this).parent();
   $.ajax({
     url: "/api/v1/comment/delete",
     type: "post",
@@ -33,9 +47,8 @@ $("body").on("click", "ul.comment li a.delete", function () {
     headers: { "Content-Type": "application/json" },
     success: function (res) {
       if (res.status) {
-        li.hide(1000, function () {
-          li.remove();
-        });
+        // A comment with replies stays as a placeholder
+        reloadComments();
       } else {
         openInfo("你似乎并没有这种权限！", "danger");
       }
@@ -45,13 +58,23 @@ $("body").on("click", "ul.comment li a.delete", function () {
 
 $("body").on("click", "ul.comment li a.reply", function () {
   var data = new Object();
-  var re_user = $(this).parent().children(".head").children("a");
+  var head = $(this).parent().children(".head");
+  var re_user = head.children("a");
   data.re_comment = $(this).parent().children(".re-comment").html();
   data.re_user_name = re_user.text();
   data.re_user_url = re_user.attr("href");
-  var html = template("tpl-reply", data);
-  $(".w-e-text").focus();
-  editor.txt.html(html);
+  cancelReplyOrEdit();
+  replyParentId = head.attr("comment-id");
+  replyUserId = head.attr("user-id");
+  $("#reply-to").html(template("tpl-reply", data)).show();
+  $("#comment-text").focus();
+});
+
+$("body").on("click", "ul.comment li a.edit", function () {
+  cancelReplyOrEdit();
+  editCommentId = $(this).parent().children(".head").attr("comment-id");
+  $("#reply-to").html(template("tpl-edit", {})).show();
+  $("#comment-text").val(commentSource[editCommentId]).focus();
 });
 
 function getTagAndModifyTime() {
@@ -90,20 +113,46 @@ function getComments() {
           command.change();
           $("#load").children().text("没有更多了");
         }
-        for (var index in result.data) {
-          result.data[index].create_time = moment
-            .utc(result.data[index].create_time)
+        var comments = [];
+        flattenComments(result.data, comments);
+        for (var index in comments) {
+          comments[index].create_time = moment
+            .utc(comments[index].create_time)
             .local()
             .format();
-          result.data[index]["admin"] = result.admin;
+          if (comments[index].edited) {
+            comments[index].edited = moment
+              .utc(comments[index].edited)
+              .local()
+              .format();
+          }
+          commentSource[comments[index].id] = comments[index].comment;
+          comments[index]["moderator"] = result.moderator;
+          comments[index]["admin"] = result.admin;
           if (result.user_id) {
-            result.data[index]["user"] = result.user_id;
+            comments[index]["user"] = result.user_id;
           }
         }
-        var html = template("tpl-comment-list", result);
+        var html = template("tpl-comment-list", { data: comments });
         $("ul.comment").append(html);
+        hightlight($("ul.comment pre code"));
         command.statusChange();
       },
     );
   }
 }
+
+// Each thread in order, the replies right after their comment
+function flattenComments(threads, out) {
+  $.each(threads, function (index, comment) {
+    out.push(comment);
+    flattenComments(comment.replies, out);
+  });
+}
+
+function reloadComments() {
+  $("ul.comment").empty();
+  $("#load").children().text("加载更多");
+  command.clear();
+  getComments();
+}
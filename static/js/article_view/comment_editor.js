@@ -0,0 +1,161 @@
+"use strict";
+// Visitors without an account, see comment.guest in config.toml
+// Promise of the solved challenge, started once the guest begins to write
+var guestChallenge;
+
+function leadingZeroBits(bytes) {
+  var bits = 0;
+  for (var i = 0; i < bytes.length; i++) {
+    if (bytes[i] === 0) {
+      bits += 8;
+      continue;
+    }
+    return bits + Math.clz32(bytes[i]) - 24;
+  }
+  return bits;
+}
+
+// Find a nonce so that sha256(id + nonce) starts with `difficulty` zero bits
+async function solveChallenge(challenge) {
+  var encoder = new TextEncoder();
+  for (var nonce = 0; ; nonce++) {
+    var hash = await crypto.subtle.digest(
+      "SHA-256",
+      encoder.encode(challenge.id + nonce),
+    );
+    if (leadingZeroBits(new Uint8Array(hash)) >= challenge.difficulty) {
+      return { id: challenge.id, nonce: nonce };
+    }
+  }
+}
+
+function loadGuestChallenge() {
+  if (guestChallenge !== undefined) {
+    return guestChallenge;
+  }
+  $("#guest-status").text("正在验证…");
+  guestChallenge = new Promise(function (resolve, reject) {
+    $.getJSON("/api/v1/comment/guest/challenge", function (res) {
+      if (res.status) {
+        resolve(res.data);
+      } else {
+        reject(res.error);
+      }
+    }).fail(reject);
+  }).then(solveChallenge);
+  guestChallenge.then(
+    function () {
+      $("#guest-status").text("验证完成");
+    },
+    function () {
+      // Tried again on the next submit
+      guestChallenge = undefined;
+      $("#guest-status").text("验证失败");
+    },
+  );
+  return guestChallenge;
+}
+
+$(document).ready(function () {
+  var guest = $("#guest-name").length > 0;
+  if (guest) {
+    $("#guest-name, #guest-email, #comment-text").one("input", loadGuestChallenge);
+  }
+  $("button.comment").click(function () {
+    var comment = $("#comment-text").val();
+    if ($.trim(comment) !== "") {
+      var article_id = $(".container .row > div[data-id]").attr("data-id");
+      var url = "/api/v1/comment/new";
+      var data = {
+        comment: comment,
+        article_id: article_id,
+        parent_id: replyParentId,
+        reply_user_id: replyUserId,
+      };
+      if (editCommentId !== undefined) {
+        url = "/api/v1/comment/edit";
+        data = { comment_id: editCommentId, comment: comment };
+      } else if (guest) {
+        data.name = $("#guest-name").val();
+        data.email = $("#guest-email").val();
+        loadGuestChallenge().then(
+          function (solved) {
+            data.challenge_id = solved.id;
+            data.nonce = solved.nonce;
+            // Each challenge is solved once
+            guestChallenge = undefined;
+            submitComment("/api/v1/comment/guest/new", data);
+          },
+          function (error) {
+            if (error === "Too many challenges, try again later") {
+              openInfo("请求太频繁，请稍后再试", "danger");
+            } else {
+              openInfo("验证失败，请重试", "danger");
+            }
+          },
+        );
+        return;
+      }
+      submitComment(url, data);
+    }
+  });
+
+  $("#reply-to").on("click", ".cancel", function (event) {
+    event.preventDefault();
+    cancelReplyOrEdit();
+  });
+});
+
+function submitComment(url, data) {
+  $.ajax({
+    url: url,
+    type: "post",
+    dataType: "json",
+    data: JSON.stringify(data),
+    headers: { "Content-Type": "application/json" },
+    success: function (res) {
+      if (res.status) {
+        openInfo(
+          res.data === "pending" ? "提交成功，等待审核" : "提交成功",
+          "success",
+        );
+        $("#comment-text").val("");
+        cancelReplyOrEdit();
+        reloadComments();
+      } else if (res.error === "Email is not verified") {
+        openInfo("请先在个人主页验证邮箱", "danger");
+      } else if (res.error === "Comment not found") {
+        cancelReplyOrEdit();
+        openInfo("评论已被删除", "danger");
+      } else if (res.error === "Edit window has passed") {
+        openInfo("已超过可编辑的时间", "danger");
+      } else if (res.error === "Comment is unchanged") {
+        openInfo("评论没有修改", "danger");
+      } else if (res.error === "Too many comments, try again later") {
+        openInfo("评论太频繁，请稍后再试", "danger");
+      } else if (res.error === "Duplicate comment") {
+        openInfo("请勿重复评论", "danger");
+      } else if (res.error === "Comment rejected") {
+        openInfo("评论未通过审核", "danger");
+      } else if (res.error === "Invalid proof of work") {
+        openInfo("验证失败，请重试", "danger");
+      } else if (res.error === "Invalid name") {
+        openInfo("请填写昵称，最多 32 个字", "danger");
+      } else if (res.error === "Invalid email") {
+        openInfo("请填写正确的邮箱", "danger");
+      } else {
+        openInfo("似乎有点错误", "danger");
+      }
+    },
+  });
+}
+
+function closeInfo() {
+  $(".alert").css("display", "none");
+}
+function openInfo(info, level) {
+  var css = ".float_alert.alert-" + level;
+  $(css).text(info);
+  $(css).css("display", "block");
+  window.setTimeout(closeInfo, 3000);
+}
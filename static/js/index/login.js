@@ -1,12 +1,24 @@
 "use strict";
+// Token of a login waiting for the one-time password
+var totpToken = null;
+
 $().ready(function () {
     loginValidate();
     registerValidate();
+    // Logins through an oauth provider come back with the token
+    var params = new URLSearchParams(window.location.search);
+    if (params.get("totp_token")) {
+        showTotp(params.get("totp_token"), params.get("totp_setup") === "true");
+    }
     document.onkeydown = function (e) {
         var ev = document.all ? window.event : e;
         if (ev.keyCode === 13) {
             if ($("#login_form").css("display") === 'block') {
                 login()
+            } else if ($("#totp_form").css("display") === 'block') {
+                totp()
+            } else if ($("#forgot_form").css("display") === 'block') {
+                forgot()
             } else {
                 register()
             }
@@ -20,8 +32,17 @@ $("#register_btn").click(function () {
     $("input[type!='button']").val("");
     $("#account").focus();
 });
-$("#back_btn").click(function () {
+$("#forgot_btn").click(function () {
+    $("#forgot_form").css("display", "block");
+    $("#login_form").css("display", "none");
+    $("input[type!='button']").val("");
+    $("#forgot_email").focus();
+});
+$("#back_btn, .back_btn").click(function () {
     $("#register_form").css("display", "none");
+    $("#forgot_form").css("display", "none");
+    $("#totp_form").css("display", "none");
+    totpToken = null;
     $("#login_form").css("display", "block");
     $("input[type!='button']").val("");
     $("#login_account").focus();
@@ -33,6 +54,22 @@ $("#login").click(function (event) {
 }
 );
 
+$("#forgot_send_btn").click(function (event) {
+    event.preventDefault();
+    forgot()
+}
+);
+
+$("#totp_btn").click(function (event) {
+    event.preventDefault();
+    totp()
+}
+);
+
+$("#totp_continue_btn").click(function () {
+    window.location = "/home"
+});
+
 $("#sign_btn").click(function (event) {
     event.preventDefault();
     register()
@@ -51,12 +88,16 @@ function login() {
             data: JSON.stringify({ "account": account, "password": password, "remember": remember }),
             headers: { 'Content-Type': 'application/json' },
             success: function (res) {
-                if (res.status) {
+                if (res.status && res.data) {
+                    showTotp(res.data.token, res.data.setup)
+                } else if (res.status) {
                     window.location = "/home"
                 } else {
                     $(".text-danger").remove();
                     if (res.error === "NotFound") {
                         $(".checkbox").parent().before("<span class='text-danger'>用户被锁定或未创建</span>")
+                    } else if (res.error.indexOf("Too many failed attempts") === 0) {
+                        $(".checkbox").parent().before("<span class='text-danger'>失败次数过多，请稍后再试</span>")
                     } else {
                         $(".checkbox").parent().before("<span class='text-danger'>用户名或密码错误</span>")
                     }
@@ -66,6 +107,61 @@ function login() {
     }
 }
 
+function showTotp(token, setup) {
+    totpToken = token;
+    $("#login_form").css("display", "none");
+    $("#totp_form").css("display", "block");
+    $("#totp_code").val("").focus();
+    if (setup) {
+        $.ajax({
+            url: "/api/v1/user/login/totp/setup",
+            type: "post",
+            dataType: "json",
+            data: JSON.stringify({ "token": token }),
+            headers: { "Content-Type": "application/json" },
+            success: function (res) {
+                if (res.status) {
+                    $("#totp_secret").text(res.data.secret);
+                    // Svg generated by the server
+                    $(".totp-qr").html(res.data.qr_svg);
+                    $("#totp_setup").css("display", "block");
+                } else {
+                    showTotpError(res.error)
+                }
+            }
+        })
+    }
+}
+
+function totp() {
+    $.ajax({
+        url: "/api/v1/user/login/totp",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "token": totpToken, "code": $("#totp_code").val() }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status && res.data) {
+                // The login finished the enrollment
+                $("#totp_setup").css("display", "none");
+                $("#totp_btn").css("display", "none");
+                $("#totp_code").parent().css("display", "none");
+                $(".recovery-codes").text(res.data.join("\n"));
+                $("#totp_recovery").css("display", "block");
+            } else if (res.status) {
+                window.location = "/home"
+            } else {
+                showTotpError(res.error)
+            }
+        }
+    })
+}
+
+function showTotpError(error) {
+    $(".text-danger").remove();
+    $("#totp_btn").parent().before($("<span class='text-danger'></span>").text(error))
+}
+
 function register() {
     if (registerValidate().form()) {
         var account = $("#account").val();
@@ -90,6 +186,44 @@ function register() {
     }
 }
 
+function forgot() {
+    if (forgotValidate().form()) {
+        var email = $("#forgot_email").val();
+        $.ajax({
+            url: "/api/v1/user/forgot",
+            type: "post",
+            dataType: "json",
+            data: JSON.stringify({ "email": email }),
+            headers: { "Content-Type": "application/json" },
+            success: function (res) {
+                $(".text-danger, .text-success").remove();
+                if (res.status) {
+                    $("#forgot_send_btn").parent().before("<span class='text-success'>如果邮箱已注册，重置链接已发送</span>")
+                } else {
+                    $("#forgot_send_btn").parent().before("<span class='text-danger'>邮件发送失败</span>")
+                }
+            }
+        })
+    }
+}
+
+function forgotValidate() {
+    return $("#forgot_form").validate({
+        rules: {
+            email: {
+                required: true,
+                email: true
+            }
+        },
+        messages: {
+            email: {
+                required: "请输入邮箱",
+                email: "请输入有效邮箱"
+            }
+        }
+    })
+}
+
 function registerValidate() {
     return $("#register_form").validate({
         rules: {
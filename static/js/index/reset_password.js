@@ -0,0 +1,68 @@
+"use strict";
+
+$("#reset_btn").click(function (event) {
+    event.preventDefault();
+    reset()
+});
+
+$("#reset_form").submit(function (event) {
+    event.preventDefault();
+    reset()
+});
+
+function reset() {
+    if (resetValidate().form()) {
+        var token = new URLSearchParams(window.location.search).get("token") || "";
+        var password = randomString(6) + $("#reset_password").val();
+        $.ajax({
+            url: "/api/v1/user/reset",
+            type: "post",
+            dataType: "json",
+            data: JSON.stringify({ "token": token, "password": password }),
+            headers: { "Content-Type": "application/json" },
+            success: function (res) {
+                $(".text-danger").remove();
+                if (res.status) {
+                    window.location = "/home"
+                } else {
+                    $("#reset_btn").parent().before("<span class='text-danger'>链接无效或已过期</span>")
+                }
+            }
+        })
+    }
+}
+
+function resetValidate() {
+    return $("#reset_form").validate({
+        rules: {
+            password: {
+                required: true,
+                minlength: 5
+            },
+            rpassword: {
+                equalTo: "#reset_password"
+            }
+        },
+        messages: {
+            password: {
+                required: "请输入密码",
+                minlength: $.validator.format("密码不能小于{0}个字 符")
+            },
+            rpassword: {
+                required: "请输入密码",
+                equalTo: "两次密码不一样"
+            }
+        }
+    })
+}
+
+function randomString(len) {
+    len = len || 32;
+    var $chars = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789';
+    var maxPos = $chars.length;
+    var pwd = '';
+    for (var i = 0; i < len; i++) {
+        pwd += $chars.charAt(Math.floor(Math.random() * (maxPos + 1)));
+    }
+    return pwd;
+}
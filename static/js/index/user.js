@@ -7,13 +7,21 @@ $("#sign_out_btn").click(function () {
     $("#information").css("display", "none");
     $("#sign_out").css("display", "block");
     $("#modify").css("display", "none");
-    $("#change_password").css("display", "none")
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none")
 });
 $("#modify_btn").click(function () {
     $("#information").css("display", "none");
     $("#sign_out").css("display", "none");
     $("#modify").css("display", "block");
     $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none");
     getInfo()
 });
 $("#change_password_btn").click(function () {
@@ -21,8 +29,56 @@ $("#change_password_btn").click(function () {
     $("#sign_out").css("display", "none");
     $("#modify").css("display", "none");
     $("#change_password").css("display", "block");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none");
     clearPassword()
 });
+$("#oauth_btn").click(function () {
+    $("#information").css("display", "none");
+    $("#sign_out").css("display", "none");
+    $("#modify").css("display", "none");
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "block");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none");
+    getOAuth()
+});
+$("#totp_btn").click(function () {
+    $("#information").css("display", "none");
+    $("#sign_out").css("display", "none");
+    $("#modify").css("display", "none");
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "block");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none");
+    getTotp()
+});
+$("#sessions_btn").click(function () {
+    $("#information").css("display", "none");
+    $("#sign_out").css("display", "none");
+    $("#modify").css("display", "none");
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "block");
+    $("#tokens").css("display", "none");
+    getSessions()
+});
+$("#tokens_btn").click(function () {
+    $("#information").css("display", "none");
+    $("#sign_out").css("display", "none");
+    $("#modify").css("display", "none");
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "block");
+    getTokens()
+});
 
 $(document).ready(function () {
     getUserInfo();
@@ -110,6 +166,230 @@ function getUserInfo() {
     })
 }
 
+$("#information").on("click", "#resend_verification", function () {
+    var button = $(this);
+    $.ajax({
+        url: "/api/v1/user/verify_email/resend",
+        type: "post",
+        dataType: "json",
+        data: "",
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                button.replaceWith("<span class='text-success'>已发送</span>");
+            } else {
+                button.replaceWith("<span class='text-danger'>" + res.error + "</span>");
+            }
+        }
+    });
+});
+
+function getOAuth() {
+    $.getJSON("/api/v1/user/oauth", function (result) {
+        var linked = {};
+        $.each(result.data.linked, function (_, account) {
+            linked[account.provider] = account;
+        });
+        result.linked = linked;
+        var html = template("tpl-oauth", result);
+        $("#oauth").empty();
+        $("#oauth").append(html)
+    })
+}
+
+$("#oauth").on("click", ".unlink", function () {
+    $.ajax({
+        url: "/api/v1/user/oauth/unlink",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "provider": $(this).attr("data-provider") }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getOAuth();
+            }
+        }
+    });
+});
+
+function getTotp(callback) {
+    $.getJSON("/api/v1/user/totp", function (result) {
+        var html = template("tpl-totp", result);
+        $("#totp").empty();
+        $("#totp").append(html);
+        if (callback) {
+            callback()
+        }
+    })
+}
+
+function showTotpError(error) {
+    $("#totp .text-danger").remove();
+    $("#totp_result").before("<span class='text-danger'>" + $("<span>").text(error).html() + "</span>")
+}
+
+function showRecoveryCodes(codes) {
+    $("#totp_result").html(template("tpl-recovery-codes", { "data": codes }))
+}
+
+$("#totp").on("click", "#totp_setup", function () {
+    $.ajax({
+        url: "/api/v1/user/totp/setup",
+        type: "post",
+        dataType: "json",
+        success: function (res) {
+            if (res.status) {
+                $("#totp_setup").remove();
+                $("#totp_result").html(template("tpl-totp-setup", res));
+                $("#totp_enable_code").focus();
+            } else {
+                showTotpError(res.error)
+            }
+        }
+    });
+});
+
+$("#totp").on("click", "#totp_enable", function () {
+    $.ajax({
+        url: "/api/v1/user/totp/enable",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "code": $("#totp_enable_code").val() }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getTotp(function () { showRecoveryCodes(res.data) });
+            } else {
+                showTotpError(res.error)
+            }
+        }
+    });
+});
+
+$("#totp").on("click", "#totp_recovery_codes", function () {
+    $.ajax({
+        url: "/api/v1/user/totp/recovery_codes",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "code": $("#totp_code").val() }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getTotp(function () { showRecoveryCodes(res.data) });
+            } else {
+                showTotpError(res.error)
+            }
+        }
+    });
+});
+
+$("#totp").on("click", "#totp_disable", function () {
+    $.ajax({
+        url: "/api/v1/user/totp/disable",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "code": $("#totp_code").val() }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getTotp();
+            } else {
+                showTotpError(res.error)
+            }
+        }
+    });
+});
+
+function getSessions() {
+    $.getJSON("/api/v1/user/sessions", function (result) {
+        $.each(result.data, function (_, session) {
+            session.login_time_str = moment.unix(session.login_time).format("YYYY-MM-DD HH:mm:ss");
+            session.last_seen_str = session.last_seen ? moment.unix(session.last_seen).fromNow() : "";
+        });
+        var html = template("tpl-sessions", result);
+        $("#sessions").empty();
+        $("#sessions").append(html)
+    })
+}
+
+function revokeSessions(data) {
+    $.ajax({
+        url: "/api/v1/user/sessions/revoke",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify(data),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getSessions();
+            }
+        }
+    });
+}
+
+$("#sessions").on("click", ".revoke", function () {
+    revokeSessions({ "id": $(this).attr("data-id") })
+});
+
+$("#sessions").on("click", "#revoke_others", function () {
+    revokeSessions({})
+});
+
+function getTokens(created) {
+    $.getJSON("/api/v1/user/tokens", function (result) {
+        $.each(result.data.tokens, function (_, token) {
+            token.scopes_str = token.scopes.join(", ");
+            token.expire_str = token.expire_time ? moment(token.expire_time).format("YYYY-MM-DD") : "";
+            token.last_used_str = token.last_used_time ? moment(token.last_used_time).fromNow() : "";
+        });
+        result.data.created = created;
+        var html = template("tpl-tokens", result.data);
+        $("#tokens").empty();
+        $("#tokens").append(html)
+    })
+}
+
+$("#tokens").on("click", "#new_token", function () {
+    var scopes = [];
+    $("#tokens .token-scope:checked").each(function () {
+        scopes.push($(this).val())
+    });
+    var days = $("#token_days").val();
+    $.ajax({
+        url: "/api/v1/user/tokens/new",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({
+            "name": $("#token_name").val(),
+            "scopes": scopes,
+            "expires_in_days": days ? parseInt(days) : null
+        }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getTokens(res.data.token);
+            } else {
+                $("#token_error").text(res.error)
+            }
+        }
+    });
+});
+
+$("#tokens").on("click", ".revoke-token", function () {
+    $.ajax({
+        url: "/api/v1/user/tokens/revoke",
+        type: "post",
+        dataType: "json",
+        data: JSON.stringify({ "id": $(this).attr("data-id") }),
+        headers: { "Content-Type": "application/json" },
+        success: function (res) {
+            if (res.status) {
+                getTokens();
+            }
+        }
+    });
+});
+
 function getInfo() {
     $("#nickname").val($(".nickname").text());
     $("#say").val($(".say").text());
@@ -125,7 +405,11 @@ function informationPage() {
     $("#information").css("display", "block");
     $("#sign_out").css("display", "none");
     $("#modify").css("display", "none");
-    $("#change_password").css("display", "none")
+    $("#change_password").css("display", "none");
+    $("#oauth").css("display", "none");
+    $("#totp").css("display", "none");
+    $("#sessions").css("display", "none");
+    $("#tokens").css("display", "none")
 }
 
 function clearPassword() {
@@ -5,6 +5,7 @@
 //! 1. PostgreSQL database is started and configured correctly
 //! 2. Redis is started
 //! 3. Server is running on http://127.0.0.1:8080
+//! 4. Server is started with `REQUIRE_ADMIN_TOTP=false`, the tests log in as admin with a password
 //!
 //! Run tests: cargo test --test api_tests -- --test-threads=1
 
@@ -80,6 +81,241 @@ mod password_tests {
     }
 }
 
+#[cfg(test)]
+mod password_hash_tests {
+    use new_blog::utils::{
+        password::{hash_password, needs_rehash, verify_password},
+        sha3_256_encode,
+    };
+
+    #[tokio::test]
+    async fn test_argon2id_hash() {
+        let hash = hash_password("password".to_string()).await.unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(!needs_rehash(&hash));
+        // A new salt every time
+        assert_ne!(hash, hash_password("password".to_string()).await.unwrap());
+
+        assert!(verify_password("password".to_string(), hash.clone(), String::new()).await);
+        assert!(!verify_password("Password".to_string(), hash, String::new()).await);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_sha3_hash() {
+        let hash = format!(
+            "$sha3-256${}",
+            sha3_256_encode("passwordsalt12".to_string())
+        );
+        assert!(needs_rehash(&hash));
+        assert!(verify_password("password".to_string(), hash.clone(), "salt12".to_string()).await);
+        assert!(!verify_password("password".to_string(), hash, "salt13".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_hash_format() {
+        // Unmarked hashes are rejected instead of being guessed
+        let hash = sha3_256_encode("passwordsalt12".to_string());
+        assert!(!verify_password("password".to_string(), hash, "salt12".to_string()).await);
+    }
+}
+
+#[cfg(test)]
+mod role_tests {
+    use new_blog::models::{
+        api_token::{SCOPES, available_scopes},
+        role::{PERMISSIONS, Role},
+    };
+
+    #[test]
+    fn test_admin_has_every_permission() {
+        for permission in PERMISSIONS {
+            assert!(Role::Admin.can(permission), "{}", permission);
+        }
+        for scope in SCOPES {
+            assert!(PERMISSIONS.contains(scope), "{}", scope);
+        }
+    }
+
+    #[test]
+    fn test_editor_and_moderator() {
+        assert!(Role::Editor.can("article:write"));
+        assert!(Role::Editor.can("tag:write"));
+        assert!(!Role::Editor.can("user:read"));
+        assert!(!Role::Editor.can("fund:read"));
+        assert!(!Role::Editor.can("comment:moderate"));
+        assert!(!Role::Editor.can("audit:read"));
+
+        assert!(Role::Moderator.can("comment:moderate"));
+        assert!(!Role::Moderator.can("admin:access"));
+        assert!(!Role::Moderator.can("article:write"));
+
+        assert!(Role::Reader.permissions().is_empty());
+        assert_eq!(Role::default(), Role::Reader);
+    }
+
+    #[test]
+    fn test_token_scopes_follow_the_role() {
+        assert_eq!(available_scopes(Role::Admin), SCOPES);
+        assert!(available_scopes(Role::Editor).contains(&"article:write"));
+        assert!(!available_scopes(Role::Editor).contains(&"fund:read"));
+        assert!(available_scopes(Role::Moderator).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod guest_comment_tests {
+    use new_blog::models::comment::{CommentAuthor, NewGuestComment};
+    use serde_json::json;
+
+    fn guest(name: &str, email: &str) -> NewGuestComment {
+        serde_json::from_value(json!({
+            "comment": "hello",
+            "article_id": "00000000-0000-0000-0000-000000000001",
+            "name": name,
+            "email": email,
+            "challenge_id": "00000000-0000-0000-0000-000000000002",
+            "nonce": 7
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_guest_author() {
+        match guest(" Ann ", "Ann@Example.com").author() {
+            Ok(CommentAuthor::Guest { name, email_hash }) => {
+                assert_eq!(name, "Ann");
+                // Only the hash of the email is kept
+                assert_eq!(email_hash.len(), 64);
+                assert!(!email_hash.contains("example"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            guest("  ", "ann@example.com").author().unwrap_err(),
+            "Invalid name"
+        );
+        assert_eq!(
+            guest(&"x".repeat(33), "ann@example.com")
+                .author()
+                .unwrap_err(),
+            "Invalid name"
+        );
+        for email in [
+            "",
+            "ann",
+            "@example.com",
+            "ann@localhost",
+            "a nn@example.com",
+        ] {
+            assert_eq!(
+                guest("Ann", email).author().unwrap_err(),
+                "Invalid email",
+                "{}",
+                email
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod spam_tests {
+    use new_blog::utils::spam::{count_links, spam_probability, tokenize};
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("<p>Buy CHEAP pills&nbsp;now, buy a x</p>"),
+            ["buy", "cheap", "now", "pills"]
+        );
+        // Bigrams of the CJK runs, a lone character stays as is
+        assert_eq!(tokenize("免费领取 好"), ["免费", "好", "费领", "领取"]);
+        assert_eq!(tokenize("ok博客"), ["ok", "博客"]);
+    }
+
+    #[test]
+    fn test_count_links() {
+        assert_eq!(count_links("no link"), 0);
+        assert_eq!(
+            count_links(
+                r#"<a href="https://a.com/x">https://a.com/x</a> http://b.org HTTPS://A.COM/X"#
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn test_spam_probability() {
+        // Nothing known, only the prior
+        assert!((spam_probability(&[], 10, 10) - 0.5).abs() < 1e-9);
+        assert!((spam_probability(&[(0, 0)], 10, 10) - 0.5).abs() < 1e-9);
+        assert!(spam_probability(&[(9, 0), (8, 1)], 10, 10) > 0.9);
+        assert!(spam_probability(&[(0, 9), (1, 8)], 10, 10) < 0.1);
+        assert!(spam_probability(&[], 30, 10) > 0.5);
+    }
+}
+
+#[cfg(test)]
+mod totp_tests {
+    use new_blog::utils::totp::{
+        base32_decode, base32_encode, generate, generate_secret, provisioning_uri, verify,
+    };
+
+    // RFC 6238 appendix B, SHA1 key "12345678901234567890"
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        for (time, code) in [
+            (59u64, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(generate(SECRET, time / 30).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn test_verify_window() {
+        let time = 1111111111;
+        let step = time / 30;
+        assert_eq!(verify(SECRET, "050471", time), Some(step));
+        // One step of clock drift either way
+        let previous = generate(SECRET, step - 1).unwrap();
+        assert_eq!(verify(SECRET, &previous, time), Some(step - 1));
+        let next = generate(SECRET, step + 1).unwrap();
+        assert_eq!(verify(SECRET, &next, time), Some(step + 1));
+        let too_old = generate(SECRET, step - 2).unwrap();
+        assert_eq!(verify(SECRET, &too_old, time), None);
+
+        assert_eq!(verify(SECRET, "05047", time), None);
+        assert_eq!(verify(SECRET, "abcdef", time), None);
+    }
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(base32_encode(b"12345678901234567890"), SECRET);
+        assert_eq!(
+            base32_decode("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap(),
+            b"12345678901234567890"
+        );
+        assert!(base32_decode("not base32!").is_none());
+
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(base32_decode(&secret).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri(SECRET, "My Blog", "alice");
+        assert!(uri.starts_with("otpauth://totp/My%20Blog:alice?"));
+        assert!(uri.contains(&format!("secret={}", SECRET)));
+        assert!(uri.contains("issuer=My+Blog"));
+    }
+}
+
 // ============================================
 // Visitor API Tests (Public Endpoints)
 // ============================================
@@ -156,6 +392,50 @@ mod visitor_api_tests {
         assert!(content_type.to_str().unwrap().contains("xml"));
     }
 
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_atom_feed() {
+        let client = create_client();
+        let url = format!("{}/atom.xml", BASE_URL);
+
+        let response = client.get(&url).send().await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .expect("No content-type header");
+        assert!(
+            content_type
+                .to_str()
+                .unwrap()
+                .contains("application/atom+xml")
+        );
+
+        let body = response.text().await.expect("Failed to read body");
+        assert!(body.contains("<feed"));
+        assert!(body.contains("http://www.w3.org/2005/Atom"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_json_feed() {
+        let client = create_client();
+        let url = format!("{}/feed.json", BASE_URL);
+
+        let response = client.get(&url).send().await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body: Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["version"], "https://jsonfeed.org/version/1.1");
+        assert!(body["items"].is_array());
+        for item in body["items"].as_array().unwrap() {
+            assert!(item["content_html"].is_string());
+            assert!(item["date_published"].is_string());
+            assert!(item["date_modified"].is_string());
+        }
+    }
+
     #[tokio::test]
     #[ignore = "requires running server"]
     async fn test_login_password_too_short() {
@@ -301,6 +581,107 @@ mod visitor_api_tests {
         assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
     }
 
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_password_reset_token() {
+        let client = create_client();
+
+        let forgot_url = format!("{}{}/user/forgot", BASE_URL, API_PREFIX);
+        let bad_resp = client
+            .post(&forgot_url)
+            .json(&json!({ "mail": "x" }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(bad_resp.status(), StatusCode::BAD_REQUEST);
+
+        // Unknown emails look the same as registered ones
+        let forgot_resp = client
+            .post(&forgot_url)
+            .json(&json!({ "email": format!("nobody-{}@example.com", unique_suffix()) }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(forgot_resp.status(), StatusCode::OK);
+        let forgot_body: Value = forgot_resp.json().await.expect("Parse forgot");
+        if forgot_body["status"] == false {
+            assert_eq!(forgot_body["error"], "Mail is not configured");
+        }
+
+        let reset_url = format!("{}{}/user/reset", BASE_URL, API_PREFIX);
+        let reset_resp = client
+            .post(&reset_url)
+            .json(&json!({ "token": "invalid", "password": format_password("newpassword") }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(reset_resp.status(), StatusCode::OK);
+        let reset_body: Value = reset_resp.json().await.expect("Parse reset");
+        assert_eq!(reset_body["status"], false);
+        assert_eq!(reset_body["error"], "Invalid or expired token");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_oauth_authorize_and_state() {
+        let client = Client::builder()
+            .cookie_store(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let unknown_url = format!("{}{}/oauth/unknown/authorize", BASE_URL, API_PREFIX);
+        let unknown_resp = client
+            .get(&unknown_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(unknown_resp.status(), StatusCode::NOT_FOUND);
+
+        let authorize_url = format!("{}{}/oauth/github/authorize", BASE_URL, API_PREFIX);
+        let authorize_resp = client
+            .get(&authorize_url)
+            .send()
+            .await
+            .expect("Request failed");
+        if authorize_resp.status() == StatusCode::NOT_FOUND {
+            // github login is not configured on this server
+            return;
+        }
+        assert_eq!(authorize_resp.status(), StatusCode::FOUND);
+        let location = authorize_resp.headers()["location"].to_str().unwrap();
+        let state = url::Url::parse(location)
+            .expect("Location is an url")
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .map(|(_, value)| value.to_string())
+            .expect("State in the authorize url");
+
+        // A state that was never issued
+        let forged_url = format!(
+            "{}{}/oauth/github/callback?code=invalid_code_for_test&state=forged",
+            BASE_URL, API_PREFIX
+        );
+        let forged_resp = client
+            .get(&forged_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(forged_resp.status(), StatusCode::NOT_ACCEPTABLE);
+
+        // The state is bound to the browser that started the authorization
+        let other_url = format!(
+            "{}{}/oauth/github/callback?code=invalid_code_for_test&state={}",
+            BASE_URL, API_PREFIX, state
+        );
+        let other_resp = create_client()
+            .get(&other_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(other_resp.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
     #[tokio::test]
     #[ignore = "requires running server"]
     async fn test_uuid_path_constraints_for_visitor_article_endpoints() {
@@ -381,6 +762,245 @@ mod auth_tests {
         assert_eq!(body["status"], false);
     }
 
+    async fn login(client: &Client, account: &str) -> Value {
+        let url = format!("{}{}/user/login", BASE_URL, API_PREFIX);
+        let response = client
+            .post(&url)
+            .json(&json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "remember": false
+            }))
+            .send()
+            .await
+            .expect("Request failed");
+        response.json().await.expect("Failed to parse JSON")
+    }
+
+    async fn post(client: &Client, path: &str, body: Value) -> Value {
+        let url = format!("{}{}{}", BASE_URL, API_PREFIX, path);
+        let response = client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        response.json().await.expect("Failed to parse JSON")
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_totp_login() {
+        use new_blog::utils::totp;
+
+        let account = format!("api_totp_user_{}", unique_suffix());
+        let user_client = create_client();
+        let body = post(
+            &user_client,
+            "/user/new",
+            json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "nickname": "TotpUser",
+                "say": "totp",
+                "email": format!("{}@example.com", account)
+            }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+
+        // Enroll
+        let setup = post(&user_client, "/user/totp/setup", json!({})).await;
+        assert_eq!(setup["status"], true);
+        let secret = setup["data"]["secret"].as_str().unwrap().to_string();
+        assert!(
+            setup["data"]["uri"]
+                .as_str()
+                .unwrap()
+                .starts_with("otpauth://totp/")
+        );
+        assert!(setup["data"]["qr_svg"].as_str().unwrap().contains("<svg"));
+
+        let wrong = post(&user_client, "/user/totp/enable", json!({"code": "000000"})).await;
+        assert_eq!(wrong["status"], false);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let code = totp::generate(&secret, now / 30).unwrap();
+        let enabled = post(&user_client, "/user/totp/enable", json!({"code": code})).await;
+        assert_eq!(enabled["status"], true);
+        let recovery_codes: Vec<String> = enabled["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(recovery_codes.len(), 10);
+
+        // The password alone gives no session
+        let client = create_client();
+        let body = login(&client, &account).await;
+        assert_eq!(body["status"], true);
+        assert_eq!(body["data"]["setup"], false);
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        let view = client.get(&view_url).send().await.expect("Request failed");
+        assert_eq!(view.status(), StatusCode::FORBIDDEN);
+
+        // The code used for the enrollment can't be replayed
+        let replay = post(
+            &client,
+            "/user/login/totp",
+            json!({"token": token, "code": code}),
+        )
+        .await;
+        assert_eq!(replay["status"], false);
+
+        let done = post(
+            &client,
+            "/user/login/totp",
+            json!({"token": token, "code": recovery_codes[0]}),
+        )
+        .await;
+        assert_eq!(done["status"], true);
+        let view = client.get(&view_url).send().await.expect("Request failed");
+        assert_eq!(view.status(), StatusCode::OK);
+
+        // The token and the recovery code are single use
+        let again = post(
+            &client,
+            "/user/login/totp",
+            json!({"token": token, "code": recovery_codes[1]}),
+        )
+        .await;
+        assert_eq!(again["status"], false);
+        let client = create_client();
+        let token = login(&client, &account).await["data"]["token"].clone();
+        let reused = post(
+            &client,
+            "/user/login/totp",
+            json!({"token": token, "code": recovery_codes[0]}),
+        )
+        .await;
+        assert_eq!(reused["status"], false);
+
+        let status_url = format!("{}{}/user/totp", BASE_URL, API_PREFIX);
+        let status: Value = user_client
+            .get(&status_url)
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+        assert_eq!(status["data"]["enabled"], true);
+        assert_eq!(status["data"]["required"], false);
+        assert_eq!(status["data"]["recovery_codes"], 9);
+
+        let disabled = post(
+            &user_client,
+            "/user/totp/disable",
+            json!({"code": recovery_codes[2]}),
+        )
+        .await;
+        assert_eq!(disabled["status"], true);
+        let client = create_client();
+        let body = login(&client, &account).await;
+        assert_eq!(body["status"], true);
+        assert!(body.get("data").is_none());
+    }
+
+    async fn register(client: &Client, prefix: &str) -> String {
+        let account = format!("{}_{}", prefix, unique_suffix());
+        let body = post(
+            client,
+            "/user/new",
+            json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "nickname": prefix,
+                "say": prefix,
+                "email": format!("{}@example.com", account)
+            }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        account
+    }
+
+    async fn list_sessions(client: &Client) -> Vec<Value> {
+        let url = format!("{}{}/user/sessions", BASE_URL, API_PREFIX);
+        let body: Value = client
+            .get(&url)
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+        assert_eq!(body["status"], true);
+        body["data"].as_array().unwrap().clone()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_sessions_list_and_revoke() {
+        let first = create_client();
+        let account = register(&first, "api_session_user").await;
+        let second = create_client();
+        assert_eq!(login(&second, &account).await["status"], true);
+        let third = create_client();
+        assert_eq!(login(&third, &account).await["status"], true);
+
+        let sessions = list_sessions(&first).await;
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions.iter().filter(|s| s["current"] == true).count(), 1);
+        // The cookie is never listed
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        assert!(
+            sessions
+                .iter()
+                .all(|s| s["id"].as_str().unwrap().len() == 64)
+        );
+
+        // Revoke one by id
+        let second_id = list_sessions(&second)
+            .await
+            .into_iter()
+            .find(|s| s["current"] == true)
+            .unwrap()["id"]
+            .clone();
+        let body = post(&first, "/user/sessions/revoke", json!({"id": second_id})).await;
+        assert_eq!(body["status"], true);
+        let view = second.get(&view_url).send().await.expect("Request failed");
+        assert_eq!(view.status(), StatusCode::FORBIDDEN);
+        assert_eq!(list_sessions(&first).await.len(), 2);
+
+        // Revoke every other one
+        let body = post(&first, "/user/sessions/revoke", json!({})).await;
+        assert_eq!(body["data"], 1);
+        let view = third.get(&view_url).send().await.expect("Request failed");
+        assert_eq!(view.status(), StatusCode::FORBIDDEN);
+        let view = first.get(&view_url).send().await.expect("Request failed");
+        assert_eq!(view.status(), StatusCode::OK);
+        assert_eq!(list_sessions(&first).await.len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server"]
+    async fn test_totp_login_expired_token() {
+        let client = create_client();
+        let body = post(
+            &client,
+            "/user/login/totp",
+            json!({"token": "unknown", "code": "123456"}),
+        )
+        .await;
+        assert_eq!(body["status"], false);
+    }
+
     #[tokio::test]
     #[ignore = "requires running server"]
     async fn test_protected_api_without_auth() {
@@ -854,26 +1474,383 @@ mod admin_api_tests {
 
     #[tokio::test]
     #[ignore = "requires running server and valid admin account"]
-    async fn test_admin_article_list() {
-        let client = login_as_admin().await;
-        let url = format!(
-            "{}{}/article/admin/view_all?limit=5&offset=0",
-            BASE_URL, API_PREFIX
+    async fn test_article_search() {
+        let admin = login_as_admin().await;
+        let prefix = unique_suffix();
+
+        let published_id =
+            create_temp_article(&admin, &format!("Search Published {}", prefix), true).await;
+        let draft_id =
+            create_temp_article(&admin, &format!("Search Draft {}", prefix), false).await;
+
+        let visitor = create_client();
+        let search_url = format!(
+            "{}{}/article/search?q={}&limit=10&offset=0",
+            BASE_URL, API_PREFIX, prefix
+        );
+        let search_resp = visitor
+            .get(&search_url)
+            .send()
+            .await
+            .expect("Search failed");
+        assert_eq!(search_resp.status(), StatusCode::OK);
+        let search_body: Value = search_resp.json().await.expect("Parse search");
+        assert_eq!(search_body["status"], true);
+        let results = search_body["data"].as_array().expect("search data");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["id"], published_id);
+        assert!(
+            results[0]["title_highlight"]
+                .as_str()
+                .unwrap()
+                .contains(&format!("<mark>{}</mark>", prefix))
         );
 
-        let response = client.get(&url).send().await.expect("Request failed");
-        assert_eq!(response.status(), StatusCode::OK);
+        let admin_search_url = format!(
+            "{}{}/article/admin/search?q={}&limit=10&offset=0",
+            BASE_URL, API_PREFIX, prefix
+        );
+        let admin_search_resp = admin
+            .get(&admin_search_url)
+            .send()
+            .await
+            .expect("Admin search failed");
+        assert_eq!(admin_search_resp.status(), StatusCode::OK);
+        let admin_search_body: Value = admin_search_resp.json().await.expect("Parse search");
+        assert_eq!(admin_search_body["status"], true);
+        assert_eq!(admin_search_body["data"].as_array().unwrap().len(), 2);
+
+        let forbidden_resp = visitor
+            .get(&admin_search_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(forbidden_resp.status(), StatusCode::FORBIDDEN);
 
-        let body: Value = response.json().await.expect("Failed to parse JSON");
-        assert_eq!(body["status"], true);
-        assert!(body["data"].is_array());
+        let page_resp = visitor
+            .get(format!("{}/search?q={}", BASE_URL, prefix))
+            .send()
+            .await
+            .expect("Search page failed");
+        assert_eq!(page_resp.status(), StatusCode::OK);
+        assert!(page_resp.text().await.unwrap().contains(&published_id));
+
+        delete_article_if_exists(&admin, &published_id).await;
+        delete_article_if_exists(&admin, &draft_id).await;
     }
 
     #[tokio::test]
     #[ignore = "requires running server and valid admin account"]
-    async fn test_admin_article_write_and_upload() {
-        let client = login_as_admin().await;
-        let title = format!("Admin Article API Test {}", unique_suffix());
+    async fn test_article_revision_diff_and_restore() {
+        let admin = login_as_admin().await;
+        let title = format!("Revision Article {}", unique_suffix());
+        let article_id = create_temp_article(&admin, &title, false).await;
+
+        let edit_url = format!("{}{}/article/edit", BASE_URL, API_PREFIX);
+        let edit_resp = admin
+            .post(&edit_url)
+            .json(&json!({
+                "id": article_id,
+                "title": format!("{} edited", title),
+                "raw_content": "# temp article\n\nedited line"
+            }))
+            .send()
+            .await
+            .expect("Edit article failed");
+        assert_eq!(edit_resp.status(), StatusCode::OK);
+        let edit_body: Value = edit_resp.json().await.expect("Parse edit article");
+        assert_eq!(edit_body["status"], true);
+
+        let list_url = format!(
+            "{}{}/article/admin/revisions?id={}",
+            BASE_URL, API_PREFIX, article_id
+        );
+        let list_resp = admin.get(&list_url).send().await.expect("List revisions");
+        assert_eq!(list_resp.status(), StatusCode::OK);
+        let list_body: Value = list_resp.json().await.expect("Parse revisions");
+        assert_eq!(list_body["status"], true);
+        let revisions = list_body["data"].as_array().expect("revisions array");
+        assert_eq!(revisions.len(), 2);
+        let newest = revisions[0]["id"].as_str().unwrap().to_string();
+        let oldest = revisions[1]["id"].as_str().unwrap().to_string();
+        assert_eq!(revisions[1]["title"], title);
+
+        let diff_url = format!(
+            "{}{}/article/admin/revision/diff?from={}&to={}",
+            BASE_URL, API_PREFIX, oldest, newest
+        );
+        let diff_resp = admin.get(&diff_url).send().await.expect("Diff revisions");
+        assert_eq!(diff_resp.status(), StatusCode::OK);
+        let diff_body: Value = diff_resp.json().await.expect("Parse diff");
+        assert_eq!(diff_body["status"], true);
+        let diff = diff_body["data"]["diff"].as_str().unwrap();
+        assert!(diff.contains("-for api tests"));
+        assert!(diff.contains("+edited line"));
+
+        let restore_url = format!("{}{}/article/revision/restore", BASE_URL, API_PREFIX);
+        let restore_resp = admin
+            .post(&restore_url)
+            .json(&json!({ "id": oldest }))
+            .send()
+            .await
+            .expect("Restore revision");
+        assert_eq!(restore_resp.status(), StatusCode::OK);
+        let restore_body: Value = restore_resp.json().await.expect("Parse restore");
+        assert_eq!(restore_body["status"], true);
+
+        let raw_url = format!(
+            "{}{}/article/admin/view_raw?id={}",
+            BASE_URL, API_PREFIX, article_id
+        );
+        let raw_body: Value = admin
+            .get(&raw_url)
+            .send()
+            .await
+            .expect("View raw article")
+            .json()
+            .await
+            .expect("Parse raw article");
+        assert_eq!(raw_body["data"]["title"], title);
+        assert_eq!(
+            raw_body["data"]["content"],
+            "# temp article\n\nfor api tests"
+        );
+
+        let list_body: Value = admin
+            .get(&list_url)
+            .send()
+            .await
+            .expect("List revisions")
+            .json()
+            .await
+            .expect("Parse revisions");
+        assert_eq!(list_body["data"].as_array().unwrap().len(), 3);
+
+        delete_article_if_exists(&admin, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_article_scheduled_publish() {
+        let admin = login_as_admin().await;
+        let title = format!("Scheduled Article {}", unique_suffix());
+
+        let create_url = format!("{}{}/article/new", BASE_URL, API_PREFIX);
+        let create_resp = admin
+            .post(&create_url)
+            .json(&json!({
+                "title": title,
+                "raw_content": "# scheduled",
+                "publish_at": "2999-01-01T00:00:00"
+            }))
+            .send()
+            .await
+            .expect("Create article failed");
+        let create_body: Value = create_resp.json().await.expect("Parse create article");
+        assert_eq!(create_body["status"], true);
+
+        let unpublished_url = format!(
+            "{}{}/article/admin/view_unpublished?limit=50&offset=0",
+            BASE_URL, API_PREFIX
+        );
+        let list_body: Value = admin
+            .get(&unpublished_url)
+            .send()
+            .await
+            .expect("List unpublished failed")
+            .json()
+            .await
+            .expect("Parse unpublished list");
+        let article = list_body["data"]
+            .as_array()
+            .and_then(|arr| arr.iter().find(|a| a["title"] == title))
+            .expect("Scheduled article not found in unpublished list")
+            .clone();
+        assert_eq!(article["publish_at"], "2999-01-01T00:00:00");
+        let article_id = article["id"].as_str().unwrap().to_string();
+
+        let edit_url = format!("{}{}/article/edit", BASE_URL, API_PREFIX);
+        let raw_url = format!(
+            "{}{}/article/admin/view_raw?id={}",
+            BASE_URL, API_PREFIX, article_id
+        );
+        let edit_and_view = |mut body: Value| {
+            let admin = &admin;
+            let edit_url = &edit_url;
+            let raw_url = &raw_url;
+            body["id"] = json!(article_id);
+            body["title"] = json!(title);
+            async move {
+                let edit_body: Value = admin
+                    .post(edit_url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .expect("Edit article failed")
+                    .json()
+                    .await
+                    .expect("Parse edit article");
+                assert_eq!(edit_body["status"], true);
+                admin
+                    .get(raw_url)
+                    .send()
+                    .await
+                    .expect("View raw article")
+                    .json::<Value>()
+                    .await
+                    .expect("Parse raw article")
+            }
+        };
+        // Left out, the schedule is kept
+        let raw_body = edit_and_view(json!({ "raw_content": "# edited" })).await;
+        assert_eq!(raw_body["data"]["publish_at"], "2999-01-01T00:00:00");
+        // null clears it
+        let raw_body =
+            edit_and_view(json!({ "raw_content": "# edited", "publish_at": null })).await;
+        assert!(raw_body["data"]["publish_at"].is_null());
+        let raw_body = edit_and_view(json!({
+            "raw_content": "# scheduled",
+            "publish_at": "2999-01-01T00:00:00"
+        }))
+        .await;
+        assert_eq!(raw_body["data"]["publish_at"], "2999-01-01T00:00:00");
+
+        // A manual publish cancels the schedule
+        let publish_url = format!("{}{}/article/publish", BASE_URL, API_PREFIX);
+        let publish_body: Value = admin
+            .post(&publish_url)
+            .json(&json!({ "id": article_id, "publish": true }))
+            .send()
+            .await
+            .expect("Publish article failed")
+            .json()
+            .await
+            .expect("Parse publish article");
+        assert_eq!(publish_body["status"], true);
+
+        let raw_body: Value = admin
+            .get(&raw_url)
+            .send()
+            .await
+            .expect("View raw article")
+            .json()
+            .await
+            .expect("Parse raw article");
+        assert_eq!(raw_body["data"]["published"], true);
+        assert!(raw_body["data"]["publish_at"].is_null());
+
+        delete_article_if_exists(&admin, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_article_slug_routing_and_history() {
+        let admin = login_as_admin().await;
+        let prefix = unique_suffix();
+        let article_id =
+            create_temp_article(&admin, &format!("Slug Article {}", prefix), true).await;
+        let slug = format!("slug-article-{}", prefix);
+
+        let visitor = Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let slug_resp = visitor
+            .get(format!("{}/article/{}", BASE_URL, slug))
+            .send()
+            .await
+            .expect("Open article by slug");
+        assert_eq!(slug_resp.status(), StatusCode::OK);
+
+        let uuid_resp = visitor
+            .get(format!("{}/article/{}", BASE_URL, article_id))
+            .send()
+            .await
+            .expect("Open article by uuid");
+        assert_eq!(uuid_resp.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            uuid_resp.headers()["location"],
+            format!("/article/{}", slug).as_str()
+        );
+
+        // Renaming regenerates the slug, the old one keeps working
+        let edit_url = format!("{}{}/article/edit", BASE_URL, API_PREFIX);
+        let edit_body: Value = admin
+            .post(&edit_url)
+            .json(&json!({
+                "id": article_id,
+                "title": format!("Slug Renamed {}", prefix),
+                "raw_content": "# temp article\n\nfor api tests"
+            }))
+            .send()
+            .await
+            .expect("Edit article failed")
+            .json()
+            .await
+            .expect("Parse edit article");
+        assert_eq!(edit_body["status"], true);
+
+        let old_resp = visitor
+            .get(format!("{}/article/{}", BASE_URL, slug))
+            .send()
+            .await
+            .expect("Open article by old slug");
+        assert_eq!(old_resp.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            old_resp.headers()["location"],
+            format!("/article/slug-renamed-{}", prefix).as_str()
+        );
+
+        // A manual slug has to be valid
+        let invalid_body: Value = admin
+            .post(&edit_url)
+            .json(&json!({
+                "id": article_id,
+                "title": format!("Slug Renamed {}", prefix),
+                "raw_content": "# temp article\n\nfor api tests",
+                "slug": "Not A Slug"
+            }))
+            .send()
+            .await
+            .expect("Edit article failed")
+            .json()
+            .await
+            .expect("Parse edit article");
+        assert_eq!(invalid_body["status"], false);
+
+        let missing_resp = visitor
+            .get(format!("{}/article/no-such-slug-{}", BASE_URL, prefix))
+            .send()
+            .await
+            .expect("Open missing article");
+        assert_eq!(missing_resp.status(), StatusCode::NOT_FOUND);
+
+        delete_article_if_exists(&admin, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_admin_article_list() {
+        let client = login_as_admin().await;
+        let url = format!(
+            "{}{}/article/admin/view_all?limit=5&offset=0",
+            BASE_URL, API_PREFIX
+        );
+
+        let response = client.get(&url).send().await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body: Value = response.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["status"], true);
+        assert!(body["data"].is_array());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_admin_article_write_and_upload() {
+        let client = login_as_admin().await;
+        let title = format!("Admin Article API Test {}", unique_suffix());
         let article_id = create_temp_article(&client, &title, false).await;
 
         // admin/view
@@ -1501,7 +2478,185 @@ mod admin_api_tests {
 
     #[tokio::test]
     #[ignore = "requires running server and valid admin account"]
-    async fn test_admin_user_permission_disable_and_delete() {
+    async fn test_tag_feeds() {
+        let client = login_as_admin().await;
+        let prefix = unique_suffix();
+        let tag_name = format!("FeedTag{}", prefix);
+
+        let create_url = format!("{}{}/tag/new", BASE_URL, API_PREFIX);
+        let response = client
+            .post(&create_url)
+            .json(&json!({ "tag": tag_name }))
+            .send()
+            .await
+            .expect("Create tag failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let list_url = format!("{}{}/tag/view?limit=50&offset=0", BASE_URL, API_PREFIX);
+        let body: Value = client
+            .get(&list_url)
+            .send()
+            .await
+            .expect("List tags failed")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+        let tag_id = body["data"]
+            .as_array()
+            .and_then(|tags| tags.iter().find(|t| t["tag"] == tag_name.as_str()))
+            .and_then(|t| t["id"].as_str())
+            .expect("Created tag not found")
+            .to_string();
+
+        // One published article with the tag, one without
+        let tagged_title = format!("Tagged Feed {}", prefix);
+        let response = client
+            .post(format!("{}{}/article/new", BASE_URL, API_PREFIX))
+            .json(&json!({
+                "title": tagged_title,
+                "raw_content": "# temp article\n\nfor api tests",
+                "exist_tags": [tag_id]
+            }))
+            .send()
+            .await
+            .expect("Create article failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        let admin_list: Value = client
+            .get(format!(
+                "{}{}/article/admin/view_all?limit=50&offset=0",
+                BASE_URL, API_PREFIX
+            ))
+            .send()
+            .await
+            .expect("List article failed")
+            .json()
+            .await
+            .expect("Parse list article");
+        let tagged_id = admin_list["data"]
+            .as_array()
+            .and_then(|arr| arr.iter().find(|a| a["title"] == tagged_title.as_str()))
+            .and_then(|a| a["id"].as_str())
+            .expect("Created article not found")
+            .to_string();
+        let publish_resp = client
+            .post(format!("{}{}/article/publish", BASE_URL, API_PREFIX))
+            .json(&json!({ "id": tagged_id, "publish": true }))
+            .send()
+            .await
+            .expect("Publish article failed");
+        assert_eq!(publish_resp.status(), StatusCode::OK);
+
+        let untagged_title = format!("Untagged Feed {}", prefix);
+        let untagged_id = create_temp_article(&client, &untagged_title, true).await;
+
+        let rss_resp = client
+            .get(format!("{}/tag/{}/rss", BASE_URL, tag_id))
+            .send()
+            .await
+            .expect("Request tag rss failed");
+        assert_eq!(rss_resp.status(), StatusCode::OK);
+        let rss = rss_resp.text().await.expect("Read tag rss");
+        assert!(rss.contains(&tag_name));
+        assert!(rss.contains(&tagged_title));
+        assert!(!rss.contains(&untagged_title));
+
+        let atom_resp = client
+            .get(format!("{}/tag/{}/atom.xml", BASE_URL, tag_id))
+            .send()
+            .await
+            .expect("Request tag atom failed");
+        assert_eq!(atom_resp.status(), StatusCode::OK);
+        let atom = atom_resp.text().await.expect("Read tag atom");
+        assert!(atom.contains(&tagged_title));
+        assert!(!atom.contains(&untagged_title));
+
+        // The tag page lists the same articles and advertises the feeds
+        let page_resp = client
+            .get(format!("{}/tag/{}", BASE_URL, tag_id))
+            .send()
+            .await
+            .expect("Request tag page failed");
+        assert_eq!(page_resp.status(), StatusCode::OK);
+        let page = page_resp.text().await.expect("Read tag page");
+        assert!(page.contains(&tagged_title));
+        assert!(page.contains(&format!("/tag/{}/rss", tag_id)));
+
+        // Unknown tag
+        let missing_resp = client
+            .get(format!(
+                "{}/tag/00000000-0000-0000-0000-000000000000/rss",
+                BASE_URL
+            ))
+            .send()
+            .await
+            .expect("Request missing tag rss failed");
+        assert_eq!(missing_resp.status(), StatusCode::NOT_FOUND);
+
+        delete_article_if_exists(&client, &tagged_id).await;
+        delete_article_if_exists(&client, &untagged_id).await;
+        let delete_url = format!("{}{}/tag/delete/{}", BASE_URL, API_PREFIX, tag_id);
+        client
+            .post(&delete_url)
+            .send()
+            .await
+            .expect("Delete tag failed");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_sitemap_and_robots() {
+        let client = login_as_admin().await;
+        let prefix = unique_suffix();
+        let published_id =
+            create_temp_article(&client, &format!("Sitemap Article {}", prefix), true).await;
+        let draft_id =
+            create_temp_article(&client, &format!("Sitemap Draft {}", prefix), false).await;
+
+        let response = client
+            .get(format!("{}/sitemap.xml", BASE_URL))
+            .send()
+            .await
+            .expect("Request sitemap failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .expect("No content-type header");
+        assert!(content_type.to_str().unwrap().contains("xml"));
+        let sitemap = response.text().await.expect("Read sitemap");
+
+        if sitemap.contains("<sitemapindex") {
+            // Large sites are split, the articles live in the sub sitemaps
+            assert!(sitemap.contains("/sitemap/pages</loc>"));
+            assert!(sitemap.contains("/sitemap/articles/0</loc>"));
+        } else {
+            assert!(sitemap.contains("<urlset"));
+            assert!(sitemap.contains("/about</loc>"));
+            assert!(sitemap.contains("/list</loc>"));
+            assert!(sitemap.contains(&format!("/article/sitemap-article-{}</loc>", prefix)));
+            assert!(!sitemap.contains(&format!("/article/sitemap-draft-{}</loc>", prefix)));
+            assert!(sitemap.contains("<lastmod>"));
+        }
+
+        let robots = client
+            .get(format!("{}/robots.txt", BASE_URL))
+            .send()
+            .await
+            .expect("Request robots failed")
+            .text()
+            .await
+            .expect("Read robots");
+        assert!(robots.contains("User-Agent: *"));
+        assert!(robots.contains("/sitemap.xml"));
+        assert!(!robots.contains("/rss"));
+
+        delete_article_if_exists(&client, &published_id).await;
+        delete_article_if_exists(&client, &draft_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_admin_user_role_disable_and_delete() {
         let admin_client = login_as_admin().await;
 
         // create temp user from public endpoint
@@ -1541,19 +2696,19 @@ mod admin_api_tests {
             .expect("Temp user not found");
         let user_id = user["id"].as_str().expect("user id missing").to_string();
 
-        let permission_url = format!("{}{}/user/permission", BASE_URL, API_PREFIX);
-        let permission_resp = admin_client
-            .post(&permission_url)
+        let role_url = format!("{}{}/user/role", BASE_URL, API_PREFIX);
+        let role_resp = admin_client
+            .post(&role_url)
             .json(&json!({
                 "id": user_id,
-                "permission": 1
+                "role": "reader"
             }))
             .send()
             .await
-            .expect("Change permission failed");
-        assert_eq!(permission_resp.status(), StatusCode::OK);
-        let permission_body: Value = permission_resp.json().await.expect("Parse permission");
-        assert_eq!(permission_body["status"], true);
+            .expect("Change role failed");
+        assert_eq!(role_resp.status(), StatusCode::OK);
+        let role_body: Value = role_resp.json().await.expect("Parse role");
+        assert_eq!(role_body["status"], true);
 
         let disable_url = format!("{}{}/user/delete/disable", BASE_URL, API_PREFIX);
         let disable_resp = admin_client
@@ -1610,6 +2765,1282 @@ mod admin_api_tests {
         assert_eq!(delete_body["status"], true);
     }
 
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_admin_revoke_user_sessions() {
+        let admin_client = login_as_admin().await;
+
+        let user_client = create_client();
+        let account = format!("api_revoke_user_{}", unique_suffix());
+        let register_url = format!("{}{}/user/new", BASE_URL, API_PREFIX);
+        let register_resp = user_client
+            .post(&register_url)
+            .json(&json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "nickname": "RevokeUser",
+                "say": "revoke",
+                "email": format!("{}@example.com", account)
+            }))
+            .send()
+            .await
+            .expect("Register user failed");
+        let register_body: Value = register_resp.json().await.expect("Parse register user");
+        assert_eq!(register_body["status"], true);
+
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        let view_body: Value = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed")
+            .json()
+            .await
+            .expect("Parse view user");
+        let user_id = view_body["data"]["id"].as_str().unwrap().to_string();
+
+        // Only admins may revoke the sessions of someone else
+        let revoke_url = format!("{}{}/user/sessions/revoke_all", BASE_URL, API_PREFIX);
+        let forbidden = user_client
+            .post(&revoke_url)
+            .json(&json!({ "id": user_id }))
+            .send()
+            .await
+            .expect("Revoke failed");
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let revoke_body: Value = admin_client
+            .post(&revoke_url)
+            .json(&json!({ "id": user_id }))
+            .send()
+            .await
+            .expect("Revoke failed")
+            .json()
+            .await
+            .expect("Parse revoke");
+        assert_eq!(revoke_body["status"], true);
+        assert_eq!(revoke_body["data"], 1);
+
+        let view_resp = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed");
+        assert_eq!(view_resp.status(), StatusCode::FORBIDDEN);
+
+        // The admin session is untouched
+        let list_url = format!("{}{}/user/view_all?limit=5&offset=0", BASE_URL, API_PREFIX);
+        let list_resp = admin_client
+            .get(&list_url)
+            .send()
+            .await
+            .expect("List users failed");
+        assert_eq!(list_resp.status(), StatusCode::OK);
+
+        let delete_url = format!("{}{}/user/delete/{}", BASE_URL, API_PREFIX, user_id);
+        let delete_resp = admin_client
+            .post(&delete_url)
+            .send()
+            .await
+            .expect("Delete user failed");
+        assert_eq!(delete_resp.status(), StatusCode::OK);
+    }
+
+    /// Register a user, returns its client, id and account
+    async fn register_user(prefix: &str) -> (Client, String, String) {
+        let user_client = create_client();
+        let account = format!("{}_{}", prefix, unique_suffix());
+        let register_url = format!("{}{}/user/new", BASE_URL, API_PREFIX);
+        let register_body: Value = user_client
+            .post(&register_url)
+            .json(&json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "nickname": prefix,
+                "say": prefix,
+                "email": format!("{}@example.com", account)
+            }))
+            .send()
+            .await
+            .expect("Register user failed")
+            .json()
+            .await
+            .expect("Parse register user");
+        assert_eq!(register_body["status"], true);
+
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        let view_body: Value = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed")
+            .json()
+            .await
+            .expect("Parse view user");
+        let user_id = view_body["data"]["id"].as_str().unwrap().to_string();
+        (user_client, user_id, account)
+    }
+
+    async fn admin_post(client: &Client, path: &str, body: Value) -> Value {
+        let url = format!("{}{}{}", BASE_URL, API_PREFIX, path);
+        let response = client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        response.json().await.expect("Parse response")
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_demoted_admin_loses_access() {
+        let admin_client = login_as_admin().await;
+        let (user_client, user_id, _) = register_user("api_demote_user").await;
+        let admin_url = format!("{}{}/user/view_all?limit=5&offset=0", BASE_URL, API_PREFIX);
+
+        let resp = user_client
+            .get(&admin_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // The live session is promoted without logging in again
+        let body = admin_post(
+            &admin_client,
+            "/user/role",
+            json!({ "id": user_id, "role": "admin" }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let resp = user_client
+            .get(&admin_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = admin_post(
+            &admin_client,
+            "/user/role",
+            json!({ "id": user_id, "role": "reader" }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let resp = user_client
+            .get(&admin_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Still logged in as a user
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        let view_body: Value = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed")
+            .json()
+            .await
+            .expect("Parse view user");
+        assert_eq!(view_body["data"]["role"], "reader");
+
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", user_id),
+            json!({}),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_editor_and_moderator_roles() {
+        let admin_client = login_as_admin().await;
+        let article_id =
+            create_temp_article(&admin_client, &format!("Roles {}", unique_suffix()), true).await;
+        let (editor, editor_id, _) = register_user("api_role_editor").await;
+        let (moderator, moderator_id, _) = register_user("api_role_moderator").await;
+        let (reader, reader_id, _) = register_user("api_role_reader").await;
+        for (id, role) in [(&editor_id, "editor"), (&moderator_id, "moderator")] {
+            let body = admin_post(
+                &admin_client,
+                "/user/role",
+                json!({ "id": id, "role": role }),
+            )
+            .await;
+            assert_eq!(body["status"], true);
+        }
+
+        let status = |client: &Client, path: String| {
+            let request = client.get(format!("{}{}", BASE_URL, path));
+            async move { request.send().await.expect("Request failed").status() }
+        };
+        // Articles but neither users nor funds
+        for (path, expected) in [
+            (
+                format!("{}/article/admin/view_all?limit=5&offset=0", API_PREFIX),
+                StatusCode::OK,
+            ),
+            ("/admin/list".to_string(), StatusCode::OK),
+            (
+                format!("{}/user/view_all?limit=5&offset=0", API_PREFIX),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                format!("{}/fund/portfolios", API_PREFIX),
+                StatusCode::FORBIDDEN,
+            ),
+            ("/admin/users".to_string(), StatusCode::FORBIDDEN),
+        ] {
+            assert_eq!(status(&editor, path.clone()).await, expected, "{}", path);
+        }
+        assert_eq!(
+            status(&moderator, "/admin".to_string()).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Only the moderator deletes the comment of someone else
+        let comment = format!("role comment {}", unique_suffix());
+        let body = admin_post(
+            &reader,
+            "/comment/new",
+            json!({ "comment": comment, "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let comments_url = format!(
+            "{}{}/article/view_comment/{}?limit=50&offset=0",
+            BASE_URL, API_PREFIX, article_id
+        );
+        let comments: Value = moderator
+            .get(&comments_url)
+            .send()
+            .await
+            .expect("List comments failed")
+            .json()
+            .await
+            .expect("Parse comments");
+        assert_eq!(comments["moderator"], true);
+        let comment_id = comments["data"]
+            .as_array()
+            .and_then(|arr| arr.iter().find(|c| c["comment"] == comment.as_str()))
+            .expect("Comment not found")["id"]
+            .clone();
+
+        // Claiming to be the author isn't enough
+        let body = admin_post(
+            &editor,
+            "/comment/delete",
+            json!({ "comment_id": comment_id, "user_id": editor_id }),
+        )
+        .await;
+        assert_eq!(body["status"], false);
+        let body = admin_post(
+            &moderator,
+            "/comment/delete",
+            json!({ "comment_id": comment_id, "user_id": reader_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+
+        delete_article_if_exists(&admin_client, &article_id).await;
+        for id in [&editor_id, &moderator_id, &reader_id] {
+            admin_post(&admin_client, &format!("/user/delete/{}", id), json!({})).await;
+        }
+    }
+
+    async fn list_threads(client: &Client, article_id: &str) -> Vec<Value> {
+        let url = format!(
+            "{}{}/article/view_comment/{}?limit=50&offset=0",
+            BASE_URL, API_PREFIX, article_id
+        );
+        let body: Value = client
+            .get(&url)
+            .send()
+            .await
+            .expect("List comments failed")
+            .json()
+            .await
+            .expect("Parse comments");
+        body["data"]
+            .as_array()
+            .cloned()
+            .expect("data should be array")
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_threaded_comments() {
+        let admin_client = login_as_admin().await;
+        let article_id =
+            create_temp_article(&admin_client, &format!("Threads {}", unique_suffix()), true).await;
+        let (author, author_id, _) = register_user("api_thread_author").await;
+        let (replier, replier_id, _) = register_user("api_thread_replier").await;
+
+        let body = admin_post(
+            &author,
+            "/comment/new",
+            json!({ "comment": "root", "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let root_id = list_threads(&author, &article_id).await[0]["id"].clone();
+
+        // Each reply answers the previous one, the last goes beyond the depth limit
+        let mut parent_id = root_id.clone();
+        for depth in 1..=4 {
+            let body = admin_post(
+                &replier,
+                "/comment/new",
+                json!({
+                    "comment": format!("reply {}", depth),
+                    "article_id": article_id,
+                    "parent_id": parent_id
+                }),
+            )
+            .await;
+            assert_eq!(body["status"], true);
+            let threads = list_threads(&replier, &article_id).await;
+            let mut node = &threads[0];
+            while let Some(reply) = node["replies"].as_array().and_then(|r| r.last()) {
+                node = reply;
+            }
+            parent_id = node["id"].clone();
+        }
+        let threads = list_threads(&replier, &article_id).await;
+        assert_eq!(threads.len(), 1);
+        let level_3 = &threads[0]["replies"][0]["replies"][0]["replies"];
+        assert_eq!(level_3.as_array().map(|r| r.len()), Some(2));
+        assert_eq!(level_3[0]["comment"], "reply 3");
+        assert_eq!(level_3[1]["comment"], "reply 4");
+        assert_eq!(level_3[1]["depth"], 3);
+
+        // A comment of another article can't be the parent
+        let other_id =
+            create_temp_article(&admin_client, &format!("Threads {}", unique_suffix()), true).await;
+        let body = admin_post(
+            &replier,
+            "/comment/new",
+            json!({ "comment": "elsewhere", "article_id": other_id, "parent_id": root_id }),
+        )
+        .await;
+        assert_eq!(body["status"], false);
+
+        // The root has replies, it stays as a placeholder
+        let body = admin_post(
+            &author,
+            "/comment/delete",
+            json!({ "comment_id": root_id, "user_id": author_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let threads = list_threads(&replier, &article_id).await;
+        assert_eq!(threads[0]["deleted"], true);
+        assert_eq!(threads[0]["comment"], "");
+        assert!(threads[0]["nickname"].is_null());
+        let body = admin_post(
+            &replier,
+            "/comment/new",
+            json!({ "comment": "late", "article_id": article_id, "parent_id": root_id }),
+        )
+        .await;
+        assert_eq!(body["status"], false);
+
+        // Removing the replies one by one clears the placeholder too
+        for _ in 0..4 {
+            let threads = list_threads(&replier, &article_id).await;
+            let mut node = &threads[0];
+            while let Some(reply) = node["replies"].as_array().and_then(|r| r.last()) {
+                node = reply;
+            }
+            let body = admin_post(
+                &replier,
+                "/comment/delete",
+                json!({ "comment_id": node["id"], "user_id": replier_id }),
+            )
+            .await;
+            assert_eq!(body["status"], true);
+        }
+        assert!(list_threads(&replier, &article_id).await.is_empty());
+
+        delete_article_if_exists(&admin_client, &article_id).await;
+        delete_article_if_exists(&admin_client, &other_id).await;
+        for id in [&author_id, &replier_id] {
+            admin_post(&admin_client, &format!("/user/delete/{}", id), json!({})).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server with COMMENT_MODERATION=all"]
+    async fn test_comment_moderation_queue() {
+        let admin_client = login_as_admin().await;
+        let article_id = create_temp_article(
+            &admin_client,
+            &format!("Moderation {}", unique_suffix()),
+            true,
+        )
+        .await;
+        let (author, author_id, _) = register_user("api_moderation_author").await;
+        let (moderator, moderator_id, _) = register_user("api_moderation_moderator").await;
+        admin_post(
+            &admin_client,
+            "/user/role",
+            json!({ "id": moderator_id, "role": "moderator" }),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for comment in ["held 1", "held 2"] {
+            let body = admin_post(
+                &author,
+                "/comment/new",
+                json!({ "comment": comment, "article_id": article_id }),
+            )
+            .await;
+            assert_eq!(body["data"], "pending");
+        }
+        // Moderators are trusted
+        let body = admin_post(
+            &moderator,
+            "/comment/new",
+            json!({ "comment": "trusted", "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["data"], "approved");
+
+        // Hidden from the others until approved
+        assert_eq!(list_threads(&create_client(), &article_id).await.len(), 1);
+        let threads = list_threads(&author, &article_id).await;
+        assert_eq!(threads.len(), 3);
+        assert_eq!(
+            threads.iter().filter(|c| c["status"] == "pending").count(),
+            2
+        );
+
+        let queue_url = format!(
+            "{}{}/moderation/view?limit=50&offset=0&status=pending",
+            BASE_URL, API_PREFIX
+        );
+        let resp = author.get(&queue_url).send().await.expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let queue: Value = moderator
+            .get(&queue_url)
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Parse queue");
+        for comment in queue["data"].as_array().expect("data should be array") {
+            if comment["article_id"] == article_id.as_str() {
+                ids.push(comment["id"].clone());
+            }
+        }
+        assert_eq!(ids.len(), 2);
+
+        let body = admin_post(
+            &moderator,
+            "/moderation/approve",
+            json!({ "ids": [ids[0]] }),
+        )
+        .await;
+        assert_eq!(body["data"], 1);
+        let body = admin_post(&moderator, "/moderation/reject", json!({ "ids": ids })).await;
+        // Only the one still pending
+        assert_eq!(body["data"], 1);
+
+        let threads = list_threads(&create_client(), &article_id).await;
+        assert_eq!(threads.len(), 2);
+        assert!(threads.iter().any(|c| c["comment"] == "held 1"));
+        assert_eq!(list_threads(&author, &article_id).await.len(), 2);
+
+        let mut remaining: Vec<Value> = list_threads(&moderator, &article_id)
+            .await
+            .into_iter()
+            .map(|c| c["id"].clone())
+            .collect();
+        remaining.push(ids[1].clone());
+        for id in remaining {
+            admin_post(
+                &moderator,
+                "/comment/delete",
+                json!({ "comment_id": id, "user_id": moderator_id }),
+            )
+            .await;
+        }
+        delete_article_if_exists(&admin_client, &article_id).await;
+        for id in [&author_id, &moderator_id] {
+            admin_post(&admin_client, &format!("/user/delete/{}", id), json!({})).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_markdown_comment() {
+        let admin_client = login_as_admin().await;
+        let article_id = create_temp_article(
+            &admin_client,
+            &format!("Markdown {}", unique_suffix()),
+            true,
+        )
+        .await;
+        let (author, author_id, _) = register_user("api_markdown_author").await;
+
+        let body = admin_post(
+            &author,
+            "/comment/new",
+            json!({ "comment": "  \n", "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["error"], "Comment is empty");
+
+        let markdown = "Try `cargo test`:\n\n```rust\nfn main() {}\n```\n\n\
+            [docs](https://docs.rs) <img src=x onerror=alert(1)>";
+        let body = admin_post(
+            &author,
+            "/comment/new",
+            json!({ "comment": markdown, "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+
+        let threads = list_threads(&author, &article_id).await;
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0]["comment"], markdown);
+        let html = threads[0]["comment_html"]
+            .as_str()
+            .expect("rendered comment");
+        assert!(html.contains("<code>cargo test</code>"));
+        assert!(html.contains(r#"<pre><code class="language-rust">"#));
+        assert!(html.contains(r#"<a href="https://docs.rs" rel="nofollow ugc">docs</a>"#));
+        assert!(html.contains("&lt;img"));
+        assert!(!html.contains("<img"));
+
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", author_id),
+            json!({}),
+        )
+        .await;
+        delete_article_if_exists(&admin_client, &article_id).await;
+    }
+
+    /// Id of a new guest challenge with a valid nonce, and an invalid one
+    async fn solve_challenge(client: &Client) -> (Value, u64, u64) {
+        let body: Value = client
+            .get(format!(
+                "{}{}/comment/guest/challenge",
+                BASE_URL, API_PREFIX
+            ))
+            .send()
+            .await
+            .expect("Challenge request failed")
+            .json()
+            .await
+            .expect("Parse challenge");
+        let id = body["data"]["id"].as_str().expect("id");
+        let difficulty = body["data"]["difficulty"].as_u64().expect("difficulty") as u32;
+        let solved = |n: &u64| new_blog::utils::proof_of_work_bits(id, *n) >= difficulty;
+        let nonce = (0..).find(solved).unwrap();
+        let wrong = (0..).find(|n| !solved(n)).unwrap();
+        (body["data"]["id"].clone(), nonce, wrong)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server with COMMENT_GUEST=true"]
+    async fn test_guest_comment() {
+        let admin_client = login_as_admin().await;
+        let article_id =
+            create_temp_article(&admin_client, &format!("Guest {}", unique_suffix()), true).await;
+        let guest = create_client();
+        let new_url = format!("{}{}/comment/guest/new", BASE_URL, API_PREFIX);
+        let comment = format!("guest says hi {}", unique_suffix());
+        let post = |challenge_id: Value, nonce: u64| {
+            let guest = &guest;
+            let new_url = &new_url;
+            let body = json!({
+                "comment": comment,
+                "article_id": article_id,
+                "name": "Guest",
+                "email": "Guest@Example.com",
+                "challenge_id": challenge_id,
+                "nonce": nonce
+            });
+            async move {
+                guest
+                    .post(new_url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .expect("Guest comment failed")
+                    .json::<Value>()
+                    .await
+                    .expect("Parse guest comment")
+            }
+        };
+
+        let (challenge_id, nonce, wrong) = solve_challenge(&guest).await;
+        let body = post(challenge_id.clone(), wrong).await;
+        assert_eq!(body["error"], "Invalid proof of work");
+        // Used up by the wrong nonce
+        let body = post(challenge_id, nonce).await;
+        assert_eq!(body["error"], "Invalid proof of work");
+
+        let (challenge_id, nonce, _) = solve_challenge(&guest).await;
+        let body = post(challenge_id, nonce).await;
+        assert_eq!(body["status"], true);
+        assert_eq!(body["data"], "pending");
+        assert!(list_threads(&guest, &article_id).await.is_empty());
+
+        let queue: Value = admin_client
+            .get(format!(
+                "{}{}/moderation/view?limit=50&offset=0&status=pending",
+                BASE_URL, API_PREFIX
+            ))
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Parse queue");
+        let held = queue["data"]
+            .as_array()
+            .expect("data should be array")
+            .iter()
+            .find(|c| c["article_id"] == article_id.as_str())
+            .expect("guest comment in the queue")
+            .clone();
+        assert!(held["user_id"].is_null());
+        assert_eq!(held["nickname"], "Guest");
+        let body = admin_post(
+            &admin_client,
+            "/moderation/approve",
+            json!({ "ids": [held["id"]] }),
+        )
+        .await;
+        assert_eq!(body["data"], 1);
+
+        let threads = list_threads(&guest, &article_id).await;
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0]["comment"], comment.as_str());
+        assert!(threads[0]["user_id"].is_null());
+        // sha256 of "guest@example.com"
+        assert_eq!(
+            threads[0]["email_hash"],
+            "513935c4d2db2d2d984dff1d68397f6e2ac8c4e5c48c92bd98e02bdc90b7aefe"
+        );
+
+        let body = admin_post(
+            &admin_client,
+            "/comment/delete",
+            json!({ "comment_id": held["id"] }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        delete_article_if_exists(&admin_client, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_edit_comment() {
+        let admin_client = login_as_admin().await;
+        let article_id =
+            create_temp_article(&admin_client, &format!("Edit {}", unique_suffix()), true).await;
+        let (author, author_id, _) = register_user("api_edit_author").await;
+        let (other, other_id, _) = register_user("api_edit_other").await;
+
+        let body = admin_post(
+            &author,
+            "/comment/new",
+            json!({ "comment": "first *draft*", "article_id": article_id }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let threads = list_threads(&author, &article_id).await;
+        let comment_id = threads[0]["id"].as_str().expect("comment id").to_string();
+        assert!(threads[0]["edited"].is_null());
+
+        let body = admin_post(
+            &author,
+            "/comment/edit",
+            json!({ "comment_id": comment_id, "comment": "first *draft*" }),
+        )
+        .await;
+        assert_eq!(body["error"], "Comment is unchanged");
+        // Only the author and the admins
+        let body = admin_post(
+            &other,
+            "/comment/edit",
+            json!({ "comment_id": comment_id, "comment": "hijacked" }),
+        )
+        .await;
+        assert_eq!(body["error"], "Comment not found");
+
+        let body = admin_post(
+            &author,
+            "/comment/edit",
+            json!({ "comment_id": comment_id, "comment": "second **draft**" }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let body = admin_post(
+            &admin_client,
+            "/comment/edit",
+            json!({ "comment_id": comment_id, "comment": "edited by admin" }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+
+        let threads = list_threads(&author, &article_id).await;
+        assert_eq!(threads[0]["comment"], "edited by admin");
+        assert_eq!(threads[0]["comment_html"], "<p>edited by admin</p>\n");
+        assert!(threads[0]["edited"].is_string());
+
+        let revisions: Value = admin_client
+            .get(format!(
+                "{}{}/moderation/revisions/{}",
+                BASE_URL, API_PREFIX, comment_id
+            ))
+            .send()
+            .await
+            .expect("Revisions request failed")
+            .json()
+            .await
+            .expect("Parse revisions");
+        let revisions = revisions["data"].as_array().expect("data should be array");
+        // Newest first, each one replaced by the next edit
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0]["comment"], "second **draft**");
+        assert_eq!(revisions[1]["comment"], "first *draft*");
+        assert_eq!(revisions[1]["editor_id"], author_id.as_str());
+
+        for id in [&author_id, &other_id] {
+            admin_post(&admin_client, &format!("/user/delete/{}", id), json!({})).await;
+        }
+        delete_article_if_exists(&admin_client, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_comment_spam_checks() {
+        let admin_client = login_as_admin().await;
+        let article_id =
+            create_temp_article(&admin_client, &format!("Spam {}", unique_suffix()), true).await;
+        let (author, author_id, _) = register_user("api_spam_author").await;
+        let suffix = unique_suffix();
+
+        let mut rule_ids = Vec::new();
+        for (pattern, is_regex, action) in [
+            (format!("casino{}", suffix), false, "reject"),
+            (format!(r"pills?\s*{}", suffix), true, "moderate"),
+        ] {
+            let body = admin_post(
+                &admin_client,
+                "/moderation/rules/new",
+                json!({ "pattern": pattern, "is_regex": is_regex, "action": action }),
+            )
+            .await;
+            assert_eq!(body["status"], true);
+            rule_ids.push(body["data"].as_str().expect("rule id").to_string());
+        }
+        let body = admin_post(
+            &admin_client,
+            "/moderation/rules/new",
+            json!({ "pattern": "(", "is_regex": true, "action": "reject" }),
+        )
+        .await;
+        assert_eq!(body["status"], false);
+
+        let post = |comment: String| {
+            let author = &author;
+            let article_id = &article_id;
+            async move {
+                admin_post(
+                    author,
+                    "/comment/new",
+                    json!({ "comment": comment, "article_id": article_id }),
+                )
+                .await
+            }
+        };
+        let body = post(format!("Play CASINO{} now", suffix)).await;
+        assert_eq!(body["error"], "Comment rejected");
+        let body = post(format!("pill {}", suffix)).await;
+        assert_eq!(body["data"], "pending");
+        let body = post(format!(
+            "see https://a.com https://b.com https://c.com {}",
+            suffix
+        ))
+        .await;
+        assert_eq!(body["data"], "pending");
+        let body = post(format!("hello {}", suffix)).await;
+        assert_eq!(body["data"], "approved");
+        let body = post(format!("Hello {} ", suffix)).await;
+        assert_eq!(body["error"], "Duplicate comment");
+        // 5 comments a minute by default
+        let body = post(format!("again {}", suffix)).await;
+        assert_eq!(body["error"], "Too many comments, try again later");
+
+        for id in rule_ids {
+            let body = admin_post(
+                &admin_client,
+                &format!("/moderation/rules/delete/{}", id),
+                json!({}),
+            )
+            .await;
+            assert_eq!(body["status"], true);
+        }
+        // Takes the comments of the author with it
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", author_id),
+            json!({}),
+        )
+        .await;
+        delete_article_if_exists(&admin_client, &article_id).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_audit_log_records_admin_mutations() {
+        let admin_client = login_as_admin().await;
+        let name = format!("AuditTag{}", unique_suffix());
+        let body = admin_post(&admin_client, "/tag/new", json!({ "tag": name })).await;
+        assert_eq!(body["status"], true);
+        let tag_id = body["data"].as_str().expect("tag id").to_string();
+        let renamed = format!("{}Renamed", name);
+        let body = admin_post(
+            &admin_client,
+            "/tag/edit",
+            json!({ "id": tag_id, "tag": renamed }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        admin_post(&admin_client, &format!("/tag/delete/{}", tag_id), json!({})).await;
+
+        let audit_url = format!(
+            "{}{}/audit/view?limit=10&offset=0&action=tag&target=tag:{}",
+            BASE_URL, API_PREFIX, tag_id
+        );
+        let body: Value = admin_client
+            .get(&audit_url)
+            .send()
+            .await
+            .expect("Audit request failed")
+            .json()
+            .await
+            .expect("Parse audit log");
+        assert_eq!(body["status"], true);
+        let entries = body["data"].as_array().expect("data should be array");
+        let actions: Vec<&str> = entries
+            .iter()
+            .map(|e| e["action"].as_str().unwrap())
+            .collect();
+        // Latest first
+        assert_eq!(actions, ["tag.delete", "tag.edit", "tag.create"]);
+        assert!(entries.iter().all(|e| e["actor"] == "admin"));
+        assert_eq!(entries[1]["before"]["tag"], name.as_str());
+        assert_eq!(entries[1]["after"]["tag"], renamed.as_str());
+        assert_eq!(entries[0]["before"]["tag"], renamed.as_str());
+        assert!(entries[0]["after"].is_null());
+
+        // Filtered by an other action
+        let body: Value = admin_client
+            .get(format!(
+                "{}{}/audit/view?limit=10&offset=0&action=article&target=tag:{}",
+                BASE_URL, API_PREFIX, tag_id
+            ))
+            .send()
+            .await
+            .expect("Audit request failed")
+            .json()
+            .await
+            .expect("Parse audit log");
+        assert_eq!(body["data"].as_array().map(|a| a.len()), Some(0));
+
+        // Editors change content but don't read the log
+        let (editor, editor_id, _) = register_user("api_audit_editor").await;
+        admin_post(
+            &admin_client,
+            "/user/role",
+            json!({ "id": editor_id, "role": "editor" }),
+        )
+        .await;
+        let resp = editor.get(&audit_url).send().await.expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp = editor
+            .get(format!("{}/admin/audit", BASE_URL))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", editor_id),
+            json!({}),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_disabled_and_deleted_user_logged_out() {
+        let admin_client = login_as_admin().await;
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+
+        let (user_client, user_id, _) = register_user("api_disable_user").await;
+        let body = admin_post(
+            &admin_client,
+            "/user/delete/disable",
+            json!({ "id": user_id, "disabled": 1 }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let resp = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", user_id),
+            json!({}),
+        )
+        .await;
+
+        let (user_client, user_id, _) = register_user("api_delete_user").await;
+        let body = admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", user_id),
+            json!({}),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let resp = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_login_lockout() {
+        let admin_client = login_as_admin().await;
+        let (_, user_id, account) = register_user("api_lockout_user").await;
+        // Keep the counter of the ip of the tests untouched
+        let ip = format!("198.51.100.{}", unique_suffix() % 250);
+
+        let client = create_client();
+        let login_url = format!("{}{}/user/login", BASE_URL, API_PREFIX);
+        let login = |password: &'static str| {
+            client
+                .post(&login_url)
+                .header("X-Real-IP", ip.as_str())
+                .json(&json!({
+                    "account": account,
+                    "password": format_password(password),
+                    "remember": false
+                }))
+                .send()
+        };
+
+        // A few free attempts, then every failure makes the account wait
+        for _ in 0..4 {
+            let body: Value = login("wrongpass").await.unwrap().json().await.unwrap();
+            assert_eq!(body["status"], false);
+            assert_eq!(body["error"], "Invalid username or password");
+        }
+        let body: Value = login("userpass123").await.unwrap().json().await.unwrap();
+        assert_eq!(body["status"], false);
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Too many failed attempts")
+        );
+
+        let lockouts_url = format!("{}{}/user/lockouts", BASE_URL, API_PREFIX);
+        let lockouts: Value = admin_client
+            .get(&lockouts_url)
+            .send()
+            .await
+            .expect("List lockouts failed")
+            .json()
+            .await
+            .expect("Parse lockouts");
+        let lockout = lockouts["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|l| l["kind"] == "account" && l["key"] == account.as_str())
+            .expect("account is not listed")
+            .clone();
+        assert_eq!(lockout["count"], 4);
+        assert!(lockout["locked_until"].as_i64().is_some());
+
+        let body = admin_post(
+            &admin_client,
+            "/user/lockouts/clear",
+            json!({ "kind": "account", "key": account }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        let body: Value = login("userpass123").await.unwrap().json().await.unwrap();
+        assert_eq!(body["status"], true);
+
+        let body = admin_post(
+            &admin_client,
+            "/user/lockouts/clear",
+            json!({ "kind": "ip", "key": ip }),
+        )
+        .await;
+        assert_eq!(body["status"], true);
+        admin_post(
+            &admin_client,
+            &format!("/user/delete/{}", user_id),
+            json!({}),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_api_tokens() {
+        let admin_client = login_as_admin().await;
+        let name = format!("api_token_{}", unique_suffix());
+
+        let unknown = admin_post(
+            &admin_client,
+            "/user/tokens/new",
+            json!({ "name": name, "scopes": ["article:delete"] }),
+        )
+        .await;
+        assert_eq!(unknown["status"], false);
+
+        let created = admin_post(
+            &admin_client,
+            "/user/tokens/new",
+            json!({ "name": name, "scopes": ["article:read"], "expires_in_days": 1 }),
+        )
+        .await;
+        assert_eq!(created["status"], true);
+        let token = created["data"]["token"].as_str().unwrap().to_string();
+        let token_id = created["data"]["id"].as_str().unwrap().to_string();
+        assert!(token.starts_with(created["data"]["token_prefix"].as_str().unwrap()));
+
+        // A fresh client, only the header authenticates
+        let script = Client::new();
+        let bearer = format!("Bearer {}", token);
+        let list_url = format!(
+            "{}{}/article/admin/view_all?limit=5&offset=0",
+            BASE_URL, API_PREFIX
+        );
+        let list_resp = script
+            .get(&list_url)
+            .header("Authorization", &bearer)
+            .send()
+            .await
+            .expect("List articles failed");
+        assert_eq!(list_resp.status(), StatusCode::OK);
+
+        // Missing scopes
+        let new_article = script
+            .post(format!("{}{}/article/new", BASE_URL, API_PREFIX))
+            .header("Authorization", &bearer)
+            .json(&json!({ "title": name, "raw_content": "token" }))
+            .send()
+            .await
+            .expect("Create article failed");
+        assert_eq!(new_article.status(), StatusCode::FORBIDDEN);
+        let fund_resp = script
+            .get(format!("{}{}/fund/portfolios", BASE_URL, API_PREFIX))
+            .header("Authorization", &bearer)
+            .send()
+            .await
+            .expect("List funds failed");
+        assert_eq!(fund_resp.status(), StatusCode::FORBIDDEN);
+
+        // Account management stays behind the session
+        let tokens_resp = script
+            .get(format!("{}{}/user/tokens", BASE_URL, API_PREFIX))
+            .header("Authorization", &bearer)
+            .send()
+            .await
+            .expect("List tokens failed");
+        assert_eq!(tokens_resp.status(), StatusCode::FORBIDDEN);
+
+        let tokens_body: Value = admin_client
+            .get(format!("{}{}/user/tokens", BASE_URL, API_PREFIX))
+            .send()
+            .await
+            .expect("List tokens failed")
+            .json()
+            .await
+            .expect("Parse tokens");
+        let listed = tokens_body["data"]["tokens"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["id"] == token_id.as_str())
+            .expect("Token not listed")
+            .clone();
+        assert!(listed.get("token").is_none());
+        assert!(listed["last_used_time"].is_string());
+
+        let revoked = admin_post(
+            &admin_client,
+            "/user/tokens/revoke",
+            json!({ "id": token_id }),
+        )
+        .await;
+        assert_eq!(revoked["status"], true);
+        let list_resp = script
+            .get(&list_url)
+            .header("Authorization", &bearer)
+            .send()
+            .await
+            .expect("List articles failed");
+        assert_eq!(list_resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires running server and valid admin account"]
+    async fn test_email_verification() {
+        let admin_client = login_as_admin().await;
+
+        let user_client = create_client();
+        let account = format!("api_verify_user_{}", unique_suffix());
+        let register_url = format!("{}{}/user/new", BASE_URL, API_PREFIX);
+        let register_resp = user_client
+            .post(&register_url)
+            .json(&json!({
+                "account": account,
+                "password": format_password("userpass123"),
+                "nickname": "ApiVerifyUser",
+                "email": format!("{}@example.com", account)
+            }))
+            .send()
+            .await
+            .expect("Register user failed");
+        let register_body: Value = register_resp.json().await.expect("Parse register user");
+        assert_eq!(register_body["status"], true);
+
+        let view_url = format!("{}{}/user/view", BASE_URL, API_PREFIX);
+        let view_body: Value = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed")
+            .json()
+            .await
+            .expect("Parse view user");
+        assert_eq!(view_body["data"]["email_verified"], false);
+        let user_id = view_body["data"]["id"].as_str().unwrap().to_string();
+
+        // A link with a forged signature
+        let verify_url = format!(
+            "{}{}/user/verify_email?id={}&email={}%40example.com&expires=9999999999&sig=00",
+            BASE_URL, API_PREFIX, user_id, account
+        );
+        let verify_body: Value = create_client()
+            .get(&verify_url)
+            .send()
+            .await
+            .expect("Verify email failed")
+            .json()
+            .await
+            .expect("Parse verify email");
+        assert_eq!(verify_body["status"], false);
+        assert_eq!(verify_body["error"], "Invalid verification link");
+
+        // Admin override
+        let admin_verify_url = format!("{}{}/user/verify", BASE_URL, API_PREFIX);
+        let forbidden_resp = user_client
+            .post(&admin_verify_url)
+            .json(&json!({ "id": user_id, "verified": true }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(forbidden_resp.status(), StatusCode::FORBIDDEN);
+        let admin_verify_body: Value = admin_client
+            .post(&admin_verify_url)
+            .json(&json!({ "id": user_id, "verified": true }))
+            .send()
+            .await
+            .expect("Verify user failed")
+            .json()
+            .await
+            .expect("Parse verify user");
+        assert_eq!(admin_verify_body["status"], true);
+
+        let list_url = format!("{}{}/user/view_all?limit=50&offset=0", BASE_URL, API_PREFIX);
+        let list_body: Value = admin_client
+            .get(&list_url)
+            .send()
+            .await
+            .expect("List users failed")
+            .json()
+            .await
+            .expect("Parse user list");
+        let user = list_body["data"]
+            .as_array()
+            .and_then(|arr| arr.iter().find(|u| u["id"] == user_id.as_str()))
+            .expect("Temp user not found");
+        assert_eq!(user["email_verified"], true);
+
+        let resend_url = format!("{}{}/user/verify_email/resend", BASE_URL, API_PREFIX);
+        let resend_body: Value = user_client
+            .post(&resend_url)
+            .send()
+            .await
+            .expect("Resend failed")
+            .json()
+            .await
+            .expect("Parse resend");
+        assert_eq!(resend_body["error"], "Email is already verified");
+
+        // A new email must be verified again
+        let edit_url = format!("{}{}/user/edit", BASE_URL, API_PREFIX);
+        let edit_body: Value = user_client
+            .post(&edit_url)
+            .json(&json!({
+                "nickname": "ApiVerifyUser",
+                "say": null,
+                "email": format!("{}-new@example.com", account)
+            }))
+            .send()
+            .await
+            .expect("Edit user failed")
+            .json()
+            .await
+            .expect("Parse edit user");
+        assert_eq!(edit_body["status"], true);
+        let view_body: Value = user_client
+            .get(&view_url)
+            .send()
+            .await
+            .expect("View user failed")
+            .json()
+            .await
+            .expect("Parse view user");
+        assert_eq!(view_body["data"]["email_verified"], false);
+
+        let delete_url = format!("{}{}/user/delete/{}", BASE_URL, API_PREFIX, user_id);
+        let delete_resp = admin_client
+            .post(&delete_url)
+            .send()
+            .await
+            .expect("Delete user failed");
+        assert_eq!(delete_resp.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     #[ignore = "requires running server and valid admin account"]
     async fn test_uuid_path_constraints_for_admin_delete_endpoints() {